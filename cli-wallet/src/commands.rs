@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use colored::Colorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use wallet::ActWallet;
 use types::{ActAmount, Transaction, TransactionType};
 
@@ -155,7 +155,7 @@ pub async fn send_transaction(
     wallet_path: &PathBuf,
     to: &str,
     amount_str: &str,
-    gas_limit: u64,
+    gas_limit: Option<u64>,
     gas_price_str: &str,
 ) -> Result<()> {
     let password = rpassword::prompt_password("Enter wallet password: ")?;
@@ -174,6 +174,15 @@ pub async fn send_transaction(
     // Get nonce
     let nonce = client.get_nonce(&wallet.address().to_string()).await?;
     
+    // Estimate gas unless a limit was given
+    let gas_limit = match gas_limit {
+        Some(gas_limit) => gas_limit,
+        None => {
+            let draft = wallet.create_transfer(to, amount, nonce, 0, gas_price)?;
+            client.estimate_gas(&draft).await?
+        }
+    };
+    
     println!();
     println!("{}", "📤 Sending ACT Transaction".bold().cyan());
     println!();
@@ -221,7 +230,7 @@ pub async fn deploy_contract(
     wallet_path: &PathBuf,
     wasm_path: &PathBuf,
     value_str: &str,
-    gas_limit: u64,
+    gas_limit: Option<u64>,
     gas_price_str: &str,
 ) -> Result<()> {
     let password = rpassword::prompt_password("Enter wallet password: ")?;
@@ -232,6 +241,7 @@ pub async fn deploy_contract(
     
     // Read WASM file
     let wasm_code = std::fs::read(wasm_path)?;
+    let code_size = wasm_code.len();
     
     // Parse value
     let value_act: f64 = value_str.parse()?;
@@ -243,14 +253,36 @@ pub async fn deploy_contract(
     // Get nonce
     let nonce = client.get_nonce(&wallet.address().to_string()).await?;
     
+    // Create transaction
+    let tx_type = TransactionType::ContractDeploy {
+        code: wasm_code,
+        init_data: vec![], // Empty init data for now
+    };
+    
+    let mut tx = Transaction {
+        from: wallet.address().clone(),
+        nonce,
+        tx_type,
+        gas_limit: 0,
+        gas_price,
+        signature: vec![],
+        pubkey: vec![],
+    };
+    
+    // Estimate gas unless a limit was given
+    tx.gas_limit = match gas_limit {
+        Some(gas_limit) => gas_limit,
+        None => client.estimate_gas(&tx).await?,
+    };
+    
     println!();
     println!("{}", "📦 Deploying WASM Contract".bold().cyan());
     println!();
     println!("From:      {}", wallet.address().to_string().cyan());
     println!("WASM File: {}", wasm_path.display());
-    println!("Size:      {} bytes", wasm_code.len());
+    println!("Size:      {} bytes", code_size);
     println!("Value:     {} ACT", format!("{:.6}", value_act).bright_green());
-    println!("Gas Limit: {}", gas_limit);
+    println!("Gas Limit: {}", tx.gas_limit);
     println!("Gas Price: {}", gas_price);
     println!("Nonce:     {}", nonce);
     println!();
@@ -267,22 +299,6 @@ pub async fn deploy_contract(
         return Ok(());
     }
     
-    // Create transaction
-    let tx_type = TransactionType::ContractDeploy {
-        code: wasm_code,
-        init_data: vec![], // Empty init data for now
-    };
-    
-    let mut tx = Transaction {
-        from: wallet.address().clone(),
-        nonce,
-        tx_type,
-        gas_limit,
-        gas_price,
-        signature: vec![],
-        pubkey: vec![],
-    };
-    
     // Sign transaction
     tx = wallet.sign_transaction(tx)?;
     
@@ -302,9 +318,123 @@ pub async fn deploy_contract(
     Ok(())
 }
 
+/// Sign and send a staking or governance action, after showing `details` for confirmation
+pub async fn send_action(
+    rpc_url: &str,
+    wallet_path: &Path,
+    title: &str,
+    tx_type: TransactionType,
+    details: &[(&str, String)],
+    gas_limit: Option<u64>,
+    gas_price_str: &str,
+) -> Result<()> {
+    let password = rpassword::prompt_password("Enter wallet password: ")?;
+    let storage = WalletStorage::load(wallet_path, &password)?;
+    let wallet = &storage.wallet;
+    
+    let client = RpcClient::new(rpc_url.to_string());
+    
+    // Parse gas price
+    let gas_price: u128 = gas_price_str.parse()?;
+    
+    // Get nonce
+    let nonce = client.get_nonce(&wallet.address().to_string()).await?;
+    
+    // Estimate gas unless a limit was given
+    let gas_limit = match gas_limit {
+        Some(gas_limit) => gas_limit,
+        None => {
+            let draft = wallet.create_transaction(tx_type.clone(), nonce, 0, gas_price)?;
+            client.estimate_gas(&draft).await?
+        }
+    };
+    
+    println!();
+    println!("{}", title.bold().cyan());
+    println!();
+    println!("From:      {}", wallet.address().to_string().cyan());
+    for (label, value) in details {
+        println!("{:<10} {}", format!("{}:", label), value);
+    }
+    println!("Gas Limit: {}", gas_limit);
+    println!("Gas Price: {}", gas_price);
+    println!("Nonce:     {}", nonce);
+    println!();
+    
+    // Confirm
+    let mut confirm = String::new();
+    print!("Confirm transaction? (yes/no): ");
+    use std::io::Write;
+    std::io::stdout().flush()?;
+    std::io::stdin().read_line(&mut confirm)?;
+    
+    if !confirm.trim().eq_ignore_ascii_case("yes") {
+        println!("Transaction cancelled.");
+        return Ok(());
+    }
+    
+    // Create and sign transaction
+    let tx = wallet.create_transaction(tx_type, nonce, gas_limit, gas_price)?;
+    
+    // Send transaction
+    println!();
+    println!("{}", "⏳ Broadcasting transaction...".yellow());
+    
+    let tx_hash = client.send_transaction(&tx).await?;
+    
+    println!();
+    println!("{}", "✅ Transaction sent successfully!".bold().green());
+    println!();
+    println!("Transaction hash: {}", tx_hash.bright_cyan());
+    println!("{}", "The action takes effect once the transaction is included; check its receipt status.".dimmed());
+    println!();
+    
+    Ok(())
+}
+
+/// Parse a decimal ACT amount exactly into smallest units. Staked amounts must be whole
+/// multiples of 10^-9 ACT, which a float conversion does not guarantee.
+pub fn parse_act_amount(amount: &str) -> Result<ActAmount> {
+    let invalid = || anyhow!("Invalid ACT amount '{}'", amount);
+    let (whole, fraction) = amount.trim().split_once('.').unwrap_or((amount.trim(), ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > types::ACT_DECIMALS as usize
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    
+    let unit = 10u128.pow(types::ACT_DECIMALS);
+    let whole: ActAmount = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let fraction: ActAmount = format!("{:0<width$}", fraction, width = types::ACT_DECIMALS as usize)
+        .parse()
+        .map_err(|_| invalid())?;
+    
+    whole
+        .checked_mul(unit)
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
 fn get_wallet_path(name: &str) -> Result<PathBuf> {
     let home = dirs::home_dir().ok_or_else(|| anyhow!("Cannot find home directory"))?;
     let wallet_dir = home.join(".act-wallet");
     std::fs::create_dir_all(&wallet_dir)?;
     Ok(wallet_dir.join(format!("{}.json", name)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_act_amount() {
+        assert_eq!(parse_act_amount("100000").unwrap(), 100_000 * 10u128.pow(18));
+        assert_eq!(parse_act_amount("1.5").unwrap(), 15 * 10u128.pow(17));
+        assert_eq!(parse_act_amount(".000000001").unwrap(), 1_000_000_000);
+        assert!(parse_act_amount("1.0000000000000000001").is_err());
+        assert!(parse_act_amount("-1").is_err());
+        assert!(parse_act_amount("1e5").is_err());
+        assert!(parse_act_amount(".").is_err());
+    }
+}
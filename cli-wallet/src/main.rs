@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use std::path::PathBuf;
 
@@ -7,7 +7,8 @@ mod commands;
 mod rpc_client;
 mod wallet_storage;
 
-use commands::{create_wallet, import_wallet, get_balance, send_transaction, deploy_contract};
+use commands::{create_wallet, import_wallet, get_balance, send_transaction, deploy_contract, send_action, parse_act_amount};
+use types::TransactionType;
 
 #[derive(Parser)]
 #[command(name = "act-wallet")]
@@ -59,9 +60,9 @@ enum Commands {
         #[arg(short, long)]
         amount: String,
         
-        /// Gas limit (default: 21000)
-        #[arg(short, long, default_value = "21000")]
-        gas_limit: u64,
+        /// Gas limit (default: estimated by the node)
+        #[arg(short, long)]
+        gas_limit: Option<u64>,
         
         /// Gas price in smallest units (default: 1000000000)
         #[arg(short = 'p', long, default_value = "1000000000")]
@@ -78,15 +79,121 @@ enum Commands {
         #[arg(short, long, default_value = "0")]
         value: String,
         
-        /// Gas limit (default: 1000000)
-        #[arg(short, long, default_value = "1000000")]
-        gas_limit: u64,
+        /// Gas limit (default: estimated by the node)
+        #[arg(short, long)]
+        gas_limit: Option<u64>,
         
         /// Gas price in smallest units (default: 1000000000)
         #[arg(short = 'p', long, default_value = "1000000000")]
         gas_price: String,
     },
     
+    /// Stake ACT to run a validator, or add to an existing stake
+    Stake {
+        /// Amount in ACT
+        #[arg(short, long)]
+        amount: String,
+        
+        /// Commission taken from delegators' rewards, in percent
+        #[arg(short, long, default_value = "10")]
+        commission: u8,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Start unbonding validator stake
+    Unstake {
+        /// Amount in ACT
+        #[arg(short, long)]
+        amount: String,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Delegate ACT to a validator
+    Delegate {
+        /// Validator address (ACT-...)
+        #[arg(short, long)]
+        validator: String,
+        
+        /// Amount in ACT
+        #[arg(short, long)]
+        amount: String,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Start unbonding stake delegated to a validator
+    Undelegate {
+        /// Validator address (ACT-...)
+        #[arg(short, long)]
+        validator: String,
+        
+        /// Amount in ACT
+        #[arg(short, long)]
+        amount: String,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Withdraw stake whose unbonding period has passed, or staking rewards
+    Claim {
+        /// Claim accumulated rewards instead of unbonded stake
+        #[arg(long)]
+        rewards: bool,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Return your validator to the active set once its downtime jail period is over
+    Unjail {
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Submit a text governance proposal
+    Propose {
+        /// Proposal title (1-100 characters)
+        #[arg(short, long)]
+        title: String,
+        
+        /// Proposal text (1-5000 characters)
+        #[arg(short, long)]
+        description: String,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Vote on an active governance proposal
+    Vote {
+        /// Proposal id
+        #[arg(short = 'i', long)]
+        proposal: u64,
+        
+        /// Vote option
+        #[arg(short, long, value_enum)]
+        option: VoteChoice,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
+    /// Report a validator that signed conflicting consensus messages, to have it slashed
+    ReportEvidence {
+        /// Evidence JSON file, as saved by the node that caught it
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        #[command(flatten)]
+        fee: FeeArgs,
+    },
+    
     /// List all wallets
     List,
     
@@ -94,6 +201,35 @@ enum Commands {
     Export,
 }
 
+/// Gas settings shared by staking and governance commands
+#[derive(Args)]
+struct FeeArgs {
+    /// Gas limit (default: estimated by the node)
+    #[arg(short, long)]
+    gas_limit: Option<u64>,
+    
+    /// Gas price in smallest units (default: 1000000000)
+    #[arg(short = 'p', long, default_value = "1000000000")]
+    gas_price: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum VoteChoice {
+    Yes,
+    No,
+    Abstain,
+}
+
+impl From<VoteChoice> for governance::VoteOption {
+    fn from(choice: VoteChoice) -> Self {
+        match choice {
+            VoteChoice::Yes => governance::VoteOption::Yes,
+            VoteChoice::No => governance::VoteOption::No,
+            VoteChoice::Abstain => governance::VoteOption::Abstain,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -126,6 +262,68 @@ async fn main() -> Result<()> {
             deploy_contract(&cli.rpc, &wallet_path, &wasm, &value, gas_limit, &gas_price).await?;
         }
         
+        Commands::Stake { amount, commission, fee } => {
+            let tx_type = TransactionType::Stake { amount: parse_act_amount(&amount)?, commission_rate: commission };
+            let details = [("Amount", format!("{} ACT", amount)), ("Commission", format!("{}%", commission))];
+            send_action(&cli.rpc, &wallet_path, "💎 Staking ACT", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Unstake { amount, fee } => {
+            let tx_type = TransactionType::Unstake { amount: parse_act_amount(&amount)? };
+            let details = [("Amount", format!("{} ACT", amount))];
+            send_action(&cli.rpc, &wallet_path, "🔓 Unstaking ACT", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Delegate { validator, amount, fee } => {
+            let details = [("Validator", validator.clone()), ("Amount", format!("{} ACT", amount))];
+            let tx_type = TransactionType::Delegate { validator, amount: parse_act_amount(&amount)? };
+            send_action(&cli.rpc, &wallet_path, "🤝 Delegating ACT", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Undelegate { validator, amount, fee } => {
+            let details = [("Validator", validator.clone()), ("Amount", format!("{} ACT", amount))];
+            let tx_type = TransactionType::Undelegate { validator, amount: parse_act_amount(&amount)? };
+            send_action(&cli.rpc, &wallet_path, "🔓 Undelegating ACT", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Claim { rewards, fee } => {
+            let (title, tx_type) = if rewards {
+                ("💰 Claiming staking rewards", TransactionType::ClaimRewards)
+            } else {
+                ("💰 Claiming unbonded stake", TransactionType::ClaimUnstaked)
+            };
+            send_action(&cli.rpc, &wallet_path, title, tx_type, &[], fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Unjail { fee } => {
+            send_action(&cli.rpc, &wallet_path, "🔓 Unjailing validator", TransactionType::Unjail, &[], fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Propose { title, description, fee } => {
+            let details = [("Title", title.clone())];
+            let tx_type = TransactionType::Propose {
+                proposal_type: governance::ProposalType::TextProposal { content: description.clone() },
+                title,
+                description,
+            };
+            send_action(&cli.rpc, &wallet_path, "🏛️  Submitting proposal", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::Vote { proposal, option, fee } => {
+            let option = governance::VoteOption::from(option);
+            let details = [("Proposal", proposal.to_string()), ("Vote", format!("{:?}", option))];
+            let tx_type = TransactionType::Vote { proposal_id: proposal, option };
+            send_action(&cli.rpc, &wallet_path, "🗳️  Voting", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
+        Commands::ReportEvidence { file, fee } => {
+            let evidence: types::consensus::Evidence = serde_json::from_slice(&std::fs::read(&file)?)?;
+            evidence.verify().map_err(|e| anyhow::anyhow!("Invalid evidence: {}", e))?;
+            let details = [("Validator", evidence.validator().to_string()), ("Height", evidence.height().to_string())];
+            let tx_type = TransactionType::ReportEvidence { evidence };
+            send_action(&cli.rpc, &wallet_path, "🚨 Reporting double signing", tx_type, &details, fee.gas_limit, &fee.gas_price).await?;
+        }
+        
         Commands::List => {
             list_wallets().await?;
         }
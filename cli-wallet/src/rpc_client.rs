@@ -31,6 +31,8 @@ struct JsonRpcResponse {
 struct JsonRpcError {
     code: i32,
     message: String,
+    #[serde(default)]
+    data: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,7 +87,10 @@ impl RpcClient {
         let rpc_response: JsonRpcResponse = response.json().await?;
         
         if let Some(error) = rpc_response.error {
-            return Err(anyhow!("RPC error {}: {}", error.code, error.message));
+            return Err(match error.data {
+                Some(data) => anyhow!("RPC error {}: {} ({})", error.code, error.message, data),
+                None => anyhow!("RPC error {}: {}", error.code, error.message),
+            });
         }
         
         rpc_response.result.ok_or_else(|| anyhow!("No result in RPC response"))
@@ -119,6 +124,13 @@ impl RpcClient {
         Ok(tx_hash)
     }
     
+    /// Estimate the gas limit for a transaction by dry-running it on the node
+    pub async fn estimate_gas(&self, tx: &types::Transaction) -> Result<u64> {
+        let result = self.call("act_estimateGas", json!([tx])).await?;
+        let gas: u64 = serde_json::from_value(result)?;
+        Ok(gas)
+    }
+    
     /// Get transaction by hash
     pub async fn get_transaction(&self, hash: &str) -> Result<Option<types::Transaction>> {
         let result = self.call("act_getTransaction", json!([hash])).await?;
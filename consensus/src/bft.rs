@@ -0,0 +1,829 @@
+use crypto::{ActAddress, ActKeyPair};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+use types::consensus::{proposal_signing_data, CommitCertificate, Evidence, SignedProposal, Vote, VoteType};
+use types::Block;
+
+use crate::evidence::SignedMessageLog;
+use crate::{select_proposer, Validator};
+
+/// How long round 0 waits in each step; every later round waits `TIMEOUT_DELTA` more
+pub const TIMEOUT_PROPOSE: Duration = Duration::from_secs(10);
+pub const TIMEOUT_PREVOTE: Duration = Duration::from_secs(5);
+pub const TIMEOUT_PRECOMMIT: Duration = Duration::from_secs(5);
+pub const TIMEOUT_DELTA: Duration = Duration::from_secs(2);
+
+/// A block offered for `height` by the proposer of `round`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Proposal {
+    pub height: u64,
+    pub round: u32,
+    pub block: Block,
+    pub pol_round: Option<u32>,  // Earlier round in which this block got 2/3+ prevotes, when re-proposed
+    pub validator: ActAddress,
+    pub pubkey: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl Proposal {
+    pub fn new(keypair: &ActKeyPair, round: u32, block: Block, pol_round: Option<u32>) -> Self {
+        let mut proposal = Self {
+            height: block.header.height,
+            round,
+            block,
+            pol_round,
+            validator: keypair.address().clone(),
+            pubkey: keypair.public_key(),
+            signature: Vec::new(),
+        };
+        proposal.signature = keypair.sign(&proposal.signing_data());
+        proposal
+    }
+
+    /// Bytes covered by the signature; the block is covered through its hash
+    pub fn signing_data(&self) -> Vec<u8> {
+        proposal_signing_data(self.height, self.round, &self.block.hash(), self.pol_round)
+    }
+
+    /// The signed fields without the block, as kept for evidence
+    pub fn signed(&self) -> SignedProposal {
+        SignedProposal {
+            height: self.height,
+            round: self.round,
+            block_hash: self.block.hash(),
+            pol_round: self.pol_round,
+            validator: self.validator.clone(),
+            pubkey: self.pubkey.clone(),
+            signature: self.signature.clone(),
+        }
+    }
+
+    pub fn verify_signature(&self) -> bool {
+        if ActAddress::from_pubkey(&self.pubkey) != self.validator {
+            return false;
+        }
+        crypto::verify_signature(&self.pubkey, &self.signing_data(), &self.signature).unwrap_or(false)
+    }
+}
+
+/// Message gossiped between validators on the consensus topic
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ConsensusMessage {
+    Proposal(Box<Proposal>),
+    Vote(Vote),
+}
+
+impl ConsensusMessage {
+    pub fn height(&self) -> u64 {
+        match self {
+            ConsensusMessage::Proposal(proposal) => proposal.height,
+            ConsensusMessage::Vote(vote) => vote.height,
+        }
+    }
+}
+
+/// Validators voting on one height, weighted by stake
+#[derive(Debug, Clone, Default)]
+pub struct ValidatorSet {
+    validators: BTreeMap<String, Validator>,
+    total_stake: u128,
+}
+
+impl ValidatorSet {
+    /// Active validators with stake; the rest have no say
+    pub fn new(validators: impl IntoIterator<Item = Validator>) -> Self {
+        let validators: BTreeMap<String, Validator> = validators
+            .into_iter()
+            .filter(|v| v.active && v.stake > 0)
+            .map(|v| (v.pubkey.clone(), v))
+            .collect();
+        let total_stake = validators.values().map(|v| v.stake as u128).sum();
+        Self { validators, total_stake }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    pub fn total_stake(&self) -> u128 {
+        self.total_stake
+    }
+
+    /// Voting power of `address`, zero for anyone outside the set
+    pub fn power(&self, address: &str) -> u128 {
+        self.validators.get(address).map_or(0, |v| v.stake as u128)
+    }
+
+    /// More than two thirds of the stake
+    pub fn is_quorum(&self, power: u128) -> bool {
+        power * 3 > self.total_stake * 2
+    }
+
+    /// More than one third of the stake: at least one honest validator among them
+    pub fn exceeds_one_third(&self, power: u128) -> bool {
+        power * 3 > self.total_stake
+    }
+
+    /// Proposer of `round` for the block after `parent_hash`. Round 0 uses the parent hash
+    /// as the seed, later rounds mix in the round number so a silent leader is replaced.
+    pub fn proposer(&self, parent_hash: &str, round: u32) -> Option<&Validator> {
+        if round == 0 {
+            return select_proposer(self.validators.values(), parent_hash);
+        }
+        select_proposer(self.validators.values(), &format!("{}/{}", parent_hash, round))
+    }
+}
+
+/// Check that `certificate` proves `block_hash` final: valid, distinct precommits for it
+/// from more than 2/3 of the stake of `validators`
+pub fn verify_commit(certificate: &CommitCertificate, block_hash: &str, validators: &ValidatorSet) -> Result<(), String> {
+    if certificate.block_hash != block_hash {
+        return Err(format!("Certificate is for block {}, not {}", certificate.block_hash, block_hash));
+    }
+
+    let mut signers = HashSet::new();
+    let mut power = 0u128;
+    for vote in &certificate.precommits {
+        if vote.vote_type != VoteType::Precommit
+            || vote.height != certificate.height
+            || vote.round != certificate.round
+            || vote.block_hash.as_deref() != Some(block_hash)
+        {
+            return Err(format!("Vote from {} does not match the certificate", vote.validator));
+        }
+        if !vote.verify_signature() {
+            return Err(format!("Invalid precommit signature from {}", vote.validator));
+        }
+        let validator = vote.validator.to_string();
+        if !signers.insert(validator.clone()) {
+            return Err(format!("Duplicate precommit from {}", validator));
+        }
+        power += validators.power(&validator);
+    }
+
+    if !validators.is_quorum(power) {
+        return Err(format!("Precommits carry {} of {} stake, more than 2/3 needed", power, validators.total_stake()));
+    }
+    Ok(())
+}
+
+/// Where a round has got to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+    Commit,
+}
+
+/// A timeout to deliver back through `Tendermint::on_timeout` once it expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    pub height: u64,
+    pub round: u32,
+    pub step: Step,
+}
+
+/// What the node must do in response to an input
+#[derive(Debug)]
+pub enum Action {
+    /// Gossip to the other validators
+    Broadcast(ConsensusMessage),
+    /// We propose this round: build a block on top of the parent and pass it to `propose`
+    BuildBlock { height: u64, round: u32 },
+    ScheduleTimeout(Timeout, Duration),
+    /// The block is final; apply it and store it with its certificate
+    Commit { block: Box<Block>, certificate: CommitCertificate },
+    /// A validator signed two conflicting messages; report it so it gets slashed
+    Evidence(Box<Evidence>),
+}
+
+/// Votes of one type in one round, at most one per validator
+#[derive(Debug, Default)]
+struct VoteSet {
+    votes: HashMap<String, Vote>,
+    power: u128,  // Of all votes, whatever they are for
+    power_for: HashMap<Option<String>, u128>,
+}
+
+impl VoteSet {
+    /// Record a vote; a second vote from the same validator is ignored
+    fn add(&mut self, vote: Vote, power: u128) -> bool {
+        let validator = vote.validator.to_string();
+        if self.votes.contains_key(&validator) {
+            return false;
+        }
+        self.power += power;
+        *self.power_for.entry(vote.block_hash.clone()).or_insert(0) += power;
+        self.votes.insert(validator, vote);
+        true
+    }
+
+    fn power_for(&self, block_hash: &Option<String>) -> u128 {
+        self.power_for.get(block_hash).copied().unwrap_or(0)
+    }
+
+    /// Block, or nil, that more than 2/3 of the stake voted for
+    fn quorum(&self, validators: &ValidatorSet) -> Option<Option<String>> {
+        self.power_for
+            .iter()
+            .find(|(_, power)| validators.is_quorum(**power))
+            .map(|(block_hash, _)| block_hash.clone())
+    }
+
+    fn votes_for(&self, block_hash: &str) -> Vec<Vote> {
+        let mut votes: Vec<Vote> = self
+            .votes
+            .values()
+            .filter(|vote| vote.block_hash.as_deref() == Some(block_hash))
+            .cloned()
+            .collect();
+        votes.sort_by_key(|vote| vote.validator.to_string());
+        votes
+    }
+}
+
+/// Tendermint-style BFT agreement on one block per height.
+///
+/// Each round a stake-weighted proposer offers a block. Validators prevote for it, and
+/// precommit once more than 2/3 of the stake prevoted for it; a block with precommits
+/// from more than 2/3 of the stake is final. Timeouts move a stuck round on to the next,
+/// with a new proposer. A validator that precommitted a block stays locked on it unless a
+/// later round shows 2/3+ prevotes for something else, so two blocks can never both be
+/// final while fewer than 1/3 of the stake misbehaves.
+///
+/// The state machine does no I/O: the node feeds it messages, block validity and expired
+/// timeouts, and carries out the returned actions.
+pub struct Tendermint {
+    keypair: Option<ActKeyPair>,  // None on a node that follows without voting
+    height: u64,
+    round: u32,
+    step: Step,
+    parent_hash: String,
+    validators: ValidatorSet,
+    proposals: HashMap<u32, (Proposal, bool)>,  // Per round, with whether the block is valid
+    prevotes: HashMap<u32, VoteSet>,
+    precommits: HashMap<u32, VoteSet>,
+    locked: Option<(u32, Block)>,
+    valid: Option<(u32, Block)>,  // Latest block seen with 2/3+ prevotes
+    polka_seen: HashSet<u32>,  // Rounds whose 2/3+ prevotes for a block were acted on
+    timeouts_scheduled: HashSet<(u32, Step)>,
+    log: SignedMessageLog,
+}
+
+impl Tendermint {
+    pub fn new(keypair: Option<ActKeyPair>) -> Self {
+        Self {
+            keypair,
+            height: 0,
+            round: 0,
+            step: Step::Commit,  // Idle until the first `start_height`
+            parent_hash: String::new(),
+            validators: ValidatorSet::default(),
+            proposals: HashMap::new(),
+            prevotes: HashMap::new(),
+            precommits: HashMap::new(),
+            locked: None,
+            valid: None,
+            polka_seen: HashSet::new(),
+            timeouts_scheduled: HashSet::new(),
+            log: SignedMessageLog::default(),
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// Start agreeing on the block at `height`, built on `parent_hash`
+    pub fn start_height(&mut self, height: u64, parent_hash: String, validators: ValidatorSet) -> Vec<Action> {
+        self.height = height;
+        self.parent_hash = parent_hash;
+        self.validators = validators;
+        self.proposals.clear();
+        self.prevotes.clear();
+        self.precommits.clear();
+        self.locked = None;
+        self.valid = None;
+        self.polka_seen.clear();
+        self.timeouts_scheduled.clear();
+        self.log = SignedMessageLog::new(height);
+
+        let mut actions = Vec::new();
+        self.start_round(0, &mut actions);
+        self.process(&mut actions);
+        actions
+    }
+
+    /// Cheap checks on a proposal, worth passing before executing its block
+    pub fn check_proposal(&self, proposal: &Proposal) -> Result<(), String> {
+        if proposal.height != self.height || self.step == Step::Commit {
+            return Err(format!("Proposal for height {} while at {}", proposal.height, self.height));
+        }
+        let header = &proposal.block.header;
+        if header.height != self.height || header.parent_hash != self.parent_hash {
+            return Err("Proposed block does not extend our chain".to_string());
+        }
+        let expected = self
+            .validators
+            .proposer(&self.parent_hash, proposal.round)
+            .ok_or("No validators")?;
+        let proposer = proposal.validator.to_string();
+        if expected.pubkey != proposer {
+            return Err(format!("{} is not the proposer of round {}", proposer, proposal.round));
+        }
+        // A re-proposed block keeps the header, and proposer, of the round it was built for
+        let built_for_round = match proposal.pol_round {
+            None => header.round == proposal.round,
+            Some(pol_round) => header.round <= pol_round && pol_round < proposal.round,
+        };
+        if !built_for_round {
+            return Err(format!("Block built for round {} proposed in round {}", header.round, proposal.round));
+        }
+        if !proposal.verify_signature() {
+            return Err("Invalid proposal signature".to_string());
+        }
+        Ok(())
+    }
+
+    /// Our block for the current round, after `Action::BuildBlock`
+    pub fn propose(&mut self, block: Block) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let Some(keypair) = &self.keypair else {
+            return actions;
+        };
+        if self.step != Step::Propose || block.header.height != self.height || self.proposals.contains_key(&self.round) {
+            return actions;
+        }
+
+        let proposal = Proposal::new(keypair, self.round, block, None);
+        actions.push(Action::Broadcast(ConsensusMessage::Proposal(Box::new(proposal.clone()))));
+        self.proposals.insert(self.round, (proposal, true));
+        self.process(&mut actions);
+        actions
+    }
+
+    /// A proposal from the network; `valid` is whether its block executes to the header's roots
+    pub fn on_proposal(&mut self, proposal: Proposal, valid: bool) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if self.check_proposal(&proposal).is_err() {
+            return actions;
+        }
+        if let Some(evidence) = self.log.record_proposal(proposal.signed()) {
+            actions.push(Action::Evidence(Box::new(evidence)));
+        }
+        if self.proposals.contains_key(&proposal.round) {
+            return actions;
+        }
+        self.proposals.insert(proposal.round, (proposal, valid));
+        self.process(&mut actions);
+        actions
+    }
+
+    pub fn on_vote(&mut self, vote: Vote) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if vote.height != self.height || self.step == Step::Commit {
+            return actions;
+        }
+        let power = self.validators.power(&vote.validator.to_string());
+        if power == 0 || !vote.verify_signature() {
+            return actions;
+        }
+        if let Some(evidence) = self.log.record_vote(vote.clone()) {
+            actions.push(Action::Evidence(Box::new(evidence)));
+        }
+        if self.add_vote(vote, power) {
+            self.process(&mut actions);
+        }
+        actions
+    }
+
+    pub fn on_timeout(&mut self, timeout: Timeout) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if timeout.height != self.height || timeout.round != self.round {
+            return actions;
+        }
+
+        match timeout.step {
+            Step::Propose if self.step == Step::Propose => self.cast(VoteType::Prevote, None, &mut actions),
+            Step::Prevote if self.step == Step::Prevote => self.cast(VoteType::Precommit, None, &mut actions),
+            Step::Precommit if self.step != Step::Commit => self.start_round(self.round + 1, &mut actions),
+            _ => return actions,
+        }
+        self.process(&mut actions);
+        actions
+    }
+
+    fn start_round(&mut self, round: u32, actions: &mut Vec<Action>) {
+        self.round = round;
+        self.step = Step::Propose;
+        self.schedule(Step::Propose, actions);
+
+        let proposer = self.validators.proposer(&self.parent_hash, round).map(|v| v.pubkey.clone());
+        let Some(keypair) = &self.keypair else {
+            return;
+        };
+        if proposer.as_deref() != Some(keypair.address().to_string().as_str()) {
+            return;
+        }
+
+        // Re-propose a block that already has 2/3+ prevotes, so validators locked on it can vote
+        match &self.valid {
+            Some((valid_round, block)) => {
+                let proposal = Proposal::new(keypair, round, block.clone(), Some(*valid_round));
+                actions.push(Action::Broadcast(ConsensusMessage::Proposal(Box::new(proposal.clone()))));
+                self.proposals.insert(round, (proposal, true));
+            }
+            None => actions.push(Action::BuildBlock { height: self.height, round }),
+        }
+    }
+
+    fn schedule(&mut self, step: Step, actions: &mut Vec<Action>) {
+        if !self.timeouts_scheduled.insert((self.round, step)) {
+            return;
+        }
+        let base = match step {
+            Step::Propose => TIMEOUT_PROPOSE,
+            Step::Prevote => TIMEOUT_PREVOTE,
+            Step::Precommit | Step::Commit => TIMEOUT_PRECOMMIT,
+        };
+        let timeout = Timeout { height: self.height, round: self.round, step };
+        actions.push(Action::ScheduleTimeout(timeout, base + TIMEOUT_DELTA * self.round));
+    }
+
+    fn add_vote(&mut self, vote: Vote, power: u128) -> bool {
+        let votes = match vote.vote_type {
+            VoteType::Prevote => self.prevotes.entry(vote.round).or_default(),
+            VoteType::Precommit => self.precommits.entry(vote.round).or_default(),
+        };
+        votes.add(vote, power)
+    }
+
+    /// Sign and send our vote for the current round, and move to the matching step
+    fn cast(&mut self, vote_type: VoteType, block_hash: Option<String>, actions: &mut Vec<Action>) {
+        self.step = match vote_type {
+            VoteType::Prevote => Step::Prevote,
+            VoteType::Precommit => Step::Precommit,
+        };
+
+        let Some(keypair) = &self.keypair else {
+            return;
+        };
+        let power = self.validators.power(&keypair.address().to_string());
+        if power == 0 {
+            return;
+        }
+        let vote = Vote::new(keypair, self.height, self.round, vote_type, block_hash);
+        actions.push(Action::Broadcast(ConsensusMessage::Vote(vote.clone())));
+        self.add_vote(vote, power);
+    }
+
+    /// Apply every rule that the messages received so far trigger
+    fn process(&mut self, actions: &mut Vec<Action>) {
+        while self.step != Step::Commit && self.apply_rule(actions) {}
+    }
+
+    /// Apply the first rule that fires; false once none does
+    fn apply_rule(&mut self, actions: &mut Vec<Action>) -> bool {
+        // 2/3+ precommits for a block we have, in any round: it is final
+        let decided = self.precommits.iter().find_map(|(round, votes)| match votes.quorum(&self.validators) {
+            Some(Some(block_hash)) => self.block(&block_hash).map(|block| (*round, block_hash, block.clone())),
+            _ => None,
+        });
+        if let Some((round, block_hash, block)) = decided {
+            let certificate = CommitCertificate {
+                height: self.height,
+                round,
+                precommits: self.precommits[&round].votes_for(&block_hash),
+                block_hash,
+            };
+            self.step = Step::Commit;
+            actions.push(Action::Commit { block: Box::new(block), certificate });
+            return true;
+        }
+
+        // More than 1/3 of the stake has moved on to a later round: catch up
+        if let Some(round) = self.later_round_with_one_third() {
+            self.start_round(round, actions);
+            return true;
+        }
+
+        let round = self.round;
+        let proposal = self
+            .proposals
+            .get(&round)
+            .map(|(proposal, valid)| (proposal.block.hash(), proposal.pol_round, *valid));
+
+        if self.step == Step::Propose {
+            if let Some((block_hash, pol_round, valid)) = proposal {
+                let locked_on = self.locked.as_ref().map(|(round, block)| (*round, block.hash()));
+                let vote = match pol_round {
+                    None => valid && locked_on.as_ref().is_none_or(|(_, locked)| *locked == block_hash),
+                    Some(pol_round) if pol_round < round && self.prevote_quorum_for(pol_round, &block_hash) => {
+                        valid && locked_on.as_ref().is_none_or(|(locked_round, locked)| *locked_round <= pol_round || *locked == block_hash)
+                    }
+                    // Re-proposal whose earlier 2/3+ prevotes we have not seen (yet)
+                    Some(_) => return false,
+                };
+                self.cast(VoteType::Prevote, vote.then_some(block_hash), actions);
+                return true;
+            }
+        }
+
+        let prevotes = self.prevotes.get(&round);
+        let prevote_quorum = prevotes.and_then(|votes| votes.quorum(&self.validators));
+        let any_prevote_quorum = prevotes.is_some_and(|votes| self.validators.is_quorum(votes.power));
+
+        if self.step == Step::Prevote && any_prevote_quorum && !self.timeouts_scheduled.contains(&(round, Step::Prevote)) {
+            self.schedule(Step::Prevote, actions);
+            return true;
+        }
+
+        // 2/3+ prevotes for the valid proposal of this round: lock on it and precommit
+        if let (Some((block_hash, _, true)), Some(Some(quorum_hash))) = (&proposal, &prevote_quorum) {
+            if block_hash == quorum_hash && self.step >= Step::Prevote && self.polka_seen.insert(round) {
+                let block = self.proposals[&round].0.block.clone();
+                if self.step == Step::Prevote {
+                    self.locked = Some((round, block.clone()));
+                    self.cast(VoteType::Precommit, Some(block_hash.clone()), actions);
+                }
+                self.valid = Some((round, block));
+                return true;
+            }
+        }
+
+        if self.step == Step::Prevote && prevote_quorum == Some(None) {
+            self.cast(VoteType::Precommit, None, actions);
+            return true;
+        }
+
+        let any_precommit_quorum = self
+            .precommits
+            .get(&round)
+            .is_some_and(|votes| self.validators.is_quorum(votes.power));
+        if any_precommit_quorum && !self.timeouts_scheduled.contains(&(round, Step::Precommit)) {
+            self.schedule(Step::Precommit, actions);
+            return true;
+        }
+
+        false
+    }
+
+    /// Block with `block_hash` from any round's proposal
+    fn block(&self, block_hash: &str) -> Option<&Block> {
+        self.proposals
+            .values()
+            .map(|(proposal, _)| &proposal.block)
+            .find(|block| block.hash() == block_hash)
+    }
+
+    fn prevote_quorum_for(&self, round: u32, block_hash: &str) -> bool {
+        self.prevotes
+            .get(&round)
+            .is_some_and(|votes| self.validators.is_quorum(votes.power_for(&Some(block_hash.to_string()))))
+    }
+
+    /// Lowest round above ours in which validators holding more than 1/3 of the stake voted
+    fn later_round_with_one_third(&self) -> Option<u32> {
+        let mut rounds: Vec<u32> = self
+            .prevotes
+            .keys()
+            .chain(self.precommits.keys())
+            .copied()
+            .filter(|round| *round > self.round)
+            .collect();
+        rounds.sort_unstable();
+        rounds.dedup();
+
+        rounds.into_iter().find(|round| {
+            let mut voters: HashSet<&String> = HashSet::new();
+            for votes in [self.prevotes.get(round), self.precommits.get(round)].into_iter().flatten() {
+                voters.extend(votes.votes.keys());
+            }
+            let power: u128 = voters.iter().map(|validator| self.validators.power(validator)).sum();
+            self.validators.exceeds_one_third(power)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::BlockHeader;
+
+    fn keypairs(count: u8) -> Vec<ActKeyPair> {
+        (1..=count).map(|seed| ActKeyPair::from_seed(&[seed; 32])).collect()
+    }
+
+    fn validator_set(keypairs: &[ActKeyPair]) -> ValidatorSet {
+        ValidatorSet::new(keypairs.iter().map(|keypair| Validator {
+            pubkey: keypair.address().to_string(),
+            stake: 100,
+            active: true,
+        }))
+    }
+
+    fn block(proposer: &str, height: u64, round: u32, parent_hash: &str) -> Block {
+        let header = BlockHeader {
+            parent_hash: parent_hash.to_string(),
+            tx_root: "0".repeat(64),
+            actor_pubkey: proposer.to_string(),
+            state_root: "0".repeat(64),
+            receipts_root: "0".repeat(64),
+            timestamp: 1_700_000_000,
+            validator_commitment: proposer.to_string(),
+            reward: crate::HEADER_BLOCK_REWARD,
+            height,
+            round,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: "0".repeat(64),
+        };
+        Block::new(header, Vec::new(), Vec::new())
+    }
+
+    /// Validators exchanging messages instantly; `offline` ones neither send nor receive
+    struct Network {
+        nodes: Vec<Tendermint>,
+        offline: HashSet<usize>,
+        timeouts: Vec<Timeout>,
+        commits: Vec<(usize, Block, CommitCertificate)>,
+    }
+
+    impl Network {
+        fn start(keypairs: &[ActKeyPair], offline: &[usize]) -> Self {
+            let mut network = Self {
+                nodes: keypairs.iter().map(|keypair| Tendermint::new(Some(keypair.clone()))).collect(),
+                offline: offline.iter().copied().collect(),
+                timeouts: Vec::new(),
+                commits: Vec::new(),
+            };
+            let validators = validator_set(keypairs);
+            for index in 0..network.nodes.len() {
+                let actions = network.nodes[index].start_height(1, "parent".to_string(), validators.clone());
+                network.run(index, actions);
+            }
+            network
+        }
+
+        fn run(&mut self, from: usize, actions: Vec<Action>) {
+            if self.offline.contains(&from) {
+                return;
+            }
+            for action in actions {
+                match action {
+                    Action::Broadcast(message) => {
+                        for to in 0..self.nodes.len() {
+                            if to == from || self.offline.contains(&to) {
+                                continue;
+                            }
+                            let replies = match message.clone() {
+                                ConsensusMessage::Proposal(proposal) => self.nodes[to].on_proposal(*proposal, true),
+                                ConsensusMessage::Vote(vote) => self.nodes[to].on_vote(vote),
+                            };
+                            self.run(to, replies);
+                        }
+                    }
+                    Action::BuildBlock { height, round } => {
+                        let proposer = self.nodes[from].keypair.as_ref().unwrap().address().to_string();
+                        let actions = self.nodes[from].propose(block(&proposer, height, round, "parent"));
+                        self.run(from, actions);
+                    }
+                    Action::ScheduleTimeout(timeout, _) => self.timeouts.push(timeout),
+                    Action::Commit { block, certificate } => self.commits.push((from, *block, certificate)),
+                    Action::Evidence(_) => {}
+                }
+            }
+        }
+
+        /// Expire every pending timeout of `step`
+        fn expire(&mut self, step: Step) {
+            let expired: Vec<Timeout> = self.timeouts.iter().copied().filter(|t| t.step == step).collect();
+            self.timeouts.retain(|t| t.step != step);
+            for timeout in expired {
+                for index in 0..self.nodes.len() {
+                    let actions = self.nodes[index].on_timeout(timeout);
+                    self.run(index, actions);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_validators_agree_on_one_block() {
+        let keys = keypairs(4);
+        let network = Network::start(&keys, &[]);
+
+        assert_eq!(network.commits.len(), 4);
+        let (_, block, certificate) = &network.commits[0];
+        assert!(network.commits.iter().all(|(_, committed, _)| committed.hash() == block.hash()));
+        assert_eq!(certificate.round, 0);
+        assert!(verify_commit(certificate, &block.hash(), &validator_set(&keys)).is_ok());
+        assert!(network.nodes.iter().all(|node| node.step() == Step::Commit));
+    }
+
+    #[test]
+    fn test_silent_proposer_is_replaced() {
+        let keys = keypairs(4);
+        let validators = validator_set(&keys);
+        let silent = keys
+            .iter()
+            .position(|k| validators.proposer("parent", 0).unwrap().pubkey == k.address().to_string())
+            .unwrap();
+        let mut network = Network::start(&keys, &[silent]);
+        assert!(network.commits.is_empty());
+
+        // Everyone prevotes and precommits nil, then moves on to round 1
+        network.expire(Step::Propose);
+        network.expire(Step::Prevote);
+        network.expire(Step::Precommit);
+
+        let round_one_proposer = validators.proposer("parent", 1).unwrap().pubkey.clone();
+        assert_ne!(round_one_proposer, keys[silent].address().to_string());
+        assert_eq!(network.commits.len(), 3);
+        let (_, block, certificate) = &network.commits[0];
+        assert_eq!(certificate.round, 1);
+        assert_eq!(block.header.validator_commitment, round_one_proposer);
+        assert!(verify_commit(certificate, &block.hash(), &validators).is_ok());
+    }
+
+    #[test]
+    fn test_equivocation_is_reported() {
+        let keys = keypairs(4);
+        let mut node = Tendermint::new(Some(keys[0].clone()));
+        node.start_height(1, "parent".to_string(), validator_set(&keys));
+
+        let first = Vote::new(&keys[1], 1, 0, VoteType::Prevote, Some("ab".repeat(32)));
+        let evidence = |actions: Vec<Action>| actions.into_iter().find_map(|action| match action {
+            Action::Evidence(evidence) => Some(*evidence),
+            _ => None,
+        });
+        assert!(evidence(node.on_vote(first.clone())).is_none());
+        assert!(evidence(node.on_vote(first.clone())).is_none());
+
+        let second = Vote::new(&keys[1], 1, 0, VoteType::Prevote, None);
+        let reported = evidence(node.on_vote(second.clone())).unwrap();
+        assert!(reported.verify().is_ok());
+        assert_eq!(reported, Evidence::ConflictingVotes { first, second });
+
+        // Reported once per height
+        let third = Vote::new(&keys[1], 1, 0, VoteType::Prevote, Some("cd".repeat(32)));
+        assert!(evidence(node.on_vote(third)).is_none());
+
+        // Two blocks proposed for one round
+        let proposer = keys
+            .iter()
+            .find(|k| validator_set(&keys).proposer("parent", 0).unwrap().pubkey == k.address().to_string())
+            .unwrap();
+        let address = proposer.address().to_string();
+        let mut other = block(&address, 1, 0, "parent");
+        other.header.timestamp += 1;
+        assert!(evidence(node.on_proposal(Proposal::new(proposer, 0, block(&address, 1, 0, "parent"), None), true)).is_none());
+        let reported = evidence(node.on_proposal(Proposal::new(proposer, 0, other, None), true)).unwrap();
+        assert!(matches!(reported, Evidence::ConflictingProposals { .. }));
+        assert!(reported.verify().is_ok());
+    }
+
+    #[test]
+    fn test_no_commit_without_quorum() {
+        let keys = keypairs(4);
+        // Two of four validators are only half the stake
+        let mut network = Network::start(&keys, &[2, 3]);
+        network.expire(Step::Propose);
+        network.expire(Step::Prevote);
+        assert!(network.commits.is_empty());
+
+        let validators = validator_set(&keys);
+        let block = block(&keys[0].address().to_string(), 1, 0, "parent");
+        let precommits: Vec<Vote> = keys[..2]
+            .iter()
+            .map(|keypair| Vote::new(keypair, 1, 0, VoteType::Precommit, Some(block.hash())))
+            .collect();
+        let mut certificate = CommitCertificate { height: 1, round: 0, block_hash: block.hash(), precommits };
+        assert!(verify_commit(&certificate, &block.hash(), &validators).is_err());
+
+        // A third signer makes 3/4 of the stake, but not if one signs twice
+        certificate.precommits.push(Vote::new(&keys[2], 1, 0, VoteType::Precommit, Some(block.hash())));
+        assert!(verify_commit(&certificate, &block.hash(), &validators).is_ok());
+        certificate.precommits[2] = certificate.precommits[1].clone();
+        assert!(verify_commit(&certificate, &block.hash(), &validators).is_err());
+
+        let mut forged = Vote::new(&keys[3], 1, 0, VoteType::Precommit, None);
+        forged.block_hash = Some(block.hash());
+        certificate.precommits[2] = forged;
+        assert!(verify_commit(&certificate, &block.hash(), &validators).is_err());
+    }
+}
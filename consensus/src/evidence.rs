@@ -0,0 +1,66 @@
+use std::collections::{HashMap, HashSet};
+use types::consensus::{Evidence, SignedProposal, Vote, VoteType};
+
+/// Proposals and votes signed for one height, the first per validator and step, to catch
+/// a validator that signs a second, different one
+#[derive(Debug, Default)]
+pub struct SignedMessageLog {
+    height: u64,
+    proposals: HashMap<(u32, String), SignedProposal>,
+    votes: HashMap<(u32, VoteType, String), Vote>,
+    reported: HashSet<String>,  // Validators already caught at this height
+}
+
+impl SignedMessageLog {
+    pub fn new(height: u64) -> Self {
+        Self { height, ..Self::default() }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Record a proposal whose signature has been checked. Returns evidence the first time
+    /// its proposer is seen proposing two different blocks in one round.
+    pub fn record_proposal(&mut self, proposal: SignedProposal) -> Option<Evidence> {
+        if proposal.height != self.height {
+            return None;
+        }
+        let key = (proposal.round, proposal.validator.to_string());
+        match self.proposals.get(&key) {
+            None => {
+                self.proposals.insert(key, proposal);
+                None
+            }
+            Some(first) if first.block_hash == proposal.block_hash => None,
+            Some(first) => {
+                let evidence = Evidence::ConflictingProposals { first: first.clone(), second: proposal };
+                self.report(evidence)
+            }
+        }
+    }
+
+    /// Record a vote whose signature has been checked. Returns evidence the first time its
+    /// validator is seen voting for two different blocks, or a block and nil, in one step.
+    pub fn record_vote(&mut self, vote: Vote) -> Option<Evidence> {
+        if vote.height != self.height {
+            return None;
+        }
+        let key = (vote.round, vote.vote_type, vote.validator.to_string());
+        match self.votes.get(&key) {
+            None => {
+                self.votes.insert(key, vote);
+                None
+            }
+            Some(first) if first.block_hash == vote.block_hash => None,
+            Some(first) => {
+                let evidence = Evidence::ConflictingVotes { first: first.clone(), second: vote };
+                self.report(evidence)
+            }
+        }
+    }
+
+    fn report(&mut self, evidence: Evidence) -> Option<Evidence> {
+        self.reported.insert(evidence.validator().to_string()).then_some(evidence)
+    }
+}
@@ -1,11 +1,28 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
-use types::{Action, BlockHeader};
-use runtime::execute_action_block;
-use storage::store_block;
+use crypto::ActKeyPair;
+use types::consensus::CommitCertificate;
+use types::{ActAmount, Block, BlockHeader, Transaction, TransactionReceipt};
+use staking::StakingManager;
+use storage::BlockchainStorage;
+
+pub mod bft;
+pub mod evidence;
+
+pub use bft::{Action, ConsensusMessage, Proposal, Step, Tendermint, Timeout, ValidatorSet};
+
+/// Blocks per epoch, defined by staking so its liveness tracking follows the same sets
+pub use staking::EPOCH_LENGTH;
+
+/// How far a block timestamp may run ahead of our clock
+pub const MAX_CLOCK_DRIFT_SECS: u64 = 15;
+
+/// Reward a block header records: staking's `BLOCK_REWARD` (9 decimals) in balance units (18)
+pub const HEADER_BLOCK_REWARD: ActAmount = staking::BLOCK_REWARD as ActAmount * 1_000_000_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Validator {
@@ -16,19 +33,25 @@ pub struct Validator {
 
 #[derive(Debug, Clone)]
 pub struct ConsensusState {
-    pub validators: HashMap<String, Validator>,
-    pub current_proposer_index: usize,
+    pub validators: BTreeMap<String, Validator>,  // Ordered, so every node walks them alike
+    pub epoch: u64,  // Epoch the validator set was loaded for
+    pub last_validators: BTreeMap<String, Validator>,  // Set that finalized the tip block
     pub block_height: u64,
     pub finalized_height: u64,
+    pub last_block_hash: String,
+    pub last_block_timestamp: u64,
 }
 
 impl ConsensusState {
     pub fn new() -> Self {
         Self {
-            validators: HashMap::new(),
-            current_proposer_index: 0,
+            validators: BTreeMap::new(),
+            epoch: 0,
+            last_validators: BTreeMap::new(),
             block_height: 0,
             finalized_height: 0,
+            last_block_hash: "0".repeat(64),
+            last_block_timestamp: 0,
         }
     }
 
@@ -43,23 +66,33 @@ impl ConsensusState {
         );
     }
 
-    pub fn get_current_proposer(&self) -> Option<&Validator> {
-        let active_validators: Vec<&Validator> = self
-            .validators
-            .values()
-            .filter(|v| v.active)
+    /// Replace the validator set with staking's active validators, weighted by their
+    /// own and delegated stake
+    pub fn load_validators(&mut self, validators: &[staking::Validator], epoch: u64) {
+        self.validators = validators
+            .iter()
+            .filter(|v| v.can_validate())
+            .map(|v| {
+                let validator = Validator {
+                    pubkey: v.address.clone(),
+                    stake: v.total_stake(),
+                    active: true,
+                };
+                (v.address.clone(), validator)
+            })
             .collect();
-        
-        if active_validators.is_empty() {
-            return None;
-        }
-        
-        let index = self.current_proposer_index % active_validators.len();
-        Some(active_validators[index])
+        self.epoch = epoch;
+    }
+
+    /// Replace the validator set with the one staking recorded for `epoch`: address -> stake
+    pub fn load_epoch_validators(&mut self, validators: &BTreeMap<String, u64>, epoch: u64) {
+        self.validators = validator_map(validators);
+        self.epoch = epoch;
     }
 
-    pub fn rotate_proposer(&mut self) {
-        self.current_proposer_index += 1;
+    /// Validator chosen to propose the block after `last_block_hash`
+    pub fn get_current_proposer(&self) -> Option<&Validator> {
+        select_proposer(self.validators.values(), &self.last_block_hash)
     }
 
     pub fn increment_height(&mut self) {
@@ -67,123 +100,462 @@ impl ConsensusState {
     }
 }
 
+/// Validator set with the given stakes
+fn validator_map(stakes: &BTreeMap<String, u64>) -> BTreeMap<String, Validator> {
+    stakes
+        .iter()
+        .map(|(address, stake)| (address.clone(), Validator { pubkey: address.clone(), stake: *stake, active: true }))
+        .collect()
+}
+
+/// Epoch a block height falls in
+pub fn epoch_of(height: u64) -> u64 {
+    height / EPOCH_LENGTH
+}
+
+/// Pick the proposer of the block following the block with hash `parent_hash`.
+///
+/// Each active validator wins with probability proportional to its stake. The draw is
+/// seeded from the parent hash, so every node with the same validator set picks the
+/// same proposer without communicating.
+pub fn select_proposer<'a>(
+    validators: impl IntoIterator<Item = &'a Validator>,
+    parent_hash: &str,
+) -> Option<&'a Validator> {
+    let candidates: Vec<&Validator> = validators
+        .into_iter()
+        .filter(|v| v.active && v.stake > 0)
+        .collect();
+    let total_stake: u128 = candidates.iter().map(|v| v.stake as u128).sum();
+    if total_stake == 0 {
+        return None;
+    }
+
+    let seed = Sha256::digest(parent_hash.as_bytes());
+    let mut target = u128::from_be_bytes(seed[..16].try_into().unwrap()) % total_stake;
+    for validator in candidates {
+        let stake = validator.stake as u128;
+        if target < stake {
+            return Some(validator);
+        }
+        target -= stake;
+    }
+    None
+}
+
 pub struct ConsensusEngine {
     state: Arc<RwLock<ConsensusState>>,
+    storage: Arc<BlockchainStorage>,
 }
 
 impl ConsensusEngine {
-    pub fn new() -> Self {
+    /// Engine with an empty validator set; load one with `update_validator_set` before proposing
+    pub fn new(storage: Arc<BlockchainStorage>) -> Self {
         let mut state = ConsensusState::new();
         
-        // Initialize with some default validators for testing
-        state.add_validator("validator1".to_string(), 1000);
-        state.add_validator("validator2".to_string(), 1000);
-        state.add_validator("validator3".to_string(), 1000);
+        // Resume from the chain tip if blocks were stored by a previous run
+        if let Ok(Some(latest_height)) = storage.get_latest_height() {
+            if let Ok(Some(block)) = storage.get_block(latest_height) {
+                state.block_height = latest_height + 1;
+                state.finalized_height = latest_height;
+                state.last_block_hash = block.hash();
+                state.last_block_timestamp = block.header.timestamp;
+            }
+        }
         
         Self {
             state: Arc::new(RwLock::new(state)),
+            storage,
         }
     }
 
-    pub async fn propose_block(&self, action: Action) -> Result<BlockHeader, String> {
-        let mut state = self.state.write().await;
+    /// Build and sign the next block on the current tip from already-executed transactions,
+    /// carrying `last_commit`, the tip's certificate from `get_last_commit`.
+    /// The block is only a candidate: it is stored by `commit_block` once validators agree on it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build_block(
+        &self,
+        keypair: &ActKeyPair,
+        round: u32,
+        transactions: Vec<Transaction>,
+        receipts: Vec<TransactionReceipt>,
+        state_root: String,
+        timestamp: u64,
+        last_commit: Option<CommitCertificate>,
+    ) -> Result<Block, String> {
+        let state = self.state.read().await;
         
-        let proposer = state
-            .get_current_proposer()
-            .ok_or("No active validators")?;
-        
-        // Execute the action using WASM runtime
-        match execute_action_block(&action) {
-            Ok(exec_result) => {
-                println!(
-                    "✅ Action executed: success={}, gas={}, state_changes={}",
-                    exec_result.success,
-                    exec_result.gas_used,
-                    exec_result.state_changes.len()
-                );
-                for log in &exec_result.logs {
-                    println!("  📝 {}", log);
-                }
-            }
-            Err(e) => {
-                println!("⚠️  Action execution failed: {}", e);
-            }
+        if timestamp <= state.last_block_timestamp {
+            return Err(format!("Timestamp {} is not after the parent's {}", timestamp, state.last_block_timestamp));
         }
         
-        // Calculate action hash
-        let action_data = serde_json::to_string(&action)
-            .map_err(|e| format!("Failed to serialize action: {}", e))?;
-        let mut hasher = Sha256::new();
-        hasher.update(action_data.as_bytes());
-        let action_hash = hex::encode(hasher.finalize());
-        
-        // Create block header
-        let block_header = BlockHeader {
-            parent_hash: format!("block_{}", state.block_height),
-            action_hash,
-            actor_pubkey: action.actor.clone(),
-            state_root: format!("state_root_{}", state.block_height + 1),
-            receipts_root: format!("receipts_root_{}", state.block_height + 1),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            validator_commitment: proposer.pubkey.clone(),
-            reward: 100, // Fixed reward in ACT (100 units)
+        let proposer = keypair.address().to_string();
+        let mut block_header = BlockHeader {
+            parent_hash: state.last_block_hash.clone(),
+            tx_root: Block::calculate_tx_root(&transactions),
+            actor_pubkey: proposer.clone(),
+            state_root,
+            receipts_root: Block::calculate_receipts_root(&receipts),
+            timestamp,
+            validator_commitment: proposer,
+            reward: HEADER_BLOCK_REWARD,
             height: state.block_height,
+            round,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: Block::calculate_last_commit_hash(last_commit.as_ref()),
         };
+        block_header.sign(keypair);
         
-        // Store the block to disk
-        let block_height = state.block_height;
-        if let Err(e) = store_block(&block_header, &action, block_height) {
-            println!("⚠️  Failed to store block: {}", e);
+        Ok(Block::new(block_header, transactions, receipts).with_last_commit(last_commit))
+    }
+
+    /// Check a block header received from a peer: it must extend the tip, be signed by the
+    /// proposer drawn for its height and round, and carry a timestamp after its parent's
+    /// and not ahead of our clock. The error says why a header is rejected.
+    pub async fn validate_header(&self, header: &BlockHeader) -> Result<(), String> {
+        let state = self.state.read().await;
+        
+        if header.height != state.block_height {
+            return Err(format!("Height {} does not follow the tip, expected {}", header.height, state.block_height));
+        }
+        if header.parent_hash != state.last_block_hash {
+            return Err(format!("Parent {} is not the tip {}", header.parent_hash, state.last_block_hash));
         }
         
-        state.increment_height();
-        state.rotate_proposer();
+        if !header.verify_signature() {
+            return Err(format!("Invalid header signature for proposer {}", header.validator_commitment));
+        }
+        if header.actor_pubkey != header.validator_commitment {
+            return Err(format!("Block credits {} but was proposed by {}", header.actor_pubkey, header.validator_commitment));
+        }
+        if header.reward != HEADER_BLOCK_REWARD {
+            return Err(format!("Block claims reward {}, expected {}", header.reward, HEADER_BLOCK_REWARD));
+        }
+        
+        // Only the validator drawn for this parent and round may propose
+        let validators = ValidatorSet::new(state.validators.values().cloned());
+        match validators.proposer(&header.parent_hash, header.round) {
+            Some(expected) if expected.pubkey == header.validator_commitment => {}
+            Some(expected) => {
+                return Err(format!("{} is not the proposer of round {}, {} is", header.validator_commitment, header.round, expected.pubkey));
+            }
+            None => return Err("No active validators".to_string()),
+        }
         
-        Ok(block_header)
+        if header.timestamp <= state.last_block_timestamp {
+            return Err(format!("Timestamp {} is not after the parent's {}", header.timestamp, state.last_block_timestamp));
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        if header.timestamp > now + MAX_CLOCK_DRIFT_SECS {
+            return Err(format!("Timestamp {} is more than {}s ahead of our clock", header.timestamp, MAX_CLOCK_DRIFT_SECS));
+        }
+        
+        Ok(())
     }
 
-    pub async fn validate_block(&self, header: &BlockHeader) -> Result<bool, String> {
-        let state = self.state.read().await;
+    /// Check a block received from a peer: its header as in `validate_header`, and the
+    /// certificate it carries for its parent, which staking counts validator signatures from
+    pub async fn validate_block(&self, block: &Block) -> Result<(), String> {
+        self.validate_header(&block.header).await?;
         
-        // Check if the validator exists and is active
-        if let Some(validator) = state.validators.get(&header.validator_commitment) {
-            if !validator.active {
-                return Ok(false);
-            }
-        } else {
-            return Ok(false);
+        let last_commit_hash = Block::calculate_last_commit_hash(block.last_commit.as_ref());
+        if block.header.last_commit_hash != last_commit_hash {
+            return Err(format!("Header commits to last commit {}, block carries {}", block.header.last_commit_hash, last_commit_hash));
         }
         
-        // Basic validation passed
-        Ok(true)
+        let state = self.state.read().await;
+        match &block.last_commit {
+            None if block.header.height == 0 => Ok(()),
+            None => Err(format!("Block {} carries no certificate for its parent", block.header.height)),
+            Some(_) if block.header.height == 0 => Err("The first block has no parent to certify".to_string()),
+            Some(certificate) => {
+                if certificate.height + 1 != block.header.height {
+                    return Err(format!("Certificate is for height {}, not the parent's", certificate.height));
+                }
+                // Without a block committed since startup, the set loaded now is our best guess
+                let validators = if state.last_validators.is_empty() { &state.validators } else { &state.last_validators };
+                bft::verify_commit(certificate, &block.header.parent_hash, &ValidatorSet::new(validators.values().cloned()))
+            }
+        }
     }
 
-    pub async fn finalize_block(&self, height: u64) -> Result<(), String> {
+    /// Store a block the validators agreed on, together with the certificate proving it
+    /// final, and move the tip to it. `persist_state` writes the block's state in the same
+    /// storage batch, so after a crash the stored tip and the stored state always match.
+    pub async fn commit_block(
+        &self,
+        block: &Block,
+        certificate: &CommitCertificate,
+        persist_state: impl FnOnce() -> anyhow::Result<()>,
+    ) -> Result<(), String> {
         let mut state = self.state.write().await;
         
-        if height > state.finalized_height {
-            state.finalized_height = height;
-            println!("✅ Block {} finalized", height);
+        if block.header.height != state.block_height || block.header.parent_hash != state.last_block_hash {
+            return Err(format!("Block {} does not extend the tip at height {}", block.header.height, state.block_height));
         }
+        let block_hash = block.hash();
+        let validators = ValidatorSet::new(state.validators.values().cloned());
+        bft::verify_commit(certificate, &block_hash, &validators)?;
+        
+        self.storage
+            .atomically(|| {
+                persist_state()?;
+                self.storage.store_commit(certificate)?;
+                self.storage.store_block(block)
+            })
+            .map_err(|e| format!("Failed to store block: {}", e))?;
+        
+        state.last_validators = state.validators.clone();
+        state.last_block_hash = block_hash;
+        state.last_block_timestamp = block.header.timestamp;
+        state.finalized_height = block.header.height;
+        state.increment_height();
+        println!("✅ Block {} finalized in round {} with {} precommits",
+            block.header.height, certificate.round, certificate.precommits.len());
         
         Ok(())
     }
 
+    /// Load the validator set from staking when the next block starts a new epoch, or when
+    /// none is loaded yet. Mid-epoch, as after a restart, the set staking recorded when the
+    /// epoch began is loaded with the stakes it had then. Returns whether the set was replaced.
+    pub async fn update_validator_set(&self, staking: &StakingManager) -> bool {
+        let mut state = self.state.write().await;
+        let epoch = epoch_of(state.block_height);
+        if !state.validators.is_empty() && epoch == state.epoch {
+            return false;
+        }
+        
+        // Staking has not started the next block yet, so its record is the set that
+        // finalized the tip, which the next block's certificate is checked against
+        let recorded = staking.epoch_validators();
+        if state.block_height > 0 && state.last_validators.is_empty() {
+            state.last_validators = validator_map(recorded);
+        }
+        if state.block_height.is_multiple_of(EPOCH_LENGTH) || recorded.is_empty() {
+            state.load_validators(&staking.get_active_validators(), epoch);
+        } else {
+            state.load_epoch_validators(recorded, epoch);
+        }
+        println!("👥 Epoch {}: {} validators, {} total stake", epoch, state.validators.len(),
+            state.validators.values().map(|v| v.stake).sum::<u64>());
+        true
+    }
+
+    /// Certificate that finalized the tip block, for the next block to carry
+    pub async fn get_last_commit(&self) -> Option<CommitCertificate> {
+        let height = self.state.read().await.block_height;
+        if height == 0 {
+            return None;
+        }
+        self.storage.get_commit(height - 1).ok().flatten()
+    }
+
+    /// Validators voting on the next block
+    pub async fn validator_set(&self) -> ValidatorSet {
+        ValidatorSet::new(self.state.read().await.validators.values().cloned())
+    }
+
+    /// Validator expected to propose the next block in round 0
+    pub async fn get_current_proposer(&self) -> Option<String> {
+        let state = self.state.read().await;
+        state.get_current_proposer().map(|v| v.pubkey.clone())
+    }
+
     pub async fn get_block_height(&self) -> u64 {
         self.state.read().await.block_height
     }
+
+    pub async fn get_last_block_hash(&self) -> String {
+        self.state.read().await.last_block_hash.clone()
+    }
+
+    pub async fn get_last_block_timestamp(&self) -> u64 {
+        self.state.read().await.last_block_timestamp
+    }
+
+    pub async fn get_finalized_height(&self) -> u64 {
+        self.state.read().await.finalized_height
+    }
 }
 
-pub async fn start_consensus(engine: Arc<ConsensusEngine>) {
-    println!("🎯 Consensus engine started");
-    
-    // Consensus loop - for now just log status
-    loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-        let height = engine.get_block_height().await;
-        println!("📊 Current block height: {}", height);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator(pubkey: &str, stake: u64) -> Validator {
+        Validator { pubkey: pubkey.to_string(), stake, active: true }
+    }
+
+    #[test]
+    fn test_stake_weighted_proposer() {
+        let validators = vec![validator("ACT-a", 100), validator("ACT-b", 300), validator("ACT-c", 0)];
+
+        // Same parent, same proposer
+        let first = select_proposer(&validators, "parent").unwrap();
+        assert_eq!(select_proposer(&validators, "parent").unwrap().pubkey, first.pubkey);
+
+        let mut wins = BTreeMap::new();
+        for i in 0..4000 {
+            let proposer = select_proposer(&validators, &format!("{:064x}", i)).unwrap();
+            *wins.entry(proposer.pubkey.as_str()).or_insert(0) += 1;
+        }
+        assert!(!wins.contains_key("ACT-c"));
+        assert!((2700..3300).contains(&wins["ACT-b"]), "{:?}", wins);
+
+        assert!(select_proposer(&[validator("ACT-c", 0)], "parent").is_none());
+        assert!(select_proposer(&[], "parent").is_none());
+    }
+
+    #[test]
+    fn test_validator_set_from_staking() {
+        let mut staking = StakingManager::new();
+        let stake = staking::MIN_VALIDATOR_STAKE;
+        staking.stake("ACT-b".to_string(), stake, 10).unwrap();
+        staking.stake("ACT-a".to_string(), stake * 2, 10).unwrap();
+
+        let mut state = ConsensusState::new();
+        state.load_validators(&staking.get_active_validators(), 3);
+        assert_eq!(state.epoch, 3);
+        let loaded: Vec<_> = state.validators.values().map(|v| (v.pubkey.as_str(), v.stake)).collect();
+        assert_eq!(loaded, vec![("ACT-a", stake * 2), ("ACT-b", stake)]);
+
+        assert_eq!(epoch_of(EPOCH_LENGTH - 1), 0);
+        assert_eq!(epoch_of(EPOCH_LENGTH), 1);
+    }
+
+    #[tokio::test]
+    async fn test_validator_set_after_restart() {
+        let dir = std::env::temp_dir().join(format!("act_consensus_restart_test_{}", std::process::id()));
+        let engine = ConsensusEngine::new(Arc::new(BlockchainStorage::new(dir.to_str().unwrap()).unwrap()));
+        let stake = staking::MIN_VALIDATOR_STAKE;
+        let mut staking = StakingManager::new();
+        staking.stake("ACT-a".to_string(), stake, 10).unwrap();
+        staking.stake("ACT-b".to_string(), stake, 10).unwrap();
+        staking.begin_block(0, None);
+
+        // Stake added mid-epoch only counts from the next epoch
+        staking.stake("ACT-b".to_string(), stake, 10).unwrap();
+        staking.stake("ACT-c".to_string(), stake, 10).unwrap();
+
+        // A node restarting mid-epoch loads the set the epoch began with
+        engine.state.write().await.block_height = 7;
+        assert!(engine.update_validator_set(&staking).await);
+        let stakes = |set: &BTreeMap<String, Validator>| set.values().map(|v| (v.pubkey.clone(), v.stake)).collect::<Vec<_>>();
+        let epoch_set = vec![("ACT-a".to_string(), stake), ("ACT-b".to_string(), stake)];
+        {
+            let state = engine.state.read().await;
+            assert_eq!(stakes(&state.validators), epoch_set);
+            assert_eq!(stakes(&state.last_validators), epoch_set);
+        }
+
+        engine.state.write().await.block_height = EPOCH_LENGTH;
+        assert!(engine.update_validator_set(&staking).await);
+        let state = engine.state.read().await;
+        assert_eq!(
+            stakes(&state.validators),
+            vec![("ACT-a".to_string(), stake), ("ACT-b".to_string(), stake * 2), ("ACT-c".to_string(), stake)]
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_validate_header() {
+        let dir = std::env::temp_dir().join(format!("act_consensus_test_{}", std::process::id()));
+        let engine = ConsensusEngine::new(Arc::new(BlockchainStorage::new(dir.to_str().unwrap()).unwrap()));
+        let keys = [ActKeyPair::from_seed(&[1; 32]), ActKeyPair::from_seed(&[2; 32])];
+        {
+            let mut state = engine.state.write().await;
+            for key in &keys {
+                state.add_validator(key.address().to_string(), 100);
+            }
+        }
+        let proposer = engine.get_current_proposer().await.unwrap();
+        let (leader, other) = if keys[0].address().to_string() == proposer { (&keys[0], &keys[1]) } else { (&keys[1], &keys[0]) };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let build = |keypair, timestamp| engine.build_block(keypair, 0, Vec::new(), Vec::new(), "0".repeat(64), timestamp, None);
+
+        let block = build(leader, now).await.unwrap();
+        assert!(engine.validate_header(&block.header).await.is_ok());
+
+        let mut tampered = block.header.clone();
+        tampered.state_root = "1".repeat(64);
+        assert!(engine.validate_header(&tampered).await.unwrap_err().contains("signature"));
+
+        let usurper = build(other, now).await.unwrap();
+        assert!(engine.validate_header(&usurper.header).await.unwrap_err().contains("not the proposer"));
+
+        let early = build(leader, now + MAX_CLOCK_DRIFT_SECS + 60).await.unwrap();
+        assert!(engine.validate_header(&early.header).await.unwrap_err().contains("ahead"));
+
+        let mut orphan = block.header.clone();
+        orphan.parent_hash = "1".repeat(64);
+        orphan.sign(leader);
+        assert!(engine.validate_header(&orphan).await.unwrap_err().contains("Parent"));
+
+        // The reward is what the executor mints, not whatever the proposer signs
+        assert_eq!(block.header.reward, 50 * 10u128.pow(types::ACT_DECIMALS));
+        let mut greedy = block.header.clone();
+        greedy.reward *= 2;
+        greedy.sign(leader);
+        assert!(engine.validate_header(&greedy).await.unwrap_err().contains("reward"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_last_commit() {
+        let dir = std::env::temp_dir().join(format!("act_consensus_commit_test_{}", std::process::id()));
+        let engine = ConsensusEngine::new(Arc::new(BlockchainStorage::new(dir.to_str().unwrap()).unwrap()));
+        let keys = [ActKeyPair::from_seed(&[1; 32]), ActKeyPair::from_seed(&[2; 32])];
+        {
+            let mut state = engine.state.write().await;
+            for key in &keys {
+                state.add_validator(key.address().to_string(), 100);
+            }
+        }
+        let key_of = |address: String| keys.iter().find(|k| k.address().to_string() == address).unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        // The first block has no parent to certify
+        assert!(engine.get_last_commit().await.is_none());
+        let leader = key_of(engine.get_current_proposer().await.unwrap());
+        let first = engine.build_block(leader, 0, Vec::new(), Vec::new(), "0".repeat(64), now - 10, None).await.unwrap();
+        assert!(engine.validate_block(&first).await.is_ok());
+
+        let precommits = keys
+            .iter()
+            .map(|k| types::consensus::Vote::new(k, 0, 0, types::consensus::VoteType::Precommit, Some(first.hash())))
+            .collect();
+        let certificate = CommitCertificate { height: 0, round: 0, block_hash: first.hash(), precommits };
+        engine.commit_block(&first, &certificate, || Ok(())).await.unwrap();
+
+        // The next block carries the certificate and its header commits to it
+        let last_commit = engine.get_last_commit().await;
+        assert_eq!(last_commit, Some(certificate));
+        let leader = key_of(engine.get_current_proposer().await.unwrap());
+        let block = engine.build_block(leader, 0, Vec::new(), Vec::new(), "0".repeat(64), now, last_commit).await.unwrap();
+        assert!(engine.validate_block(&block).await.is_ok());
+
+        let mut swapped = block.clone();
+        swapped.last_commit.as_mut().unwrap().precommits.pop();
+        assert!(engine.validate_block(&swapped).await.unwrap_err().contains("commits to"));
+
+        let mut uncertified = block.clone().with_last_commit(None);
+        uncertified.header.last_commit_hash = Block::calculate_last_commit_hash(None);
+        uncertified.header.sign(leader);
+        assert!(engine.validate_block(&uncertified).await.unwrap_err().contains("no certificate"));
+
+        // One of two equal validators is no quorum
+        let mut partial = block.last_commit.clone().unwrap();
+        partial.precommits.pop();
+        let mut underpowered = block.clone().with_last_commit(Some(partial));
+        underpowered.header.last_commit_hash = Block::calculate_last_commit_hash(underpowered.last_commit.as_ref());
+        underpowered.header.sign(leader);
+        assert!(engine.validate_block(&underpowered).await.unwrap_err().contains("2/3"));
+
+        let _ = std::fs::remove_dir_all(dir);
     }
 }
@@ -5,7 +5,7 @@ extern "C" {
     fn emit_event(topic_ptr: *const u8, topic_len: u32, data_ptr: *const u8, data_len: u32) -> i32;
     fn log(msg_ptr: *const u8, msg_len: u32);
     fn storage_write(key_ptr: *const u8, key_len: u32, val_ptr: *const u8, val_len: u32) -> i32;
-    fn storage_read(key_ptr: *const u8, key_len: u32, val_ptr: *mut u8) -> i32;
+    fn storage_read(key_ptr: *const u8, key_len: u32, val_ptr: *mut u8, val_cap: u32) -> i32;
 }
 
 /// Event: Transfer
@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Result};
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::{rngs::OsRng, Rng};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
 use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -243,6 +244,17 @@ impl EthKeyPair {
         Ok(signature.serialize_compact().to_vec())
     }
     
+    /// Sign a keccak256 hash, returning the compact signature and recovery id
+    pub fn sign_recoverable(&self, message_hash: &[u8; 32]) -> ([u8; 64], i32) {
+        let secp = Secp256k1::new();
+        let message = Message::from_digest(*message_hash);
+        let (recovery_id, signature) = secp
+            .sign_ecdsa_recoverable(&message, &self.secret_key)
+            .serialize_compact();
+        
+        (signature, recovery_id.to_i32())
+    }
+    
     /// Get address
     pub fn address(&self) -> &EthAddress {
         &self.address
@@ -270,6 +282,26 @@ pub fn verify_eth_signature(
     Ok(secp.verify_ecdsa(&message, &signature, &pubkey).is_ok())
 }
 
+/// Recover the signer of an Ethereum signature over a keccak256 `message_hash`.
+/// Returns the uncompressed public key (without the 0x04 prefix) and its address.
+pub fn recover_eth_signer(
+    message_hash: &[u8; 32],
+    signature: &[u8],
+    recovery_id: i32,
+) -> Result<(Vec<u8>, EthAddress)> {
+    let secp = Secp256k1::new();
+    
+    let recovery_id = RecoveryId::from_i32(recovery_id)?;
+    let signature = RecoverableSignature::from_compact(signature, recovery_id)?;
+    let message = Message::from_digest(*message_hash);
+    let pubkey = secp.recover_ecdsa(&message, &signature)?;
+    
+    let pubkey_bytes = pubkey.serialize_uncompressed()[1..].to_vec(); // Skip 0x04 prefix
+    let address = EthAddress::from_pubkey(&pubkey_bytes);
+    
+    Ok((pubkey_bytes, address))
+}
+
 /// Keccak256 hash (Ethereum-style)
 pub fn keccak256(data: &[u8]) -> [u8; 32] {
     let mut hasher = Keccak::v256();
@@ -300,6 +332,21 @@ mod tests {
         assert!(verified);
     }
 
+    #[test]
+    fn test_eth_signer_recovery() {
+        let keypair = EthKeyPair::generate().unwrap();
+        let hash = keccak256(b"Hello ACT Chain");
+        
+        let (signature, recovery_id) = keypair.sign_recoverable(&hash);
+        
+        let (pubkey, address) = recover_eth_signer(&hash, &signature, recovery_id).unwrap();
+        assert_eq!(address, keypair.address);
+        assert_eq!(pubkey, keypair.public_key.serialize_uncompressed()[1..].to_vec());
+        
+        let (_, other) = recover_eth_signer(&keccak256(b"tampered"), &signature, recovery_id).unwrap();
+        assert_ne!(other, keypair.address);
+    }
+
     #[test]
     fn test_address_format() {
         let keypair = ActKeyPair::generate();
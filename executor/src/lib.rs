@@ -0,0 +1,1116 @@
+use anyhow::{anyhow, Result};
+use runtime::{CallParams, CallTrace, ExecutionResult, HostState, WasmRuntime};
+use state::{calculate_gas_cost, GasConfig, StateCheckpoint, StateManager, StateOverlay};
+use std::path::PathBuf;
+use std::sync::Arc;
+use types::consensus::CommitCertificate;
+use types::{ActAmount, Block, EventLog, Transaction, TransactionReceipt, TransactionType};
+
+mod system;
+
+pub use system::{SystemState, SYSTEM_RECORDS_ACCOUNT};
+
+/// Block-level inputs to execution that do not come from any transaction
+#[derive(Debug, Clone)]
+pub struct BlockContext {
+    pub height: u64,
+    pub timestamp: u64,
+    pub coinbase: String, // Account credited with transaction fees
+    pub last_commit: Option<CommitCertificate>,  // Parent's certificate, whose signers staking counts
+}
+
+impl BlockContext {
+    pub fn from_block(block: &Block) -> Self {
+        Self {
+            height: block.header.height,
+            timestamp: block.header.timestamp,
+            coinbase: block.header.actor_pubkey.clone(),
+            last_commit: block.last_commit.clone(),
+        }
+    }
+}
+
+/// Result of running a list of transactions against the parent state
+pub struct BlockExecution {
+    pub state_root: String,
+    pub transactions: Vec<Transaction>,   // Transactions included, in execution order
+    pub receipts: Vec<TransactionReceipt>,
+    pub events: Vec<EventLog>,
+    pub gas_used: u64,
+    pub fees: ActAmount,
+    pub rejected: Vec<(String, String)>,  // (tx hash, reason) for transactions left out
+    pub state: StateOverlay,              // Post-state; apply with `StateManager::commit`
+    pub system: SystemState,              // Staking and governance records after the block
+}
+
+/// A contract call simulated outside any transaction, as made by `eth_call`
+#[derive(Debug, Clone)]
+pub struct CallRequest {
+    pub from: String,  // Caller seen by the contract
+    pub to: String,
+    pub method: String,
+    pub input: Vec<u8>,
+    pub value: ActAmount,
+    pub gas_limit: u64,
+}
+
+/// A historic transaction re-executed with call tracing
+#[derive(Debug, Clone)]
+pub struct TransactionTrace {
+    pub receipt: TransactionReceipt,
+    pub trace: Option<CallTrace>,  // None when the transaction did not call a contract
+}
+
+/// Deterministic state transition function shared by block production and validation
+pub struct Executor {
+    state: Arc<StateManager>,
+    runtime: WasmRuntime,
+    gas_config: GasConfig,
+    traces: Option<Vec<CallTrace>>,  // Collected while `trace_transaction` runs
+}
+
+impl Executor {
+    pub fn new(state: Arc<StateManager>, gas_config: GasConfig) -> Result<Self> {
+        Ok(Self {
+            state,
+            runtime: WasmRuntime::with_gas_config(gas_config.clone())?,
+            gas_config,
+            traces: None,
+        })
+    }
+
+    /// Executor running contracts on `runtime`, sharing its module cache and gas schedule
+    pub fn with_runtime(state: Arc<StateManager>, runtime: WasmRuntime) -> Self {
+        Self {
+            state,
+            gas_config: runtime.gas_config().clone(),
+            runtime,
+            traces: None,
+        }
+    }
+
+    /// Persist compiled contract modules under `dir` so restarts skip recompilation
+    pub fn with_module_cache_dir(mut self, dir: impl Into<PathBuf>) -> Result<Self> {
+        self.runtime = self.runtime.with_cache_dir(dir)?;
+        Ok(self)
+    }
+
+    /// Execute candidate transactions for a new block on top of the parent state.
+    /// Transactions that are invalid against the parent state are left out and reported in `rejected`.
+    pub fn execute_block(&mut self, transactions: Vec<Transaction>, ctx: &BlockContext) -> Result<BlockExecution> {
+        let mut overlay = self.state.overlay();
+        let mut system = Self::block_system(&mut overlay, ctx)?;
+        let mut included = Vec::new();
+        let mut receipts = Vec::new();
+        let mut rejected = Vec::new();
+
+        for tx in transactions {
+            match self.apply_transaction(&mut overlay, &mut system, &tx, ctx) {
+                Ok(receipt) => {
+                    receipts.push(receipt);
+                    included.push(tx);
+                }
+                Err(e) => rejected.push((tx.hash(), e.to_string())),
+            }
+        }
+
+        Self::finish(overlay, system, ctx, included, receipts, rejected)
+    }
+
+    /// Re-execute a block against the parent state and check that its header commits to the result
+    pub fn validate_block(&mut self, block: &Block) -> Result<BlockExecution> {
+        let ctx = BlockContext::from_block(block);
+        let mut overlay = self.state.overlay();
+        let mut system = Self::block_system(&mut overlay, &ctx)?;
+        let mut receipts = Vec::new();
+
+        for tx in &block.transactions {
+            let receipt = self
+                .apply_transaction(&mut overlay, &mut system, tx, &ctx)
+                .map_err(|e| anyhow!("Invalid transaction {}: {}", tx.hash(), e))?;
+            receipts.push(receipt);
+        }
+
+        let execution = Self::finish(overlay, system, &ctx, block.transactions.clone(), receipts, Vec::new())?;
+
+        let tx_root = Block::calculate_tx_root(&block.transactions);
+        if tx_root != block.header.tx_root {
+            return Err(anyhow!("Transaction root mismatch: header {}, computed {}", block.header.tx_root, tx_root));
+        }
+
+        let receipts_root = Block::calculate_receipts_root(&execution.receipts);
+        if receipts_root != block.header.receipts_root {
+            return Err(anyhow!("Receipts root mismatch: header {}, computed {}", block.header.receipts_root, receipts_root));
+        }
+
+        if execution.state_root != block.header.state_root {
+            return Err(anyhow!("State root mismatch: header {}, computed {}", block.header.state_root, execution.state_root));
+        }
+
+        Ok(execution)
+    }
+
+    /// Run a contract call against the latest state, or the state at `state_root`, and
+    /// discard its writes. No signature, nonce or fee applies.
+    pub fn call(&mut self, request: &CallRequest, state_root: Option<&str>, block_height: u64) -> Result<ExecutionResult> {
+        let mut overlay = match state_root {
+            Some(root) => self.state.overlay_at(root)?,
+            None => self.state.overlay(),
+        };
+
+        let code = overlay
+            .get_code(&request.to)?
+            .ok_or_else(|| anyhow!("No contract deployed at {}", request.to))?;
+
+        if request.value > 0 {
+            overlay.transfer(&request.from, &request.to, request.value)?;
+        }
+
+        let params = CallParams {
+            contract_address: request.to.clone(),
+            caller: request.from.clone(),
+            method: request.method.clone(),
+            input: request.input.clone(),
+            value: request.value,
+            gas_limit: request.gas_limit,
+            transaction_hash: String::new(),
+            block_height,
+            trace: false,
+        };
+
+        // The overlay is dropped with the host, so nothing the call writes survives
+        let (result, _) = self.runtime.execute_call(&params, &code, OverlayHost(overlay));
+        Ok(result)
+    }
+
+    /// Smallest gas limit with which `tx` succeeds against the latest state plus `pending`.
+    ///
+    /// Signature, nonce and fee checks are skipped so unsigned transactions can be estimated.
+    /// The gas a run reports using is only a starting point: a call may succeed with less
+    /// (WASM fuel is checked at loop headers and calls, so the instructions after the last
+    /// check run even once the limit is reached) or fail with exactly that much (e.g. gas
+    /// forwarded to sub-calls). The limit is binary-searched below it or up to `max_gas`.
+    pub fn estimate_gas(
+        &mut self,
+        tx: &Transaction,
+        pending: &[Transaction],
+        ctx: &BlockContext,
+        max_gas: u64,
+    ) -> Result<u64> {
+        let mut base = self.state.overlay();
+        let mut system = Self::block_system(&mut base, ctx)?;
+        for pending_tx in pending {
+            // Pending transactions that would be rejected leave the overlay untouched
+            let _ = self.apply_transaction(&mut base, &mut system, pending_tx, ctx);
+        }
+
+        let used = self
+            .dry_run(&base, &system, tx, max_gas, ctx)
+            .ok_or_else(|| anyhow!("Transaction fails even with the maximum gas limit of {}", max_gas))?;
+        // Below the intrinsic cost nothing runs
+        let (lo, hi) = if self.dry_run(&base, &system, tx, used, ctx).is_some() {
+            (calculate_gas_cost(tx, &self.gas_config) - 1, used)
+        } else {
+            (used, max_gas)
+        };
+        Ok(lowest_passing_limit(lo, hi, |limit| {
+            self.dry_run(&base, &system, tx, limit, ctx).is_some()
+        }))
+    }
+
+    /// Execute `tx` with the given gas limit on a copy of `base` and `system`; gas used if it succeeds
+    fn dry_run(
+        &mut self,
+        base: &StateOverlay,
+        system: &SystemState,
+        tx: &Transaction,
+        gas_limit: u64,
+        ctx: &BlockContext,
+    ) -> Option<u64> {
+        let intrinsic_gas = calculate_gas_cost(tx, &self.gas_config);
+        if gas_limit < intrinsic_gas {
+            return None;
+        }
+
+        let mut tx = tx.clone();
+        tx.gas_limit = gas_limit;
+        let mut overlay = base.clone();
+        let mut system = system.clone();
+        let outcome = self.dispatch(&mut overlay, &mut system, &tx, &tx.hash(), intrinsic_gas, ctx);
+        outcome.success.then_some(outcome.gas_used)
+    }
+
+    /// Re-execute a transaction of `block` against the block's parent state, replaying the
+    /// transactions before it, and trace its contract call
+    pub fn trace_transaction(&mut self, block: &Block, parent_state_root: &str, tx_hash: &str) -> Result<TransactionTrace> {
+        let ctx = BlockContext::from_block(block);
+        let mut overlay = self.state.overlay_at(parent_state_root)?;
+        let mut system = Self::block_system(&mut overlay, &ctx)?;
+
+        for tx in &block.transactions {
+            if tx.hash() != tx_hash {
+                self.apply_transaction(&mut overlay, &mut system, tx, &ctx)
+                    .map_err(|e| anyhow!("Invalid transaction {}: {}", tx.hash(), e))?;
+                continue;
+            }
+
+            self.traces = Some(Vec::new());
+            let receipt = self.apply_transaction(&mut overlay, &mut system, tx, &ctx);
+            let trace = self.traces.take().and_then(|mut traces| traces.pop());
+            return Ok(TransactionTrace { receipt: receipt?, trace });
+        }
+
+        Err(anyhow!("Transaction {} not found in block {}", tx_hash, block.header.height))
+    }
+
+    /// Records of the parent state `overlay` starts from, moved to the block's height.
+    /// Validators that have missed too many blocks are jailed before any transaction runs.
+    fn block_system(overlay: &mut StateOverlay, ctx: &BlockContext) -> Result<SystemState> {
+        let mut system = SystemState::load(overlay)?;
+        system.begin_block(overlay, ctx.height, ctx.last_commit.as_ref())?;
+        Ok(system)
+    }
+
+    /// Record the proposer's block reward and settle the records, then compute the state root.
+    /// Fees were already paid to the coinbase by each transaction.
+    fn finish(
+        mut overlay: StateOverlay,
+        mut system: SystemState,
+        ctx: &BlockContext,
+        transactions: Vec<Transaction>,
+        receipts: Vec<TransactionReceipt>,
+        rejected: Vec<(String, String)>,
+    ) -> Result<BlockExecution> {
+        let gas_used = receipts.iter().map(|r| r.gas_used).sum();
+        let fees = transactions
+            .iter()
+            .zip(&receipts)
+            .map(|(tx, r)| r.gas_used as ActAmount * tx.gas_price)
+            .sum();
+        let events = receipts.iter().flat_map(|r| r.logs.iter().cloned()).collect();
+
+        // The state root covers the records as they are after the block
+        system.end_block(&ctx.coinbase);
+        system.store(&mut overlay)?;
+        overlay.update_storage_roots()?;
+
+        Ok(BlockExecution {
+            state_root: overlay.state_root()?,
+            transactions,
+            receipts,
+            events,
+            gas_used,
+            fees,
+            rejected,
+            state: overlay,
+            system,
+        })
+    }
+
+    /// Apply one transaction.
+    ///
+    /// An `Err` means the transaction cannot be included at all (bad signature, nonce or
+    /// funds) and the overlay is left untouched. Execution failures are included: the
+    /// nonce is bumped and gas is charged, but the transaction's other effects are reverted.
+    fn apply_transaction(
+        &mut self,
+        overlay: &mut StateOverlay,
+        system: &mut SystemState,
+        tx: &Transaction,
+        ctx: &BlockContext,
+    ) -> Result<TransactionReceipt> {
+        let sender = tx.from.to_string();
+        let tx_hash = tx.hash();
+
+        if !tx.verify_signature() {
+            return Err(anyhow!("Invalid transaction signature"));
+        }
+
+        let intrinsic_gas = calculate_gas_cost(tx, &self.gas_config);
+        if tx.gas_limit < intrinsic_gas {
+            return Err(anyhow!("Gas limit {} below intrinsic cost {}", tx.gas_limit, intrinsic_gas));
+        }
+
+        let account_nonce = overlay.get_nonce(&sender)?;
+        if tx.nonce != account_nonce {
+            return Err(anyhow!("Nonce mismatch: expected {}, got {}", account_nonce, tx.nonce));
+        }
+
+        let value = tx.tx_type.value();
+        let max_cost = (tx.gas_limit as ActAmount)
+            .checked_mul(tx.gas_price)
+            .and_then(|fee| fee.checked_add(value))
+            .ok_or_else(|| anyhow!("Transaction cost overflows"))?;
+        let balance = overlay.get_balance(&sender)?;
+        if balance < max_cost {
+            return Err(anyhow!("Insufficient balance: has {}, needs {}", balance, max_cost));
+        }
+
+        let checkpoint = overlay.checkpoint();
+        let outcome = self.dispatch(overlay, system, tx, &tx_hash, intrinsic_gas, ctx);
+        if !outcome.success {
+            overlay.revert(checkpoint);
+        }
+        overlay.update_storage_roots()?;
+
+        // Nonce and fee apply whether or not execution succeeded
+        overlay.increment_nonce(&sender)?;
+        let fee = outcome.gas_used as ActAmount * tx.gas_price;
+        overlay.transfer(&sender, &ctx.coinbase, fee)?;
+
+        Ok(TransactionReceipt {
+            transaction_hash: tx_hash,
+            block_height: ctx.height,
+            from: sender,
+            to: outcome.to,
+            contract_address: outcome.contract_address,
+            status: outcome.success,
+            gas_used: outcome.gas_used,
+            logs: outcome.events,
+            logs_bloom: None,
+        })
+    }
+
+    fn dispatch(
+        &mut self,
+        overlay: &mut StateOverlay,
+        system: &mut SystemState,
+        tx: &Transaction,
+        tx_hash: &str,
+        intrinsic_gas: u64,
+        ctx: &BlockContext,
+    ) -> Outcome {
+        let sender = tx.from.to_string();
+
+        match &tx.tx_type {
+            TransactionType::Transfer { to, amount } => {
+                let success = overlay.transfer(&sender, to, *amount).is_ok();
+                Outcome::new(success, intrinsic_gas).to(to)
+            }
+            TransactionType::ContractDeploy { code, .. } => {
+                self.deploy_contract(overlay, &sender, code, 0, intrinsic_gas)
+            }
+            TransactionType::ContractCall { contract, method, args } => {
+                self.call_contract(overlay, tx, tx_hash, contract, method, args, 0, intrinsic_gas, ctx)
+                    .to(contract)
+            }
+            TransactionType::EthereumLegacy { to, value, data, .. } => {
+                if to.is_empty() {
+                    // Contract creation: data is the contract code
+                    return self.deploy_contract(overlay, &sender, data, *value, intrinsic_gas);
+                }
+
+                let is_contract = overlay.get_account(to).map(|a| a.is_contract()).unwrap_or(false);
+                if is_contract {
+                    self.call_contract(overlay, tx, tx_hash, to, "execute", data, *value, intrinsic_gas, ctx)
+                        .to(to)
+                } else {
+                    let success = overlay.transfer(&sender, to, *value).is_ok();
+                    Outcome::new(success, intrinsic_gas).to(to)
+                }
+            }
+            staking_or_governance => {
+                let success = system.apply(overlay, &sender, staking_or_governance).is_ok();
+                Outcome::new(success, intrinsic_gas)
+            }
+        }
+    }
+
+    /// Deploy contract code once it passes validation; invalid code gives a failed receipt
+    fn deploy_contract(
+        &self,
+        overlay: &mut StateOverlay,
+        sender: &str,
+        code: &[u8],
+        value: ActAmount,
+        intrinsic_gas: u64,
+    ) -> Outcome {
+        if self.runtime.validate_contract(code).is_err() {
+            return Outcome::new(false, intrinsic_gas);
+        }
+
+        match overlay.deploy_contract(sender, code, value) {
+            Ok(address) => Outcome::new(true, intrinsic_gas).deployed(address),
+            Err(_) => Outcome::new(false, intrinsic_gas),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn call_contract(
+        &mut self,
+        overlay: &mut StateOverlay,
+        tx: &Transaction,
+        tx_hash: &str,
+        contract: &str,
+        method: &str,
+        input: &[u8],
+        value: ActAmount,
+        intrinsic_gas: u64,
+        ctx: &BlockContext,
+    ) -> Outcome {
+        let sender = tx.from.to_string();
+
+        let code = match overlay.get_code(contract) {
+            Ok(Some(code)) => code,
+            _ => return Outcome::new(false, intrinsic_gas),
+        };
+
+        if value > 0 && overlay.transfer(&sender, contract, value).is_err() {
+            return Outcome::new(false, intrinsic_gas);
+        }
+
+        let params = CallParams {
+            contract_address: contract.to_string(),
+            caller: sender,
+            method: method.to_string(),
+            input: input.to_vec(),
+            value,
+            gas_limit: tx.gas_limit - intrinsic_gas,
+            transaction_hash: tx_hash.to_string(),
+            block_height: ctx.height,
+            trace: self.traces.is_some(),
+        };
+
+        // The runtime owns the overlay for the duration of the call
+        let host = OverlayHost(std::mem::replace(overlay, self.state.overlay()));
+        let (mut result, host) = self.runtime.execute_call(&params, &code, host);
+        *overlay = host.0;
+
+        if let (Some(traces), Some(trace)) = (&mut self.traces, result.trace.take()) {
+            traces.push(trace);
+        }
+
+        let mut outcome = Outcome::new(result.success, intrinsic_gas + result.gas_used);
+        outcome.events = result.events;
+        outcome
+    }
+}
+
+/// Exposes a block's state overlay to contract host functions
+struct OverlayHost(StateOverlay);
+
+impl HostState for OverlayHost {
+    type Checkpoint = StateCheckpoint;
+
+    fn storage_read(&self, contract: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.0.get_storage(contract, key)
+    }
+
+    fn storage_write(&mut self, contract: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.0.set_storage(contract, key, value);
+        Ok(())
+    }
+
+    fn storage_delete(&mut self, contract: &str, key: &[u8]) -> Result<()> {
+        self.0.delete_storage(contract, key);
+        Ok(())
+    }
+
+    fn balance(&self, address: &str) -> Result<ActAmount> {
+        self.0.get_balance(address)
+    }
+
+    fn get_code(&self, address: &str) -> Result<Option<Vec<u8>>> {
+        self.0.get_code(address)
+    }
+
+    fn transfer(&mut self, from: &str, to: &str, amount: ActAmount) -> Result<()> {
+        self.0.transfer(from, to, amount)
+    }
+
+    fn checkpoint(&self) -> StateCheckpoint {
+        self.0.checkpoint()
+    }
+
+    fn revert(&mut self, checkpoint: StateCheckpoint) {
+        self.0.revert(checkpoint)
+    }
+}
+
+/// Effects of dispatching a single transaction, before nonce and fee handling
+struct Outcome {
+    success: bool,
+    gas_used: u64,
+    to: Option<String>,
+    contract_address: Option<String>,
+    events: Vec<EventLog>,
+}
+
+impl Outcome {
+    fn new(success: bool, gas_used: u64) -> Self {
+        Self {
+            success,
+            gas_used,
+            to: None,
+            contract_address: None,
+            events: Vec::new(),
+        }
+    }
+
+    fn to(mut self, address: &str) -> Self {
+        self.to = Some(address.to_string());
+        self
+    }
+
+    fn deployed(mut self, address: String) -> Self {
+        self.contract_address = Some(address);
+        self
+    }
+}
+
+/// Smallest gas limit above `lo` that `succeeds` with, found by binary search between a
+/// limit known to fail (`lo`) and one known to succeed (`hi`)
+fn lowest_passing_limit(mut lo: u64, mut hi: u64, mut succeeds: impl FnMut(u64) -> bool) -> u64 {
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if succeeds(mid) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    hi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ActKeyPair;
+    use state::GenesisAccount;
+    use storage::BlockchainStorage;
+    use types::BlockHeader;
+
+    fn signed_transfer(keypair: &ActKeyPair, to: &str, amount: ActAmount, nonce: u64) -> Transaction {
+        let tx_type = TransactionType::Transfer {
+            to: to.to_string(),
+            amount,
+        };
+        signed(keypair, tx_type, nonce)
+    }
+
+    fn signed(keypair: &ActKeyPair, tx_type: TransactionType, nonce: u64) -> Transaction {
+        let mut tx = Transaction {
+            from: keypair.address().clone(),
+            nonce,
+            tx_type,
+            gas_limit: 21000,
+            gas_price: 1,
+            signature: vec![],
+            pubkey: keypair.public_key(),
+        };
+        tx.signature = keypair.sign(&tx.signing_data());
+        tx
+    }
+
+    /// Block whose header commits to `execution`
+    fn sealed_block(execution: &BlockExecution, ctx: &BlockContext) -> Block {
+        let header = BlockHeader {
+            parent_hash: "0".repeat(64),
+            tx_root: Block::calculate_tx_root(&execution.transactions),
+            actor_pubkey: ctx.coinbase.clone(),
+            state_root: execution.state_root.clone(),
+            receipts_root: Block::calculate_receipts_root(&execution.receipts),
+            timestamp: ctx.timestamp,
+            validator_commitment: ctx.coinbase.clone(),
+            reward: 0,
+            height: ctx.height,
+            round: 0,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: "0".repeat(64),
+        };
+        Block::new(header, execution.transactions.clone(), execution.receipts.clone())
+    }
+
+    #[test]
+    fn test_execute_and_validate_block() {
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let keypair = ActKeyPair::generate();
+        let sender = keypair.address().to_string();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(sender.clone(), 1000.0)])
+            .unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx = BlockContext {
+            height: 1,
+            timestamp: 1_700_000_000,
+            coinbase: "ACT-coinbase".to_string(),
+            last_commit: None,
+        };
+
+        let txs = vec![
+            signed_transfer(&keypair, "ACT-receiver", 500, 0),
+            signed_transfer(&keypair, "ACT-receiver", 500, 5), // Wrong nonce: left out
+        ];
+        let execution = executor.execute_block(txs, &ctx).unwrap();
+
+        assert_eq!(execution.transactions.len(), 1);
+        assert_eq!(execution.rejected.len(), 1);
+        assert!(execution.receipts[0].status);
+        assert_eq!(execution.gas_used, 21000);
+        assert_eq!(execution.state.get_balance("ACT-coinbase").unwrap(), 21000);
+
+        // A verifier re-executing the same block reaches the same roots
+        let block = sealed_block(&execution, &ctx);
+        assert!(executor.validate_block(&block).is_ok());
+
+        let mut tampered = block.clone();
+        tampered.header.state_root = "0".repeat(64);
+        assert!(executor.validate_block(&tampered).is_err());
+
+        state_manager.commit(execution.state).unwrap();
+        assert_eq!(state_manager.get_balance("ACT-receiver").unwrap(), 500);
+        assert_eq!(state_manager.get_nonce(&sender).unwrap(), 1);
+
+        // Tracing replays the transaction against the block's parent state
+        let genesis_root = state_manager.genesis_state_root().unwrap();
+        let tx_hash = block.transactions[0].hash();
+        let traced = executor.trace_transaction(&block, &genesis_root, &tx_hash).unwrap();
+        assert!(traced.receipt.status);
+        assert_eq!(traced.receipt.gas_used, 21000);
+        assert!(traced.trace.is_none());
+        assert!(executor.trace_transaction(&block, &genesis_root, "missing").is_err());
+
+        // Estimates run after the sender's pending transactions
+        let remaining = state_manager.get_balance(&sender).unwrap();
+        let pending = vec![signed_transfer(&keypair, "ACT-receiver", remaining - 21000 - 10, 1)];
+        let mut unsigned = signed_transfer(&keypair, "ACT-receiver", 5, 2);
+        unsigned.signature.clear();
+        assert_eq!(executor.estimate_gas(&unsigned, &pending, &ctx, 10_000_000).unwrap(), 21000);
+
+        let overdraft = signed_transfer(&keypair, "ACT-receiver", 100, 2);
+        assert!(executor.estimate_gas(&overdraft, &pending, &ctx, 10_000_000).is_err());
+        assert!(executor.estimate_gas(&overdraft, &[], &ctx, 10_000_000).is_ok());
+
+        std::fs::remove_dir_all("./test_executor_db").ok();
+    }
+
+    #[test]
+    fn test_staking_transactions() {
+        use staking::{MIN_VALIDATOR_STAKE, STAKING_POOL_ACCOUNT, UNSTAKE_LOCK_PERIOD};
+
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_staking_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let keypair = ActKeyPair::generate();
+        let sender = keypair.address().to_string();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(sender.clone(), 200_000.0)])
+            .unwrap();
+        let initial_balance = state_manager.get_balance(&sender).unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx_at = |height| BlockContext {
+            height,
+            timestamp: 1_700_000_000 + height,
+            coinbase: "ACT-coinbase".to_string(),
+            last_commit: None,
+        };
+        let stake = MIN_VALIDATOR_STAKE as ActAmount * 1_000_000_000;  // Records use 9 decimals
+
+        let ctx = ctx_at(1);
+        let txs = vec![
+            signed(&keypair, TransactionType::Stake { amount: stake, commission_rate: 10 }, 0),
+            signed(&keypair, TransactionType::Delegate { validator: "ACT-nobody".to_string(), amount: 5 }, 1),
+            signed(&keypair, TransactionType::Vote { proposal_id: 1, option: governance::VoteOption::Yes }, 2),
+        ];
+        let execution = executor.execute_block(txs, &ctx).unwrap();
+
+        // Failed actions are included and charged, but change nothing
+        let statuses: Vec<bool> = execution.receipts.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![true, false, false]);
+        assert_eq!(execution.state.get_balance(STAKING_POOL_ACCOUNT).unwrap(), stake);
+        assert_eq!(execution.system.staking.get_validator(&sender).unwrap().stake, MIN_VALIDATOR_STAKE);
+        assert!(execution.system.staking.get_delegations(&sender).is_empty());
+
+        // Validators replaying the block from the same parent state agree
+        let block = sealed_block(&execution, &ctx);
+        assert!(executor.validate_block(&block).is_ok());
+
+        // The records are committed with the state, and read back from it
+        assert!(SystemState::load(&state_manager.overlay()).unwrap().staking.get_validator(&sender).is_none());
+        let staked_root = execution.state_root.clone();
+        state_manager.commit(execution.state).unwrap();
+        assert_eq!(state_manager.overlay().base_state_root(), block.header.state_root);
+        let committed = SystemState::load(&state_manager.overlay()).unwrap();
+        assert_eq!(committed.staking.get_validator(&sender).unwrap().stake, MIN_VALIDATOR_STAKE);
+
+        // Unstaked tokens stay in the pool until the lock period has passed
+        let txs = vec![
+            signed(&keypair, TransactionType::Unstake { amount: stake }, 3),
+            signed(&keypair, TransactionType::ClaimUnstaked, 4),
+        ];
+        let execution = executor.execute_block(txs, &ctx_at(2)).unwrap();
+        let statuses: Vec<bool> = execution.receipts.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![true, false]);
+        assert!(!execution.system.staking.get_validator(&sender).unwrap().active);
+
+        let unstake_block = sealed_block(&execution, &ctx_at(2));
+        state_manager.commit(execution.state).unwrap();
+
+        // Traces replay against the records of the block's parent, not the latest ones,
+        // where the stake is already gone
+        let latest = SystemState::load(&state_manager.overlay()).unwrap();
+        assert!(!latest.staking.get_validator(&sender).unwrap().active);
+        let unstake_hash = unstake_block.transactions[0].hash();
+        let traced = executor.trace_transaction(&unstake_block, &staked_root, &unstake_hash).unwrap();
+        assert!(traced.receipt.status);
+
+        let claim = vec![signed(&keypair, TransactionType::ClaimUnstaked, 5)];
+        let execution = executor.execute_block(claim, &ctx_at(2 + UNSTAKE_LOCK_PERIOD)).unwrap();
+        assert!(execution.receipts[0].status);
+        assert_eq!(execution.state.get_balance(STAKING_POOL_ACCOUNT).unwrap(), 0);
+        assert_eq!(execution.state.get_balance(&sender).unwrap(), initial_balance - 6 * 21000);
+
+        std::fs::remove_dir_all("./test_executor_staking_db").ok();
+    }
+
+    #[test]
+    fn test_block_rewards_and_proposal_updates() {
+        use governance::{ProposalStatus, ProposalType, REVIEW_PERIOD};
+        use staking::{BLOCK_REWARD, MIN_VALIDATOR_STAKE};
+
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_rewards_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let validator = ActKeyPair::generate();
+        let address = validator.address().to_string();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(address.clone(), 300_000.0)])
+            .unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx_at = |height| BlockContext {
+            height,
+            timestamp: 1_700_000_000 + height,
+            coinbase: address.clone(),
+            last_commit: None,
+        };
+        let stake = MIN_VALIDATOR_STAKE as ActAmount * 1_000_000_000;
+        let proposal_type = ProposalType::TextProposal { content: "Hello".to_string() };
+
+        let mut propose = Transaction {
+            gas_limit: 50_000,
+            ..signed(&validator, TransactionType::Propose { proposal_type, title: "T".to_string(), description: "D".to_string() }, 1)
+        };
+        propose.signature = validator.sign(&propose.signing_data());
+        let txs = vec![signed(&validator, TransactionType::Stake { amount: stake, commission_rate: 10 }, 0), propose];
+        let execution = executor.execute_block(txs, &ctx_at(1)).unwrap();
+        assert!(execution.receipts.iter().all(|r| r.status));
+
+        // The proposer's commission is recorded by the block itself, so validators replay it
+        let commission = BLOCK_REWARD / 10;
+        assert_eq!(execution.system.staking.get_validator(&address).unwrap().unclaimed_rewards, commission);
+        assert!(executor.validate_block(&sealed_block(&execution, &ctx_at(1))).is_ok());
+        state_manager.commit(execution.state).unwrap();
+
+        // Voting opens once the review period is over
+        let ctx = ctx_at(1 + REVIEW_PERIOD);
+        let execution = executor.execute_block(vec![signed(&validator, TransactionType::ClaimRewards, 2)], &ctx).unwrap();
+        assert!(execution.receipts[0].status);
+        assert_eq!(execution.system.governance.get_proposal(1).unwrap().status, ProposalStatus::Active);
+        assert_eq!(
+            execution.state.get_balance(&address).unwrap(),
+            state_manager.get_balance(&address).unwrap() + commission as ActAmount * 1_000_000_000
+        );
+
+        std::fs::remove_dir_all("./test_executor_rewards_db").ok();
+    }
+
+    #[test]
+    fn test_fees_are_not_minted() {
+        use staking::{BLOCK_REWARD, MIN_VALIDATOR_STAKE, STAKING_POOL_ACCOUNT};
+
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_fees_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let validator = ActKeyPair::generate();
+        let sender = ActKeyPair::generate();
+        let address = validator.address().to_string();
+        state_manager
+            .initialize_genesis(vec![
+                GenesisAccount::new(address.clone(), 200_000.0),
+                GenesisAccount::new(sender.address().to_string(), 1_000.0),
+            ])
+            .unwrap();
+        let accounts = [address.clone(), sender.address().to_string(), "ACT-receiver".to_string(), STAKING_POOL_ACCOUNT.to_string()];
+        let supply = |state: &StateOverlay| -> ActAmount { accounts.iter().map(|a| state.get_balance(a).unwrap()).sum() };
+        let genesis_supply = supply(&state_manager.overlay());
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx_at = |height| BlockContext {
+            height,
+            timestamp: 1_700_000_000 + height,
+            coinbase: address.clone(),
+            last_commit: None,
+        };
+        let stake = MIN_VALIDATOR_STAKE as ActAmount * 1_000_000_000;
+        let execution = executor
+            .execute_block(vec![signed(&validator, TransactionType::Stake { amount: stake, commission_rate: 10 }, 0)], &ctx_at(1))
+            .unwrap();
+        assert!(execution.receipts[0].status);
+        state_manager.commit(execution.state).unwrap();
+
+        // Fees large enough to show up in the records go to the proposer's balance, and only there
+        let gas_price: ActAmount = 1_000_000_000_000;
+        let priced = |nonce| {
+            let mut tx = Transaction { gas_price, ..signed_transfer(&sender, "ACT-receiver", 1, nonce) };
+            tx.signature = sender.sign(&tx.signing_data());
+            tx
+        };
+        let before = state_manager.get_balance(&address).unwrap();
+        let execution = executor.execute_block(vec![priced(0), priced(1), priced(2)], &ctx_at(2)).unwrap();
+        assert!(execution.receipts.iter().all(|r| r.status));
+        assert_eq!(execution.fees, 3 * 21000 * gas_price);
+        assert_eq!(execution.state.get_balance(&address).unwrap(), before + execution.fees);
+        assert_eq!(supply(&execution.state), genesis_supply);
+        let commission = BLOCK_REWARD / 10;
+        assert_eq!(execution.system.staking.get_validator(&address).unwrap().unclaimed_rewards, 2 * commission);
+        state_manager.commit(execution.state).unwrap();
+
+        // Claiming mints the block rewards of both blocks and nothing for the fees
+        let execution = executor.execute_block(vec![signed(&validator, TransactionType::ClaimRewards, 1)], &ctx_at(3)).unwrap();
+        assert!(execution.receipts[0].status);
+        assert_eq!(supply(&execution.state), genesis_supply + 2 * commission as ActAmount * 1_000_000_000);
+
+        std::fs::remove_dir_all("./test_executor_fees_db").ok();
+    }
+
+    #[test]
+    fn test_votes_weighed_at_proposal_snapshot() {
+        use governance::{ProposalType, VoteOption, REVIEW_PERIOD};
+
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_votes_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let holder = ActKeyPair::generate();
+        let friend = ActKeyPair::generate();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(holder.address().to_string(), 20_000.0)])
+            .unwrap();
+        let snapshot_balance = state_manager.get_balance(&holder.address().to_string()).unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx_at = |height| BlockContext {
+            height,
+            timestamp: 1_700_000_000 + height,
+            coinbase: "ACT-coinbase".to_string(),
+            last_commit: None,
+        };
+
+        let proposal_type = ProposalType::TextProposal { content: "Hello".to_string() };
+        let mut propose = Transaction {
+            gas_limit: 50_000,
+            ..signed(&holder, TransactionType::Propose { proposal_type, title: "T".to_string(), description: "D".to_string() }, 0)
+        };
+        propose.signature = holder.sign(&propose.signing_data());
+        let execution = executor.execute_block(vec![propose], &ctx_at(1)).unwrap();
+        assert!(execution.receipts[0].status);
+        state_manager.commit(execution.state).unwrap();
+
+        // Tokens handed on after the snapshot vote once, with the power they had there
+        let vote = |keypair: &ActKeyPair, nonce| signed(keypair, TransactionType::Vote { proposal_id: 1, option: VoteOption::Yes }, nonce);
+        let txs = vec![
+            signed_transfer(&holder, &friend.address().to_string(), 5_000 * 1_000_000_000_000_000_000, 1),
+            vote(&holder, 2),
+            vote(&friend, 0),
+        ];
+        let execution = executor.execute_block(txs, &ctx_at(1 + REVIEW_PERIOD)).unwrap();
+        assert!(execution.receipts.iter().all(|r| r.status));
+        let proposal = execution.system.governance.get_proposal(1).unwrap();
+        assert_eq!(proposal.yes_votes, (snapshot_balance / 1_000_000_000) as u64);
+
+        std::fs::remove_dir_all("./test_executor_votes_db").ok();
+    }
+
+    #[test]
+    fn test_double_signing_evidence() {
+        use staking::{MIN_VALIDATOR_STAKE, STAKING_POOL_ACCOUNT};
+        use types::consensus::{Evidence, Vote, VoteType};
+
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_evidence_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let validator = ActKeyPair::generate();
+        let reporter = ActKeyPair::generate();
+        state_manager
+            .initialize_genesis(vec![
+                GenesisAccount::new(validator.address().to_string(), 200_000.0),
+                GenesisAccount::new(reporter.address().to_string(), 1.0),
+            ])
+            .unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx = BlockContext {
+            height: 1,
+            timestamp: 1_700_000_001,
+            coinbase: "ACT-coinbase".to_string(),
+            last_commit: None,
+        };
+        let stake = MIN_VALIDATOR_STAKE as ActAmount * 1_000_000_000;
+
+        let first = Vote::new(&validator, 7, 0, VoteType::Precommit, Some("ab".repeat(32)));
+        let second = Vote::new(&validator, 7, 0, VoteType::Precommit, Some("cd".repeat(32)));
+        let evidence = Evidence::ConflictingVotes { first: first.clone(), second: second.clone() };
+        let swapped = Evidence::ConflictingVotes { first: second, second: first.clone() };
+        let third = Vote::new(&validator, 7, 0, VoteType::Precommit, None);
+        let other_pair = Evidence::ConflictingVotes { first: first.clone(), second: third };
+        let innocent = Evidence::ConflictingVotes { first: first.clone(), second: first };
+
+        let txs = vec![
+            signed(&validator, TransactionType::Stake { amount: stake, commission_rate: 10 }, 0),
+            signed(&reporter, TransactionType::ReportEvidence { evidence: innocent }, 0),
+            signed(&reporter, TransactionType::ReportEvidence { evidence: evidence.clone() }, 1),
+            // The same offence cannot be punished twice, whichever votes prove it
+            signed(&reporter, TransactionType::ReportEvidence { evidence: swapped }, 2),
+            signed(&reporter, TransactionType::ReportEvidence { evidence: other_pair }, 3),
+        ];
+        let execution = executor.execute_block(txs, &ctx).unwrap();
+        let statuses: Vec<bool> = execution.receipts.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![true, false, true, false, false]);
+
+        let slashed = MIN_VALIDATOR_STAKE / 10 * 3;
+        let record = execution.system.staking.get_validator(&validator.address().to_string()).unwrap();
+        assert_eq!(record.stake, MIN_VALIDATOR_STAKE - slashed);
+        assert_eq!(record.slash_events[0].evidence_hash, Some(evidence.hash()));
+        assert_eq!(record.slash_events[0].reporter, Some(reporter.address().to_string()));
+        assert_eq!(
+            execution.state.get_balance(STAKING_POOL_ACCOUNT).unwrap(),
+            stake - slashed as ActAmount * 1_000_000_000
+        );
+
+        std::fs::remove_dir_all("./test_executor_evidence_db").ok();
+    }
+
+    #[test]
+    fn test_downtime_jailing() {
+        use staking::{DOWNTIME_JAIL_PERIOD, MIN_VALIDATOR_STAKE, SIGNED_BLOCKS_WINDOW, STAKING_POOL_ACCOUNT};
+
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_downtime_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let validator = ActKeyPair::generate();
+        let address = validator.address().to_string();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(address.clone(), 300_000.0)])
+            .unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx_at = |height| BlockContext {
+            height,
+            timestamp: 1_700_000_000 + height,
+            coinbase: "ACT-coinbase".to_string(),
+            last_commit: None,
+        };
+        let stake = MIN_VALIDATOR_STAKE as ActAmount * 1_000_000_000;
+
+        let txs = vec![signed(&validator, TransactionType::Stake { amount: stake, commission_rate: 10 }, 0)];
+        let execution = executor.execute_block(txs, &ctx_at(1)).unwrap();
+        let mut system = execution.system;
+        state_manager.commit(execution.state).unwrap();
+
+        // Certificates its precommit never makes it into
+        let unsigned = CommitCertificate { height: 0, round: 0, block_hash: "ab".repeat(32), precommits: Vec::new() };
+        let mut overlay = state_manager.overlay();
+        let mut height = 2;
+        while system.staking.get_validator(&address).unwrap().jailed_until.is_none() {
+            assert!(height <= 2 + SIGNED_BLOCKS_WINDOW as u64, "never jailed");
+            system.begin_block(&mut overlay, height, Some(&unsigned)).unwrap();
+            height += 1;
+        }
+
+        let slashed = MIN_VALIDATOR_STAKE / 20;
+        assert_eq!(system.staking.get_validator(&address).unwrap().stake, MIN_VALIDATOR_STAKE - slashed);
+        assert_eq!(
+            overlay.get_balance(STAKING_POOL_ACCOUNT).unwrap(),
+            stake - slashed as ActAmount * 1_000_000_000
+        );
+        system.store(&mut overlay).unwrap();
+        state_manager.commit(overlay).unwrap();
+
+        // Unjailing waits out the jail period, and the slashed stake must be topped up
+        let execution = executor.execute_block(vec![signed(&validator, TransactionType::Unjail, 1)], &ctx_at(height)).unwrap();
+        assert!(!execution.receipts[0].status);
+
+        let back = height - 1 + DOWNTIME_JAIL_PERIOD;
+        let txs = vec![
+            signed(&validator, TransactionType::Unjail, 1),
+            signed(&validator, TransactionType::Stake { amount: stake, commission_rate: 10 }, 2),
+        ];
+        let execution = executor.execute_block(txs, &ctx_at(back)).unwrap();
+        assert!(execution.receipts.iter().all(|r| r.status));
+        assert!(execution.system.staking.get_validator(&address).unwrap().can_validate());
+
+        std::fs::remove_dir_all("./test_executor_downtime_db").ok();
+    }
+
+    #[test]
+    fn test_lowest_passing_limit() {
+        // Succeeding with a limit above the gas it reports using, as with gas held back for sub-calls
+        assert_eq!(lowest_passing_limit(21_000, 10_000_000, |limit| limit >= 30_123), 30_123);
+        assert_eq!(lowest_passing_limit(21_000, 21_001, |_| true), 21_001);
+    }
+
+    #[test]
+    fn test_estimate_contract_call_gas() {
+        let storage = Arc::new(BlockchainStorage::new("./test_executor_estimate_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+
+        let keypair = ActKeyPair::generate();
+        let sender = keypair.address().to_string();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(sender.clone(), 1000.0)])
+            .unwrap();
+
+        // `execute` loops 1000 times; `forever` never returns
+        let code = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "execute") (result i32)
+                    (local $i i32)
+                    (loop $again
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $again (i32.lt_u (local.get $i) (i32.const 1000))))
+                    (i32.const 0))
+                (func (export "forever") (result i32)
+                    (loop $forever (br $forever))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+        let mut overlay = state_manager.overlay();
+        let contract = overlay.deploy_contract("ACT-deployer", &code, 0).unwrap();
+        state_manager.commit(overlay).unwrap();
+
+        let mut executor = Executor::new(state_manager.clone(), GasConfig::default()).unwrap();
+        let ctx = BlockContext {
+            height: 1,
+            timestamp: 1_700_000_000,
+            coinbase: "ACT-coinbase".to_string(),
+            last_commit: None,
+        };
+        let call = |method: &str, gas_limit: u64| {
+            let tx_type = TransactionType::ContractCall { contract: contract.clone(), method: method.to_string(), args: Vec::new() };
+            let mut tx = signed(&keypair, tx_type, 0);
+            tx.gas_limit = gas_limit;
+            tx.signature = keypair.sign(&tx.signing_data());
+            tx
+        };
+
+        // The estimate is the smallest limit the call succeeds with
+        let estimate = executor.estimate_gas(&call("execute", 0), &[], &ctx, 1_000_000).unwrap();
+        assert!(estimate > GasConfig::default().contract_call_base);
+        let execution = executor.execute_block(vec![call("execute", estimate)], &ctx).unwrap();
+        assert!(execution.receipts[0].status);
+        let execution = executor.execute_block(vec![call("execute", estimate - 1)], &ctx).unwrap();
+        assert!(!execution.receipts[0].status);
+
+        // A call that fails with the most gas allowed has no estimate
+        let err = executor.estimate_gas(&call("forever", 0), &[], &ctx, 1_000_000).unwrap_err();
+        assert!(err.to_string().contains("maximum gas limit of 1000000"));
+
+        std::fs::remove_dir_all("./test_executor_estimate_db").ok();
+    }
+}
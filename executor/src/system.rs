@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Result};
+use governance::{GovernanceManager, ProposalStatus};
+use staking::{SlashReason, StakingManager, STAKING_POOL_ACCOUNT};
+use state::StateOverlay;
+use std::collections::HashSet;
+use types::consensus::CommitCertificate;
+use types::{ActAmount, TransactionType};
+
+/// Staking and governance records count tokens with 9 decimals, account balances with 18
+const RECORD_UNIT: ActAmount = 1_000_000_000;
+
+/// Supply that proposal quorums are measured against (13M ACT base supply, 9 decimals)
+const GOVERNANCE_TOTAL_SUPPLY: u64 = 13_000_000_000_000_000;
+
+/// Account whose storage holds the staking and governance records, so that the state
+/// root commits to them and they are persisted and versioned with the rest of the state
+pub const SYSTEM_RECORDS_ACCOUNT: &str = "ACT-system-records";
+
+const STAKING_RECORDS_KEY: &[u8] = b"staking";
+const GOVERNANCE_RECORDS_KEY: &[u8] = b"governance";
+
+/// Staking and governance records updated by system transactions.
+///
+/// The records are part of the account state: a block loads them from its parent state
+/// and writes them back before its state root is computed.
+#[derive(Clone)]
+pub struct SystemState {
+    pub staking: StakingManager,
+    pub governance: GovernanceManager,
+}
+
+impl SystemState {
+    pub fn new(staking: StakingManager, governance: GovernanceManager) -> Self {
+        Self { staking, governance }
+    }
+
+    /// Records as of the state `overlay` reads from; empty if none have been stored yet
+    pub fn load(overlay: &StateOverlay) -> Result<Self> {
+        let staking = match overlay.get_storage(SYSTEM_RECORDS_ACCOUNT, STAKING_RECORDS_KEY)? {
+            Some(bytes) => StakingManager::from_bytes(&bytes).map_err(|e| anyhow!("Invalid staking records: {}", e))?,
+            None => StakingManager::new(),
+        };
+        let governance = match overlay.get_storage(SYSTEM_RECORDS_ACCOUNT, GOVERNANCE_RECORDS_KEY)? {
+            Some(bytes) => GovernanceManager::from_bytes(&bytes).map_err(|e| anyhow!("Invalid governance records: {}", e))?,
+            None => GovernanceManager::new(),
+        };
+        Ok(Self { staking, governance })
+    }
+
+    /// Write the records into `overlay`
+    pub fn store(&self, overlay: &mut StateOverlay) -> Result<()> {
+        let staking = self.staking.to_bytes().map_err(|e| anyhow!("Failed to encode staking records: {}", e))?;
+        let governance = self.governance.to_bytes().map_err(|e| anyhow!("Failed to encode governance records: {}", e))?;
+        overlay.set_storage(SYSTEM_RECORDS_ACCOUNT, STAKING_RECORDS_KEY, &staking);
+        overlay.set_storage(SYSTEM_RECORDS_ACCOUNT, GOVERNANCE_RECORDS_KEY, &governance);
+        Ok(())
+    }
+
+    pub fn set_block_height(&mut self, height: u64) {
+        self.staking.set_block_height(height);
+        self.governance.set_block_height(height);
+    }
+
+    /// Move the records to block `height`: open or close voting on proposals whose review or
+    /// voting period is over, and count the parent's signers from `last_commit`.
+    /// Stake slashed from validators jailed for downtime leaves the pool.
+    pub(crate) fn begin_block(&mut self, overlay: &mut StateOverlay, height: u64, last_commit: Option<&CommitCertificate>) -> Result<()> {
+        self.governance.set_block_height(height);
+        let open: Vec<u64> = self
+            .governance
+            .list_proposals(None)
+            .iter()
+            .filter(|p| matches!(p.status, ProposalStatus::Review | ProposalStatus::Active))
+            .map(|p| p.id)
+            .collect();
+        for proposal_id in open {
+            // Only fails for unknown proposals
+            let _ = self.governance.update_proposal_status(proposal_id);
+        }
+
+        let signers: Option<HashSet<String>> = last_commit.map(|c| c.signers().map(|a| a.to_string()).collect());
+        for (_, slashed) in self.staking.begin_block(height, signers.as_ref()) {
+            overlay.debit(STAKING_POOL_ACCOUNT, from_record_units(slashed))?;
+        }
+        Ok(())
+    }
+
+    /// Close the block: credit the proposer's block reward, issued when claimed.
+    /// Its fees are not counted here: they already went to its balance as the coinbase.
+    pub(crate) fn end_block(&mut self, proposer: &str) {
+        self.staking.distribute_block_reward(proposer, 0);
+    }
+
+    /// Voting power of `address`, in record units: its balance plus the stake it holds as a
+    /// validator or delegator
+    pub fn voting_power(&self, address: &str, balance: ActAmount) -> u64 {
+        let validator_stake = self
+            .staking
+            .get_validator(address)
+            .map(|v| v.total_stake())
+            .unwrap_or(0);
+        let delegated: u64 = self.staking.get_delegations(address).iter().map(|d| d.amount).sum();
+
+        balance_in_record_units(balance)
+            .saturating_add(validator_stake)
+            .saturating_add(delegated)
+    }
+
+    /// Apply a staking or governance transaction sent by `sender`.
+    /// The records are left untouched on `Err`; the caller reverts `overlay`.
+    pub(crate) fn apply(&mut self, overlay: &mut StateOverlay, sender: &str, tx_type: &TransactionType) -> Result<()> {
+        let mut next = self.clone();
+        next.dispatch(overlay, sender, tx_type)?;
+        *self = next;
+        Ok(())
+    }
+
+    fn dispatch(&mut self, overlay: &mut StateOverlay, sender: &str, tx_type: &TransactionType) -> Result<()> {
+        match tx_type {
+            TransactionType::Stake { amount, commission_rate } => {
+                self.staking
+                    .stake(sender.to_string(), to_record_units(*amount)?, *commission_rate)
+                    .map_err(|e| anyhow!("Stake failed: {}", e))?;
+                overlay.transfer(sender, STAKING_POOL_ACCOUNT, *amount)
+            }
+            TransactionType::Unstake { amount } => {
+                self.staking
+                    .unstake(sender.to_string(), to_record_units(*amount)?)
+                    .map_err(|e| anyhow!("Unstake failed: {}", e))?;
+                Ok(())
+            }
+            TransactionType::Delegate { validator, amount } => {
+                self.staking
+                    .delegate(sender.to_string(), validator.clone(), to_record_units(*amount)?)
+                    .map_err(|e| anyhow!("Delegation failed: {}", e))?;
+                overlay.transfer(sender, STAKING_POOL_ACCOUNT, *amount)
+            }
+            TransactionType::Undelegate { validator, amount } => {
+                self.staking
+                    .undelegate(sender.to_string(), validator.clone(), to_record_units(*amount)?)
+                    .map_err(|e| anyhow!("Undelegation failed: {}", e))?;
+                Ok(())
+            }
+            TransactionType::ClaimUnstaked => {
+                let amount = self
+                    .staking
+                    .claim_unstaked(sender.to_string())
+                    .map_err(|e| anyhow!("Claim failed: {}", e))?;
+                overlay.transfer(STAKING_POOL_ACCOUNT, sender, from_record_units(amount))
+            }
+            TransactionType::ClaimRewards => {
+                let rewards = self
+                    .staking
+                    .claim_rewards(sender.to_string())
+                    .map_err(|e| anyhow!("Claim rewards failed: {}", e))?;
+                // Block rewards are issued when claimed
+                overlay.credit(sender, from_record_units(rewards))
+            }
+            TransactionType::Propose { proposal_type, title, description } => {
+                let balance = overlay.get_balance(sender)?;
+                let proposal_id = self
+                    .governance
+                    .create_proposal(
+                        sender.to_string(),
+                        proposal_type.clone(),
+                        title.clone(),
+                        description.clone(),
+                        balance_in_record_units(balance),
+                        GOVERNANCE_TOTAL_SUPPLY,
+                    )
+                    .map_err(|e| anyhow!("Proposal creation failed: {}", e))?;
+                // Votes are weighed by the state the block started from
+                self.governance
+                    .set_snapshot_state_root(proposal_id, overlay.base_state_root())
+                    .map_err(|e| anyhow!("Proposal creation failed: {}", e))
+            }
+            TransactionType::Vote { proposal_id, option } => {
+                // Power held when the proposal was made, so tokens cannot vote again once moved
+                let snapshot_root = self
+                    .governance
+                    .get_proposal(*proposal_id)
+                    .and_then(|p| p.snapshot_state_root)
+                    .ok_or_else(|| anyhow!("Vote failed: proposal {} has no voting snapshot", proposal_id))?;
+                let snapshot = overlay.overlay_at(&snapshot_root)?;
+                let power = SystemState::load(&snapshot)?.voting_power(sender, snapshot.get_balance(sender)?);
+                self.governance
+                    .cast_vote(*proposal_id, sender.to_string(), option.clone(), power)
+                    .map_err(|e| anyhow!("Vote failed: {}", e))
+            }
+            TransactionType::ReportEvidence { evidence } => {
+                evidence.verify().map_err(|e| anyhow!("Invalid evidence: {}", e))?;
+                let evidence_hash = evidence.hash();
+                self.staking
+                    .check_evidence(&evidence_hash, evidence.height())
+                    .map_err(|e| anyhow!("Evidence rejected: {}", e))?;
+                let slashed = self
+                    .staking
+                    .slash(
+                        evidence.validator().to_string(),
+                        SlashReason::DoubleSigning,
+                        Some(sender.to_string()),
+                        Some(evidence_hash),
+                    )
+                    .map_err(|e| anyhow!("Slash failed: {}", e))?;
+                // Slashed stake leaves the pool for good
+                overlay.debit(STAKING_POOL_ACCOUNT, from_record_units(slashed))
+            }
+            TransactionType::Unjail => self
+                .staking
+                .unjail(sender.to_string())
+                .map_err(|e| anyhow!("Unjail failed: {}", e)),
+            _ => Err(anyhow!("Not a staking or governance transaction")),
+        }
+    }
+}
+
+/// Convert a staked amount exactly; the digits records cannot hold must be zero
+fn to_record_units(amount: ActAmount) -> Result<u64> {
+    if !amount.is_multiple_of(RECORD_UNIT) {
+        return Err(anyhow!("Stake amount {} is not a whole number of 10^-9 ACT", amount));
+    }
+    u64::try_from(amount / RECORD_UNIT).map_err(|_| anyhow!("Stake amount {} too large", amount))
+}
+
+fn from_record_units(amount: u64) -> ActAmount {
+    amount as ActAmount * RECORD_UNIT
+}
+
+fn balance_in_record_units(balance: ActAmount) -> u64 {
+    u64::try_from(balance / RECORD_UNIT).unwrap_or(u64::MAX)
+}
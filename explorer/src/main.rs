@@ -28,10 +28,32 @@ pub struct BlockInfo {
     pub timestamp: u64,
     pub validator: String,
     pub transaction_count: usize,
+    pub transactions: Vec<String>,
     pub state_root: String,
+    pub tx_root: String,
     pub reward: u128,
 }
 
+impl From<types::Block> for BlockInfo {
+    fn from(block: types::Block) -> Self {
+        let hash = block.hash();
+        let transactions: Vec<String> = block.transactions.iter().map(|tx| tx.hash()).collect();
+        
+        Self {
+            height: block.header.height,
+            hash,
+            parent_hash: block.header.parent_hash,
+            timestamp: block.header.timestamp,
+            validator: block.header.actor_pubkey,
+            transaction_count: transactions.len(),
+            transactions,
+            state_root: block.header.state_root,
+            tx_root: block.header.tx_root,
+            reward: block.header.reward,
+        }
+    }
+}
+
 /// Transaction information response
 #[derive(Debug, Serialize)]
 pub struct TransactionInfo {
@@ -128,18 +150,7 @@ async fn get_latest_blocks(
     let start = latest_height.saturating_sub(9);
     for height in (start..=latest_height).rev() {
         match state.rpc_client.get_block_by_number(height).await {
-            Ok(Some(block)) => {
-                blocks.push(BlockInfo {
-                    height: block.height,
-                    hash: block.state_root.clone(), // Using state_root as block identifier
-                    parent_hash: block.parent_hash,
-                    timestamp: block.timestamp,
-                    validator: block.actor_pubkey,
-                    transaction_count: 0, // Would need full block with txs
-                    state_root: block.state_root,
-                    reward: block.reward,
-                });
-            }
+            Ok(Some(block)) => blocks.push(BlockInfo::from(block)),
             Ok(None) => break,
             Err(_) => break,
         }
@@ -155,18 +166,7 @@ async fn get_block(
 ) -> Result<Json<BlockInfo>, AppError> {
     // Query actual block from RPC
     match state.rpc_client.get_block_by_number(height).await {
-        Ok(Some(block)) => {
-            Ok(Json(BlockInfo {
-                height: block.height,
-                hash: block.state_root.clone(),
-                parent_hash: block.parent_hash,
-                timestamp: block.timestamp,
-                validator: block.actor_pubkey,
-                transaction_count: 0, // Would need full block with txs
-                state_root: block.state_root,
-                reward: block.reward,
-            }))
-        }
+        Ok(Some(block)) => Ok(Json(BlockInfo::from(block))),
         Ok(None) => Err(AppError::NotFound(format!("Block {} not found", height))),
         Err(e) => Err(AppError::Internal(format!("RPC error: {}", e))),
     }
@@ -180,12 +180,7 @@ async fn get_transaction(
     // Try to get from RPC
     match state.rpc_client.get_transaction(&hash).await {
         Ok(Some(tx)) => {
-            let tx_type = match tx.tx_type {
-                types::TransactionType::Transfer { .. } => "Transfer",
-                types::TransactionType::ContractDeploy { .. } => "ContractDeploy",
-                types::TransactionType::ContractCall { .. } => "ContractCall",
-                types::TransactionType::EthereumLegacy { .. } => "EthereumLegacy",
-            };
+            let tx_type = tx_type_name(&tx.tx_type);
             
             // Try to get receipt for block height
             let (block_height, status) = match state.rpc_client.get_transaction_receipt(&hash).await {
@@ -267,15 +262,19 @@ async fn search(
             }))),
             Err(_) => Err(AppError::NotFound("Account not found".to_string())),
         }
-    } else if query.starts_with("0x") {
-        // Search for transaction hash
-        match state.rpc_client.get_transaction(&query).await {
+    } else if query.starts_with("0x") || query.len() == 64 {
+        // Search for block hash first, then transaction hash
+        if let Ok(Some(block)) = state.rpc_client.get_block_by_hash(&query).await {
+            return Ok(Json(SearchResult::Block(BlockInfo::from(block))));
+        }
+        
+        match state.rpc_client.get_transaction(query.trim_start_matches("0x")).await {
             Ok(Some(tx)) => {
-                let tx_type = match tx.tx_type {
-                    types::TransactionType::Transfer { .. } => "Transfer",
-                    types::TransactionType::ContractDeploy { .. } => "ContractDeploy",
-                    types::TransactionType::ContractCall { .. } => "ContractCall",
-                    types::TransactionType::EthereumLegacy { .. } => "EthereumLegacy",
+                let tx_type = tx_type_name(&tx.tx_type);
+                
+                let (block_height, status) = match state.rpc_client.get_transaction_receipt(query.trim_start_matches("0x")).await {
+                    Ok(Some(receipt)) => (Some(receipt.block_height), if receipt.status { "Success" } else { "Failed" }),
+                    _ => (None, "Pending"),
                 };
                 
                 Ok(Json(SearchResult::Transaction(TransactionInfo {
@@ -285,8 +284,8 @@ async fn search(
                     gas_limit: tx.gas_limit,
                     gas_price: tx.gas_price,
                     nonce: tx.nonce,
-                    block_height: None,
-                    status: "Pending".to_string(),
+                    block_height,
+                    status: status.to_string(),
                 })))
             }
             Ok(None) => Err(AppError::NotFound("Transaction not found".to_string())),
@@ -294,22 +293,36 @@ async fn search(
         }
     } else if let Ok(height) = query.parse::<u64>() {
         // Search for block by height
-        let block = BlockInfo {
-            height,
-            hash: format!("0xblock{}", height),
-            parent_hash: format!("0xblock{}", height.saturating_sub(1)),
-            timestamp: 1700000000 + (height * 30),
-            validator: "ACT-validator1...".to_string(),
-            transaction_count: 3,
-            state_root: "0xstate...".to_string(),
-            reward: 1000000000000000000,
-        };
-        Ok(Json(SearchResult::Block(block)))
+        match state.rpc_client.get_block_by_number(height).await {
+            Ok(Some(block)) => Ok(Json(SearchResult::Block(BlockInfo::from(block)))),
+            Ok(None) => Err(AppError::NotFound(format!("Block {} not found", height))),
+            Err(e) => Err(AppError::Internal(format!("RPC error: {}", e))),
+        }
     } else {
         Err(AppError::BadRequest("Invalid search query".to_string()))
     }
 }
 
+/// Display name of a transaction's kind
+fn tx_type_name(tx_type: &types::TransactionType) -> &'static str {
+    match tx_type {
+        types::TransactionType::Transfer { .. } => "Transfer",
+        types::TransactionType::ContractDeploy { .. } => "ContractDeploy",
+        types::TransactionType::ContractCall { .. } => "ContractCall",
+        types::TransactionType::EthereumLegacy { .. } => "EthereumLegacy",
+        types::TransactionType::Stake { .. } => "Stake",
+        types::TransactionType::Unstake { .. } => "Unstake",
+        types::TransactionType::Delegate { .. } => "Delegate",
+        types::TransactionType::Undelegate { .. } => "Undelegate",
+        types::TransactionType::ClaimUnstaked => "ClaimUnstaked",
+        types::TransactionType::ClaimRewards => "ClaimRewards",
+        types::TransactionType::Propose { .. } => "Propose",
+        types::TransactionType::Vote { .. } => "Vote",
+        types::TransactionType::ReportEvidence { .. } => "ReportEvidence",
+        types::TransactionType::Unjail => "Unjail",
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 enum SearchResult {
@@ -30,6 +30,8 @@ struct JsonRpcResponse {
 struct JsonRpcError {
     code: i32,
     message: String,
+    #[serde(default)]
+    data: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -84,7 +86,10 @@ impl NodeRpcClient {
         let rpc_response: JsonRpcResponse = response.json().await?;
         
         if let Some(error) = rpc_response.error {
-            return Err(anyhow::anyhow!("RPC error {}: {}", error.code, error.message));
+            return Err(match error.data {
+                Some(data) => anyhow::anyhow!("RPC error {}: {} ({})", error.code, error.message, data),
+                None => anyhow::anyhow!("RPC error {}: {}", error.code, error.message),
+            });
         }
         
         rpc_response.result.ok_or_else(|| anyhow::anyhow!("No result in RPC response"))
@@ -137,14 +142,25 @@ impl NodeRpcClient {
         Ok(block_num)
     }
     
-    pub async fn get_block_by_number(&self, height: u64) -> Result<Option<types::BlockHeader>> {
+    pub async fn get_block_by_number(&self, height: u64) -> Result<Option<types::Block>> {
         let result = self.call("act_getBlock", json!([height])).await?;
         
         if result.is_null() {
             return Ok(None);
         }
         
-        let block: types::BlockHeader = serde_json::from_value(result)?;
+        let block: types::Block = serde_json::from_value(result)?;
+        Ok(Some(block))
+    }
+    
+    pub async fn get_block_by_hash(&self, hash: &str) -> Result<Option<types::Block>> {
+        let result = self.call("act_getBlockByHash", json!([hash])).await?;
+        
+        if result.is_null() {
+            return Ok(None);
+        }
+        
+        let block: types::Block = serde_json::from_value(result)?;
         Ok(Some(block))
     }
     
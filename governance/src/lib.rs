@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+// Carried by governance transactions, so they are defined with the transaction types
+pub use types::governance::{ProposalType, ValidatorActionType, VoteOption};
+
 /// Proposal deposit (1,000 ACT)
 pub const PROPOSAL_DEPOSIT: u64 = 1_000_000_000_000;
 
@@ -34,40 +37,6 @@ pub const CRITICAL_THRESHOLD: f64 = 0.66;
 /// Emergency approval threshold (>75%)
 pub const EMERGENCY_THRESHOLD: f64 = 0.75;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum ProposalType {
-    ParameterChange {
-        parameter: String,
-        old_value: String,
-        new_value: String,
-    },
-    TreasurySpend {
-        recipient: String,
-        amount: u64,
-        purpose: String,
-    },
-    ValidatorAction {
-        action: ValidatorActionType,
-        validator_address: String,
-        reason: String,
-    },
-    UpgradeProposal {
-        version: String,
-        activation_height: u64,
-        description: String,
-    },
-    TextProposal {
-        content: String,
-    },
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum ValidatorActionType {
-    Remove,
-    Slash,
-    Pardon,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProposalStatus {
     Review,
@@ -99,6 +68,8 @@ pub struct Proposal {
     pub total_supply_snapshot: u64,
     pub executed: bool,
     pub execution_result: Option<String>,
+    #[serde(default)]
+    pub snapshot_state_root: Option<String>,  // State the proposal was made on; votes carry the power held there
 }
 
 impl Proposal {
@@ -129,6 +100,7 @@ impl Proposal {
             total_supply_snapshot: total_supply,
             executed: false,
             execution_result: None,
+            snapshot_state_root: None,
         }
     }
 
@@ -172,13 +144,6 @@ impl Proposal {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum VoteOption {
-    Yes,
-    No,
-    Abstain,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vote {
     pub proposal_id: u64,
@@ -204,6 +169,7 @@ pub struct TallyResult {
     pub passed: bool,
 }
 
+#[derive(Clone)]
 pub struct GovernanceManager {
     proposals: HashMap<u64, Proposal>,
     votes: HashMap<u64, HashMap<String, Vote>>, // proposal_id -> (voter -> vote)
@@ -449,6 +415,16 @@ impl GovernanceManager {
         Ok(())
     }
 
+    /// Fix the state whose balances and stake votes on `proposal_id` are weighed by
+    pub fn set_snapshot_state_root(&mut self, proposal_id: u64, state_root: String) -> Result<(), String> {
+        let proposal = self
+            .proposals
+            .get_mut(&proposal_id)
+            .ok_or("Proposal not found")?;
+        proposal.snapshot_state_root = Some(state_root);
+        Ok(())
+    }
+
     /// Get proposal by ID
     pub fn get_proposal(&self, proposal_id: u64) -> Option<Proposal> {
         self.proposals.get(&proposal_id).cloned()
@@ -744,11 +720,14 @@ pub struct GovernanceState {
     pub proposals: Vec<Proposal>,
     pub votes: Vec<(u64, String, Vote)>, // (proposal_id, voter, vote)
     pub next_proposal_id: u64,
+    #[serde(default)]
+    pub current_height: u64,
 }
 
 // Persistence methods for GovernanceManager
 impl GovernanceManager {
-    /// Serialize governance state to JSON for persistence
+    /// Serialize governance state to JSON for persistence.
+    /// Proposals and votes are ordered, so equal states always serialize to the same bytes.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         let mut votes_flat = Vec::new();
         for (proposal_id, vote_map) in &self.votes {
@@ -756,11 +735,15 @@ impl GovernanceManager {
                 votes_flat.push((*proposal_id, voter.clone(), vote.clone()));
             }
         }
+        votes_flat.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        let mut proposals: Vec<Proposal> = self.proposals.values().cloned().collect();
+        proposals.sort_by_key(|p| p.id);
         
         let state = GovernanceState {
-            proposals: self.proposals.values().cloned().collect(),
+            proposals,
             votes: votes_flat,
             next_proposal_id: self.next_proposal_id,
+            current_height: self.current_height,
         };
         serde_json::to_string(&state)
     }
@@ -771,7 +754,9 @@ impl GovernanceManager {
         let mut manager = GovernanceManager::new();
         
         manager.next_proposal_id = state.next_proposal_id;
+        manager.current_height = state.current_height;
         for proposal in state.proposals {
+            manager.votes.insert(proposal.id, HashMap::new());
             manager.proposals.insert(proposal.id, proposal);
         }
         for (proposal_id, voter, vote) in state.votes {
@@ -1,15 +1,26 @@
 use anyhow::{anyhow, Result};
-use crypto::verify_signature;
 use state::StateManager;
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, RwLock};
-use types::{ActAmount, Transaction};
+use tokio::sync::broadcast;
+use types::consensus::Evidence;
+use types::{ActAmount, Transaction, TransactionType};
+
+/// Smallest gas limit the mempool accepts
+pub const MIN_GAS_LIMIT: u64 = 21000;
+
+/// Largest gas limit the mempool accepts
+pub const MAX_GAS_LIMIT: u64 = 10_000_000;
+
+/// Newly admitted transactions buffered for subscribers that have not caught up yet
+pub const PENDING_EVENTS_CAPACITY: usize = 1024;
 
 /// Transaction mempool for pending transactions
 pub struct Mempool {
     pending: Arc<RwLock<HashMap<String, VecDeque<Transaction>>>>, // address -> txs
     by_hash: Arc<RwLock<HashMap<String, Transaction>>>,            // tx_hash -> tx
     max_size: usize,
+    admitted: broadcast::Sender<Arc<Transaction>>,
 }
 
 impl Mempool {
@@ -18,9 +29,15 @@ impl Mempool {
             pending: Arc::new(RwLock::new(HashMap::new())),
             by_hash: Arc::new(RwLock::new(HashMap::new())),
             max_size,
+            admitted: broadcast::channel(PENDING_EVENTS_CAPACITY).0,
         }
     }
 
+    /// Receive every transaction admitted from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Transaction>> {
+        self.admitted.subscribe()
+    }
+
     /// Add transaction to mempool
     pub fn add_transaction(
         &self,
@@ -40,10 +57,10 @@ impl Mempool {
             }
         }
         
-        // Check mempool size
+        // Check mempool size; verified evidence is admitted regardless
         {
             let by_hash = self.by_hash.read().unwrap();
-            if by_hash.len() >= self.max_size {
+            if by_hash.len() >= self.max_size && evidence_of(&tx).is_none() {
                 return Err(anyhow!("Mempool is full"));
             }
         }
@@ -58,9 +75,12 @@ impl Mempool {
                 .or_insert_with(VecDeque::new)
                 .push_back(tx.clone());
             
-            by_hash.insert(tx_hash.clone(), tx);
+            by_hash.insert(tx_hash.clone(), tx.clone());
         }
         
+        // Nobody may be listening
+        let _ = self.admitted.send(Arc::new(tx));
+        
         println!("📥 Transaction added to mempool: {}", &tx_hash[..8]);
         
         Ok(tx_hash)
@@ -73,10 +93,20 @@ impl Mempool {
         state_manager: &StateManager,
     ) -> Result<()> {
         // Check signature
-        if !self.verify_transaction_signature(tx)? {
+        if !tx.verify_signature() {
             return Err(anyhow!("Invalid transaction signature"));
         }
         
+        // Evidence goes ahead of every other transaction, so it must hold up and be new
+        if let Some(evidence) = evidence_of(tx) {
+            evidence.verify().map_err(|e| anyhow!("Invalid evidence: {}", e))?;
+            let evidence_hash = evidence.hash();
+            let by_hash = self.by_hash.read().unwrap();
+            if by_hash.values().filter_map(evidence_of).any(|pending| pending.hash() == evidence_hash) {
+                return Err(anyhow!("Evidence already pending"));
+            }
+        }
+        
         // Check nonce
         let current_nonce = state_manager.get_nonce(&tx.from.to_string())?;
         if tx.nonce < current_nonce {
@@ -98,38 +128,21 @@ impl Mempool {
         }
         
         // Check gas limit
-        if tx.gas_limit < 21000 {
+        if tx.gas_limit < MIN_GAS_LIMIT {
             return Err(anyhow!("Gas limit too low"));
         }
-        if tx.gas_limit > 10_000_000 {
+        if tx.gas_limit > MAX_GAS_LIMIT {
             return Err(anyhow!("Gas limit too high"));
         }
         
         Ok(())
     }
 
-    /// Verify transaction signature
-    fn verify_transaction_signature(&self, tx: &Transaction) -> Result<bool> {
-        // Reconstruct signed data
-        let tx_data = serde_json::to_vec(&(
-            &tx.from,
-            &tx.nonce,
-            &tx.tx_type,
-            &tx.gas_limit,
-            &tx.gas_price,
-        ))?;
-        
-        verify_signature(&tx.pubkey, &tx_data, &tx.signature)
-    }
-
     /// Calculate total cost (amount + gas)
     fn calculate_total_cost(&self, tx: &Transaction) -> ActAmount {
         let gas_cost = tx.gas_limit as u128 * tx.gas_price;
         
-        match &tx.tx_type {
-            types::TransactionType::Transfer { amount, .. } => amount + gas_cost,
-            _ => gas_cost,
-        }
+        tx.tx_type.value() + gas_cost
     }
 
     /// Get transaction by hash
@@ -147,7 +160,8 @@ impl Mempool {
             .unwrap_or_default()
     }
 
-    /// Get next transactions to include in block (sorted by gas price)
+    /// Get next transactions to include in block: evidence first, so it lands before its
+    /// unbonding window closes whatever it pays, then by gas price
     pub fn get_transactions_for_block(
         &self,
         max_count: usize,
@@ -160,10 +174,11 @@ impl Mempool {
             .flat_map(|queue| queue.iter().cloned())
             .collect();
         
-        // Sort by gas price (descending) and nonce (ascending)
+        // Sort evidence first, then by gas price (descending) and nonce (ascending)
         all_txs.sort_by(|a, b| {
-            b.gas_price
-                .cmp(&a.gas_price)
+            evidence_of(b).is_some()
+                .cmp(&evidence_of(a).is_some())
+                .then_with(|| b.gas_price.cmp(&a.gas_price))
                 .then_with(|| a.nonce.cmp(&b.nonce))
         });
         
@@ -243,6 +258,14 @@ impl Mempool {
     }
 }
 
+/// Evidence of double signing a transaction reports, if it is a report
+fn evidence_of(tx: &Transaction) -> Option<&Evidence> {
+    match &tx.tx_type {
+        TransactionType::ReportEvidence { evidence } => Some(evidence),
+        _ => None,
+    }
+}
+
 /// Mempool statistics
 #[derive(Debug, Clone)]
 pub struct MempoolStats {
@@ -257,12 +280,26 @@ mod tests {
     use crypto::ActKeyPair;
     use state::GenesisAccount;
     use storage::BlockchainStorage;
-    use types::TransactionType;
+    use types::consensus::{Vote, VoteType};
+
+    fn signed(keypair: &ActKeyPair, tx_type: TransactionType, gas_price: ActAmount) -> Transaction {
+        let mut tx = Transaction {
+            from: keypair.address().clone(),
+            nonce: 0,
+            tx_type,
+            gas_limit: MIN_GAS_LIMIT,
+            gas_price,
+            signature: Vec::new(),
+            pubkey: keypair.public_key(),
+        };
+        tx.signature = keypair.sign(&tx.signing_data());
+        tx
+    }
 
     #[test]
     fn test_mempool_add_transaction() {
         let storage = Arc::new(BlockchainStorage::new("./test_mempool_db").unwrap());
-        let state_manager = StateManager::new(storage);
+        let state_manager = StateManager::new(storage).unwrap();
         
         let keypair = ActKeyPair::generate();
         let address = keypair.address().to_string();
@@ -273,7 +310,7 @@ mod tests {
         let mempool = Mempool::new(1000);
         
         let tx = Transaction {
-            from: address,
+            from: keypair.address().clone(),
             nonce: 0,
             tx_type: TransactionType::Transfer {
                 to: "ACT-receiver".to_string(),
@@ -291,4 +328,42 @@ mod tests {
         
         std::fs::remove_dir_all("./test_mempool_db").ok();
     }
+
+    #[test]
+    fn test_evidence_goes_first() {
+        let storage = Arc::new(BlockchainStorage::new("./test_mempool_evidence_db").unwrap());
+        let state_manager = StateManager::new(storage).unwrap();
+        
+        let sender = ActKeyPair::generate();
+        let reporter = ActKeyPair::generate();
+        let offender = ActKeyPair::generate();
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new(sender.address().to_string(), 1000.0)])
+            .unwrap();
+        
+        // Full once the well-paying transfer is in
+        let mempool = Mempool::new(1);
+        let transfer = TransactionType::Transfer { to: "ACT-receiver".to_string(), amount: 1 };
+        mempool.add_transaction(signed(&sender, transfer, 1_000_000_000), &state_manager).unwrap();
+        
+        let vote = |block_hash| Vote::new(&offender, 5, 0, VoteType::Precommit, block_hash);
+        let report = |first, second| {
+            let evidence = Evidence::ConflictingVotes { first, second };
+            signed(&reporter, TransactionType::ReportEvidence { evidence }, 0)
+        };
+        
+        // Verified evidence is admitted to a full mempool and included first, though it pays nothing
+        let evidence = report(vote(Some("ab".repeat(32))), vote(None));
+        mempool.add_transaction(evidence.clone(), &state_manager).unwrap();
+        let next = mempool.get_transactions_for_block(1, &state_manager);
+        assert_eq!(next[0].hash(), evidence.hash());
+        
+        // The same offence is only pending once, and messages that do not conflict are no evidence
+        let again = report(vote(Some("cd".repeat(32))), vote(None));
+        assert!(mempool.add_transaction(again, &state_manager).unwrap_err().to_string().contains("already pending"));
+        let bogus = report(vote(None), vote(None));
+        assert!(mempool.add_transaction(bogus, &state_manager).unwrap_err().to_string().contains("Invalid evidence"));
+        
+        std::fs::remove_dir_all("./test_mempool_evidence_db").ok();
+    }
 }
@@ -0,0 +1,544 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+use consensus::{Action, ConsensusEngine, ConsensusMessage, Proposal, Step, Tendermint, Timeout};
+use crypto::ActKeyPair;
+use executor::{BlockContext, BlockExecution, Executor, SystemState};
+use mempool::Mempool;
+use rpc::{metrics, RpcState};
+use state::StateManager;
+use types::consensus::{CertifiedBlock, CommitCertificate, Evidence};
+use types::{Block, Transaction, TransactionType};
+
+/// Pause after a block is final before agreeing on the next one, so transactions gather
+pub const BLOCK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Messages for later heights kept until we get there
+const MAX_FUTURE_MESSAGES: usize = 1_000;
+
+/// Finalized blocks ahead of our tip kept while the ones before them are fetched
+const MAX_AHEAD_BLOCKS: usize = 1_000;
+
+/// Blocks a peer sends back for one catch-up request
+pub const MAX_SYNC_BLOCKS: u64 = 100;
+
+/// Wait before asking peers again for blocks that have not arrived
+const SYNC_RETRY: Duration = Duration::from_secs(5);
+
+/// Where evidence of double signing is saved, for `act-wallet report-evidence`
+const EVIDENCE_DIR: &str = "./actchain_evidence";
+
+/// Inputs to the consensus task
+#[derive(Debug)]
+pub enum ConsensusEvent {
+    StartHeight,
+    Message(ConsensusMessage),
+    Timeout(Timeout),
+    Block(Box<CertifiedBlock>),  // Finalized block gossiped by a peer
+}
+
+/// What the consensus task asks the network loop to publish
+#[derive(Debug)]
+pub enum Gossip {
+    Consensus(ConsensusMessage),
+    Block(Box<CertifiedBlock>),
+    Transaction(Box<Transaction>),
+    RequestBlocks { from: u64 },  // We are behind; ask peers for certified blocks from `from`
+}
+
+/// Catch-up messages on the sync topic. Both carry the request's nonce, so a repeated
+/// request and its answers are not dropped as duplicates of earlier ones.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SyncMessage {
+    Request { from: u64, nonce: u64 },
+    Block { nonce: u64, block: Box<CertifiedBlock> },
+}
+
+/// Finalized blocks from peers that are ahead of our tip, applied once the blocks before
+/// them arrive
+#[derive(Default)]
+struct Catchup {
+    ahead: BTreeMap<u64, CertifiedBlock>,
+    requested: Option<Instant>,  // When we last asked peers for the missing blocks
+}
+
+/// Key this node signs consensus messages with: ACT_VALIDATOR_KEY (hex 32-byte seed) if
+/// set, else the key file at `path`, created on first start
+pub fn load_validator_key(path: &Path) -> Result<ActKeyPair, Box<dyn Error>> {
+    if let Ok(seed) = std::env::var("ACT_VALIDATOR_KEY") {
+        let seed: [u8; 32] = hex::decode(seed.trim().trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| "ACT_VALIDATOR_KEY must be a 32-byte hex seed")?;
+        return Ok(ActKeyPair::from_seed(&seed));
+    }
+
+    if path.exists() {
+        return Ok(serde_json::from_slice(&std::fs::read(path)?)?);
+    }
+    let keypair = ActKeyPair::generate();
+    std::fs::write(path, serde_json::to_vec(&keypair)?)?;
+    println!("🔑 Generated validator key {}", path.display());
+    Ok(keypair)
+}
+
+/// Runs `Tendermint` for this node: validates and executes proposals, builds blocks when
+/// it is our turn, and applies every block the validators finalize
+pub struct ConsensusDriver {
+    pub keypair: ActKeyPair,
+    pub engine: Arc<ConsensusEngine>,
+    pub executor: Executor,
+    pub mempool: Arc<Mempool>,
+    pub state: Arc<StateManager>,
+    pub rpc: RpcState,
+    pub events: mpsc::Sender<ConsensusEvent>,  // Expired timeouts come back through here
+    pub gossip: mpsc::Sender<Gossip>,
+}
+
+impl ConsensusDriver {
+    pub async fn run(mut self, mut events: mpsc::Receiver<ConsensusEvent>) {
+        let mut tendermint = Tendermint::new(Some(self.keypair.clone()));
+        let mut executions: HashMap<String, BlockExecution> = HashMap::new();  // Executed candidates by block hash
+        let mut future: Vec<ConsensusMessage> = Vec::new();
+        let mut catchup = Catchup::default();
+        let mut started = Instant::now();
+
+        while let Some(event) = events.recv().await {
+            let actions = match event {
+                ConsensusEvent::StartHeight => {
+                    let height = self.engine.get_block_height().await;
+                    if tendermint.height() == height && tendermint.step() != Step::Commit {
+                        continue;  // Already running; a block import scheduled a second start
+                    }
+
+                    self.load_validator_set().await;
+                    let validators = self.engine.validator_set().await;
+                    if validators.is_empty() {
+                        eprintln!("❌ No active validators, retrying in {:?}", BLOCK_INTERVAL);
+                        self.schedule(ConsensusEvent::StartHeight, BLOCK_INTERVAL);
+                        continue;
+                    }
+
+                    started = Instant::now();
+                    executions.clear();
+                    let mut actions = tendermint.start_height(height, self.engine.get_last_block_hash().await, validators);
+
+                    // Replay what arrived for this height while we were still on the last one
+                    future.retain(|message| message.height() >= height);
+                    let (current, later): (Vec<_>, Vec<_>) = future.drain(..).partition(|message| message.height() == height);
+                    future = later;
+                    for message in current {
+                        actions.extend(self.on_message(&mut tendermint, &mut executions, message).await);
+                    }
+                    actions
+                }
+                ConsensusEvent::Message(message) => {
+                    if tendermint.step() == Step::Commit || message.height() > tendermint.height() {
+                        if future.len() < MAX_FUTURE_MESSAGES {
+                            future.push(message);
+                        }
+                        continue;
+                    }
+                    self.on_message(&mut tendermint, &mut executions, message).await
+                }
+                ConsensusEvent::Timeout(timeout) => tendermint.on_timeout(timeout),
+                ConsensusEvent::Block(certified) => {
+                    if self.import(certified, &mut executions, &mut catchup, started).await {
+                        self.schedule(ConsensusEvent::StartHeight, BLOCK_INTERVAL);
+                    }
+                    continue;
+                }
+            };
+
+            let mut queue = actions;
+            while !queue.is_empty() {
+                let mut next = Vec::new();
+                for action in queue {
+                    match action {
+                        Action::Broadcast(message) => {
+                            let _ = self.gossip.send(Gossip::Consensus(message)).await;
+                        }
+                        Action::ScheduleTimeout(timeout, after) => self.schedule(ConsensusEvent::Timeout(timeout), after),
+                        Action::BuildBlock { height, round } => {
+                            if let Some((block, execution)) = self.build_block(height, round).await {
+                                println!("📣 Proposing block {} in round {} (hash: {}...)", height, round, &block.hash()[..16]);
+                                executions.insert(block.hash(), execution);
+                                next.extend(tendermint.propose(block));
+                            }
+                        }
+                        Action::Evidence(evidence) => self.report_evidence(*evidence).await,
+                        Action::Commit { block, certificate } => {
+                            if block.header.height < self.engine.get_block_height().await {
+                                continue;  // Imported from a peer meanwhile
+                            }
+                            let execution = executions.remove(&block.hash());
+                            self.commit(*block, certificate, execution, started).await;
+                            self.schedule(ConsensusEvent::StartHeight, BLOCK_INTERVAL);
+                        }
+                    }
+                }
+                queue = next;
+            }
+        }
+    }
+
+    /// Validators come from the staking records, reloaded when a new epoch starts
+    async fn load_validator_set(&self) {
+        match SystemState::load(&self.state.overlay()) {
+            Ok(records) => {
+                self.engine.update_validator_set(&records.staking).await;
+            }
+            Err(e) => eprintln!("⚠️  Failed to load staking records: {}", e),
+        }
+    }
+
+    /// Deliver `event` to the consensus task after `after`
+    fn schedule(&self, event: ConsensusEvent, after: Duration) {
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(after).await;
+            let _ = events.send(event).await;
+        });
+    }
+
+    async fn on_message(
+        &mut self,
+        tendermint: &mut Tendermint,
+        executions: &mut HashMap<String, BlockExecution>,
+        message: ConsensusMessage,
+    ) -> Vec<Action> {
+        match message {
+            ConsensusMessage::Vote(vote) => tendermint.on_vote(vote),
+            ConsensusMessage::Proposal(proposal) => {
+                if let Err(e) = tendermint.check_proposal(&proposal) {
+                    eprintln!("⚠️  Ignoring proposal for height {} round {}: {}", proposal.height, proposal.round, e);
+                    return Vec::new();
+                }
+                // An invalid block still counts as the round's proposal, and gets a nil prevote
+                let valid = match self.engine.validate_block(&proposal.block).await {
+                    Ok(()) => self.execute_proposal(&proposal, executions).await,
+                    Err(e) => {
+                        eprintln!("⛔ Rejected block {} proposed by {}: {}", proposal.height, proposal.validator, e);
+                        false
+                    }
+                };
+                tendermint.on_proposal(*proposal, valid)
+            }
+        }
+    }
+
+    /// Re-execute a proposed block on our state; a block we cannot reproduce gets a nil prevote
+    async fn execute_proposal(&mut self, proposal: &Proposal, executions: &mut HashMap<String, BlockExecution>) -> bool {
+        let block_hash = proposal.block.hash();
+        if executions.contains_key(&block_hash) {
+            return true;
+        }
+        match self.executor.validate_block(&proposal.block) {
+            Ok(execution) => {
+                executions.insert(block_hash, execution);
+                true
+            }
+            Err(e) => {
+                eprintln!("⛔ Rejected block {} proposed by {}: {}", proposal.height, proposal.validator, e);
+                false
+            }
+        }
+    }
+
+    /// Save evidence of double signing and submit it in a transaction from our key, so the
+    /// offender is slashed in a coming block
+    async fn report_evidence(&self, evidence: Evidence) {
+        let evidence_hash = evidence.hash();
+        println!("🚨 {} signed conflicting messages at height {} (evidence {}...)",
+            evidence.validator(), evidence.height(), &evidence_hash[..16]);
+
+        let path = Path::new(EVIDENCE_DIR).join(format!("{}.json", evidence_hash));
+        let saved = std::fs::create_dir_all(EVIDENCE_DIR)
+            .and_then(|_| std::fs::write(&path, serde_json::to_vec_pretty(&evidence).unwrap()));
+        if let Err(e) = saved {
+            eprintln!("⚠️  Failed to save evidence: {}", e);
+        }
+
+        // Reporting is free, so a validator without a spendable balance can still report;
+        // the mempool includes evidence ahead of paying transactions
+        let address = self.keypair.address().to_string();
+        let nonce = self.state.get_nonce(&address).unwrap_or(0) + self.mempool.get_pending_transactions(&address).len() as u64;
+        let mut tx = Transaction {
+            from: self.keypair.address().clone(),
+            nonce,
+            tx_type: TransactionType::ReportEvidence { evidence },
+            gas_limit: mempool::MIN_GAS_LIMIT,
+            gas_price: 0,
+            signature: Vec::new(),
+            pubkey: self.keypair.public_key(),
+        };
+        tx.signature = self.keypair.sign(&tx.signing_data());
+
+        match self.mempool.add_transaction(tx.clone(), &self.state) {
+            Ok(tx_hash) => {
+                println!("📤 Evidence submitted in tx {}...", &tx_hash[..16]);
+                let _ = self.gossip.send(Gossip::Transaction(Box::new(tx))).await;
+            }
+            Err(e) => eprintln!("⚠️  Failed to submit evidence ({}); it is saved in {}", e, path.display()),
+        }
+    }
+
+    /// Apply finalized blocks from peers, for heights whose votes we missed. A block ahead
+    /// of our tip is held until the blocks before it arrive, and those are requested from
+    /// peers. Returns whether any block was applied.
+    async fn import(
+        &mut self,
+        certified: Box<CertifiedBlock>,
+        executions: &mut HashMap<String, BlockExecution>,
+        catchup: &mut Catchup,
+        started: Instant,
+    ) -> bool {
+        let height = certified.block.header.height;
+        if height < self.engine.get_block_height().await {
+            return false;  // Already applied
+        }
+        if height == self.engine.get_block_height().await || catchup.ahead.len() < MAX_AHEAD_BLOCKS {
+            catchup.ahead.insert(height, *certified);
+        }
+
+        // Apply in height order, as far as the blocks we hold reach
+        let mut applied = false;
+        loop {
+            let tip = self.engine.get_block_height().await;
+            catchup.ahead.retain(|&height, _| height >= tip);
+            let Some(next) = catchup.ahead.remove(&tip) else { break };
+            if !self.apply(next, executions, started).await {
+                break;
+            }
+            applied = true;
+            catchup.requested = None;
+            // The next block may start a new epoch
+            self.load_validator_set().await;
+        }
+
+        if !catchup.ahead.is_empty() && catchup.requested.is_none_or(|at| at.elapsed() >= SYNC_RETRY) {
+            let from = self.engine.get_block_height().await;
+            println!("🔄 Peers are at height {}, requesting blocks from {}",
+                catchup.ahead.keys().next_back().unwrap(), from);
+            catchup.requested = Some(Instant::now());
+            let _ = self.gossip.send(Gossip::RequestBlocks { from }).await;
+        }
+        applied
+    }
+
+    /// Validate and execute a finalized block for our next height, then commit it once its
+    /// certificate checks out against our validator set. Returns whether it was applied.
+    async fn apply(&mut self, certified: CertifiedBlock, executions: &mut HashMap<String, BlockExecution>, started: Instant) -> bool {
+        let CertifiedBlock { block, certificate } = certified;
+        if let Err(e) = self.engine.validate_block(&block).await {
+            eprintln!("⛔ Rejected block {} from peer: {}", block.header.height, e);
+            return false;
+        }
+
+        let execution = match executions.remove(&block.hash()) {
+            Some(execution) => execution,
+            None => match self.executor.validate_block(&block) {
+                Ok(execution) => execution,
+                Err(e) => {
+                    eprintln!("⛔ Rejected block {} from peer: {}", block.header.height, e);
+                    return false;
+                }
+            },
+        };
+        println!("📥 Importing block {} finalized by peers", block.header.height);
+        self.commit(block, certificate, Some(execution), started).await
+    }
+
+    /// Execute mempool transactions into our candidate block for `height`
+    async fn build_block(&mut self, height: u64, round: u32) -> Option<(Block, BlockExecution)> {
+        let txs = self.mempool.get_transactions_for_block(100, &self.state);
+        if !txs.is_empty() {
+            println!("\n🔨 Creating block {} with {} transactions", height, txs.len());
+        }
+
+        // Timestamps must increase, even if our clock is behind the parent's proposer
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let ctx = BlockContext {
+            height,
+            timestamp: now.max(self.engine.get_last_block_timestamp().await + 1),
+            coinbase: self.keypair.address().to_string(),
+            last_commit: self.engine.get_last_commit().await,
+        };
+
+        // Execute transactions against the current state, staking/governance records included
+        let execution = match self.executor.execute_block(txs, &ctx) {
+            Ok(execution) => execution,
+            Err(e) => {
+                eprintln!("❌ Block execution failed: {}", e);
+                return None;
+            }
+        };
+
+        for (tx_hash, reason) in &execution.rejected {
+            eprintln!("     ⚠️  Dropping tx {}...: {}", &tx_hash[..16], reason);
+            self.mempool.remove_transaction(tx_hash);
+        }
+
+        match self
+            .engine
+            .build_block(&self.keypair, round, execution.transactions.clone(), execution.receipts.clone(), execution.state_root.clone(), ctx.timestamp, ctx.last_commit)
+            .await
+        {
+            Ok(block) => Some((block, execution)),
+            Err(e) => {
+                eprintln!("❌ Failed to build block: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Apply a finalized block: store it with its certificate, then commit its state and
+    /// staking/governance records and pay its proposer. Returns whether it was applied.
+    async fn commit(&mut self, block: Block, certificate: CommitCertificate, execution: Option<BlockExecution>, started: Instant) -> bool {
+        let execution = match execution {
+            Some(execution) => execution,
+            None => match self.executor.validate_block(&block) {
+                Ok(execution) => execution,
+                Err(e) => {
+                    eprintln!("❌ Cannot apply finalized block {}: {}", block.header.height, e);
+                    return false;
+                }
+            },
+        };
+
+        // The state and receipts are written in the same batch as the block
+        let receipts = execution.receipts.clone();
+        let persist_state = || {
+            self.state.commit(execution.state)?;
+            for receipt in receipts {
+                self.state.store_receipt(receipt)?;
+            }
+            Ok(())
+        };
+        if let Err(e) = self.engine.commit_block(&block, &certificate, persist_state).await {
+            eprintln!("❌ Failed to commit block: {}", e);
+            return false;
+        }
+
+        for (tx, receipt) in block.transactions.iter().zip(&execution.receipts) {
+            let tx_hash = tx.hash();
+            println!("  ⚡ Including tx {}... from {} ({})", &tx_hash[..16], tx.from.to_string(),
+                if receipt.status { "success" } else { "failed" });
+            self.mempool.remove_transaction(&tx_hash);
+        }
+
+        let total_tx_fees = u64::try_from(execution.fees).unwrap_or(u64::MAX);
+        if !block.transactions.is_empty() {
+            println!("📊 Block {} processed {} transactions, gas: {}, fees: {} ACT",
+                block.header.height, block.transactions.len(), execution.gas_used, total_tx_fees / 1_000_000_000);
+        }
+
+        // The committed state now holds the records
+        let SystemState { staking, governance } = execution.system;
+        for validator in staking.get_all_validators() {
+            let jailed_now = validator.slash_events.last().is_some_and(|e| e.block_height == block.header.height);
+            if let Some(until) = validator.jailed_until.filter(|_| jailed_now) {
+                println!("⛓️  {} jailed for downtime until block {}", validator.address, until);
+            }
+        }
+        println!("💰 Block rewards distributed to {}", block.header.validator_commitment);
+        metrics::record_system_state(&staking, &governance);
+        metrics::TRANSACTIONS_PENDING.set(self.mempool.size() as i64);
+
+        println!("📦 Block {} finalized with {} txs (hash: {}...)",
+            block.header.height, block.transactions.len(), &block.hash()[..16]);
+        metrics::record_block(&block, started.elapsed());
+        self.rpc.publish_block(&block);
+        let _ = self.gossip.send(Gossip::Block(Box::new(CertifiedBlock { block, certificate }))).await;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use governance::GovernanceManager;
+    use runtime::WasmRuntime;
+    use staking::StakingManager;
+    use state::GenesisAccount;
+    use storage::BlockchainStorage;
+    use types::consensus::{Vote, VoteType};
+
+    /// Driver of a node on a one-validator chain, every node starting from the same genesis
+    fn node(path: &str, validator: &ActKeyPair) -> (ConsensusDriver, Arc<BlockchainStorage>, mpsc::Receiver<Gossip>) {
+        let _ = std::fs::remove_dir_all(path);
+        let storage = Arc::new(BlockchainStorage::new(path).unwrap());
+        let state = Arc::new(StateManager::new(storage.clone()).unwrap());
+        let mut staking = StakingManager::new();
+        staking.stake(validator.address().to_string(), staking::MIN_VALIDATOR_STAKE, 10).unwrap();
+        let mut records = state.overlay();
+        SystemState::new(staking, GovernanceManager::new()).store(&mut records).unwrap();
+        state.commit(records).unwrap();
+        state
+            .initialize_genesis(vec![GenesisAccount::new(staking::STAKING_POOL_ACCOUNT.to_string(), 100_000.0)])
+            .unwrap();
+
+        let runtime = WasmRuntime::new().unwrap();
+        let mempool = Arc::new(Mempool::new(100));
+        let (events, _) = mpsc::channel(100);
+        let (gossip, gossiped) = mpsc::channel(100);
+        let driver = ConsensusDriver {
+            keypair: validator.clone(),
+            engine: Arc::new(ConsensusEngine::new(storage.clone())),
+            executor: Executor::with_runtime(state.clone(), runtime.clone()),
+            mempool: mempool.clone(),
+            state: state.clone(),
+            rpc: RpcState::new(state, storage.clone(), mempool, runtime),
+            events,
+            gossip,
+        };
+        (driver, storage, gossiped)
+    }
+
+    #[tokio::test]
+    async fn test_rejoin_behind() {
+        let validator = ActKeyPair::from_seed(&[7; 32]);
+        let (mut network, network_storage, _) = node("./test_node_network_db", &validator);
+        let (mut rejoined, _, mut gossiped) = node("./test_node_rejoined_db", &validator);
+
+        // The network finalizes several blocks while the node is away
+        for height in 0..5 {
+            network.load_validator_set().await;
+            let (block, execution) = network.build_block(height, 0).await.unwrap();
+            let precommit = Vote::new(&validator, height, 0, VoteType::Precommit, Some(block.hash()));
+            let certificate = CommitCertificate { height, round: 0, block_hash: block.hash(), precommits: vec![precommit] };
+            assert!(network.commit(block, certificate, Some(execution), Instant::now()).await);
+        }
+        let certified = |height| Box::new(network_storage.get_certified_block(height).unwrap().unwrap());
+
+        // Back online, it hears of the newest block and asks for the ones it missed
+        let mut executions = HashMap::new();
+        let mut catchup = Catchup::default();
+        rejoined.load_validator_set().await;
+        assert!(!rejoined.import(certified(4), &mut executions, &mut catchup, Instant::now()).await);
+        assert!(matches!(gossiped.recv().await, Some(Gossip::RequestBlocks { from: 0 })));
+
+        // A block whose certificate was not signed by the validators is not applied
+        let mut forged = certified(0);
+        forged.certificate.precommits = vec![Vote::new(&ActKeyPair::from_seed(&[8; 32]), 0, 0, VoteType::Precommit, Some(forged.block.hash()))];
+        assert!(!rejoined.import(forged, &mut executions, &mut catchup, Instant::now()).await);
+        assert_eq!(rejoined.engine.get_block_height().await, 0);
+
+        // Answers arrive out of order and are applied by height
+        for height in [2, 0, 3, 1] {
+            rejoined.import(certified(height), &mut executions, &mut catchup, Instant::now()).await;
+        }
+        assert_eq!(rejoined.engine.get_block_height().await, 5);
+        assert!(catchup.ahead.is_empty());
+        assert_eq!(rejoined.engine.get_last_block_hash().await, network.engine.get_last_block_hash().await);
+        assert_eq!(rejoined.state.calculate_state_root().unwrap(), network.state.calculate_state_root().unwrap());
+        assert!(rejoined.state.verify_tip().is_ok());
+
+        let _ = std::fs::remove_dir_all("./test_node_network_db");
+        let _ = std::fs::remove_dir_all("./test_node_rejoined_db");
+    }
+}
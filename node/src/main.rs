@@ -7,18 +7,26 @@ use libp2p::{
 use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::{io, select};
 
-use consensus::{start_consensus, ConsensusEngine};
+use consensus::{ConsensusEngine, ConsensusMessage};
+use executor::{Executor, SystemState};
 use governance::GovernanceManager;
 use mempool::Mempool;
-use rpc::{start_rpc_server, RpcState};
-use state::{GenesisAccount, StateManager};
+use runtime::WasmRuntime;
+use rpc::{metrics, start_rpc_server, NamespaceConfig, RateLimit, RpcListener, RpcState};
+use state::{GasConfig, GenesisAccount, StateManager};
 use staking::StakingManager;
 use storage::BlockchainStorage;
-use types::{Action, Transaction, TransactionType};
+use types::consensus::CertifiedBlock;
+use types::Transaction;
+
+mod bft;
+
+use bft::{ConsensusDriver, ConsensusEvent, Gossip, SyncMessage};
 
 #[derive(NetworkBehaviour)]
 struct NodeBehaviour {
@@ -34,8 +42,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let storage = Arc::new(BlockchainStorage::new("./actchain_data")?);
     println!("💾 Storage initialized");
 
+    // Key this node votes and proposes with
+    let validator_key = bft::load_validator_key(Path::new("./actchain_validator_key.json"))?;
+    println!("🔑 Validator address: {}", validator_key.address());
+
+    // Genesis validators: ACT_GENESIS_VALIDATORS (comma-separated addresses), or just this
+    // node. Every node of a network must use the same list.
+    let genesis_validators: Vec<String> = match std::env::var("ACT_GENESIS_VALIDATORS") {
+        Ok(list) => list.split(',').map(str::trim).filter(|a| !a.is_empty()).map(String::from).collect(),
+        Err(_) => vec![validator_key.address().to_string()],
+    };
+
     // Initialize state manager with genesis accounts
-    let state_manager = Arc::new(StateManager::new(storage.clone()));
+    let state_manager = Arc::new(StateManager::new(storage.clone())?);
     
     // Create genesis accounts with initial ACT allocation
     let genesis_accounts = vec![
@@ -43,45 +62,97 @@ async fn main() -> Result<(), Box<dyn Error>> {
         GenesisAccount::new("ACT-validator2".to_string(), 1_000_000.0),
         GenesisAccount::new("ACT-validator3".to_string(), 1_000_000.0),
         GenesisAccount::new("ACT-treasury".to_string(), 10_000_000.0),  // 10M ACT
+        // Backs the genesis validators' stake below
+        GenesisAccount::new(
+            staking::STAKING_POOL_ACCOUNT.to_string(),
+            100_000.0 * genesis_validators.len() as f64,
+        ),
     ];
     
-    state_manager.initialize_genesis(genesis_accounts)?;
-    println!("🌱 Genesis state initialized");
+    // Staking and governance records are part of the state, so only a fresh chain sets
+    // them up: the genesis validators with 100k ACT staked each, and no proposals
+    if state_manager.genesis_state_root().is_err() {
+        let mut staking = StakingManager::new();
+        for validator in &genesis_validators {
+            staking.stake(validator.clone(), staking::MIN_VALIDATOR_STAKE, 10)?;
+        }
+        let mut records = state_manager.overlay();
+        SystemState::new(staking, GovernanceManager::new()).store(&mut records)?;
+        state_manager.commit(records)?;
+
+        state_manager.initialize_genesis(genesis_accounts)?;
+        println!("🌱 Genesis state initialized");
+    }
+
+    // A block and its state are stored together; refuse to run on a directory where they differ
+    state_manager.verify_tip()?;
 
     // Initialize mempool
     let mempool = Arc::new(Mempool::new(10_000)); // Max 10k pending txs
     println!("🔄 Mempool initialized");
 
-    // Initialize staking manager
-    let staking_manager = Arc::new(tokio::sync::Mutex::new(StakingManager::new()));
-    println!("💎 Staking manager initialized");
-
-    // Initialize governance manager
-    let governance_manager = Arc::new(tokio::sync::Mutex::new(GovernanceManager::new()));
-    println!("🏛️  Governance manager initialized");
+    // Staking and governance records live in the state; check they load before starting
+    let records = SystemState::load(&state_manager.overlay())?;
+    println!("💎 Staking records loaded ({} validators)", records.staking.get_all_validators().len());
+    println!("🏛️  Governance records loaded ({} proposals)", records.governance.list_proposals(None).len());
 
     // Initialize consensus engine
-    let consensus_engine = Arc::new(ConsensusEngine::new());
+    let consensus_engine = Arc::new(ConsensusEngine::new(storage.clone()));
     println!("🎯 Consensus engine initialized");
 
-    // Start consensus in background
-    let consensus_handle = consensus_engine.clone();
-    tokio::spawn(async move {
-        start_consensus(consensus_handle).await;
-    });
+    // Contract runtime shared by block execution and RPC calls, so both reuse compiled modules
+    let runtime = WasmRuntime::with_gas_config(GasConfig::default())?
+        .with_cache_dir("./actchain_module_cache")?;
 
     // Start RPC server in background
     let rpc_state = RpcState::new(
         state_manager.clone(),
+        storage.clone(),
         mempool.clone(),
-        staking_manager.clone(),
-        governance_manager.clone(),
+        runtime.clone(),
     );
-    tokio::spawn(async move {
-        if let Err(e) = start_rpc_server(rpc_state, 8545).await {
-            eprintln!("❌ RPC server error: {}", e);
+    // /health reports on the validator this node runs
+    let rpc_state = rpc_state.with_validator(validator_key.address().to_string());
+    // Public listener on every interface, full access for the operator on localhost.
+    // ACT_RPC_PUBLIC_NAMESPACES / ACT_RPC_LOCAL_NAMESPACES override what each serves,
+    // e.g. "act,eth,net,stake,gov".
+    let mut public_rpc = RpcListener::public(8545);
+    let mut local_rpc = RpcListener::local(8546);
+    if let Ok(spec) = std::env::var("ACT_RPC_PUBLIC_NAMESPACES") {
+        public_rpc.namespaces = NamespaceConfig::parse(&spec)?;
+    }
+    if let Ok(spec) = std::env::var("ACT_RPC_LOCAL_NAMESPACES") {
+        local_rpc.namespaces = NamespaceConfig::parse(&spec)?;
+    }
+    // ACT_RPC_RATE_LIMIT ("50/100" requests/s and burst per client, or "off"),
+    // ACT_RPC_METHOD_COSTS ("eth_getLogs=20,..."), ACT_RPC_MAX_BODY_BYTES and
+    // ACT_RPC_MAX_BATCH_SIZE tune the public listener. ACT_RPC_API_KEYS (comma-separated)
+    // puts debug and admin behind a key on both.
+    if let Ok(spec) = std::env::var("ACT_RPC_RATE_LIMIT") {
+        public_rpc.limits.rate = RateLimit::parse(&spec)?;
+    }
+    if let Ok(spec) = std::env::var("ACT_RPC_METHOD_COSTS") {
+        public_rpc.limits = public_rpc.limits.with_method_costs(&spec)?;
+    }
+    if let Ok(bytes) = std::env::var("ACT_RPC_MAX_BODY_BYTES") {
+        public_rpc.limits.max_body_bytes = bytes.parse()?;
+    }
+    if let Ok(size) = std::env::var("ACT_RPC_MAX_BATCH_SIZE") {
+        public_rpc.limits.max_batch_size = size.parse()?;
+    }
+    if let Ok(keys) = std::env::var("ACT_RPC_API_KEYS") {
+        for listener in [&mut public_rpc, &mut local_rpc] {
+            listener.auth = listener.auth.clone().with_api_keys(keys.split(',').map(str::trim));
         }
-    });
+    }
+    for listener in [public_rpc, local_rpc] {
+        let rpc_for_server = rpc_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = start_rpc_server(rpc_for_server, listener).await {
+                eprintln!("❌ RPC server error: {}", e);
+            }
+        });
+    }
 
     // Create a random PeerId
     let local_key = libp2p::identity::Keypair::generate_ed25519();
@@ -99,6 +170,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .heartbeat_interval(Duration::from_secs(10))
         .validation_mode(gossipsub::ValidationMode::Strict)
         .message_id_fn(message_id_fn)
+        .max_transmit_size(4 * 1024 * 1024)  // Proposals carry whole blocks
         .build()
         .map_err(|msg| io::Error::new(io::ErrorKind::Other, msg))?;
 
@@ -110,9 +182,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Subscribe to topics
     let blocks_topic = gossipsub::IdentTopic::new("act-blocks");
     let tx_topic = gossipsub::IdentTopic::new("act-transactions");
+    let consensus_topic = gossipsub::IdentTopic::new("act-consensus");
+    let sync_topic = gossipsub::IdentTopic::new("act-sync");
     gossipsub.subscribe(&blocks_topic)?;
     gossipsub.subscribe(&tx_topic)?;
-    println!("📡 Subscribed to act-blocks and act-transactions topics");
+    gossipsub.subscribe(&consensus_topic)?;
+    gossipsub.subscribe(&sync_topic)?;
+    println!("📡 Subscribed to act-blocks, act-transactions, act-consensus and act-sync topics");
 
     // Set up mDNS for local peer discovery
     let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)?;
@@ -144,6 +220,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         while let Some(tx) = tx_receiver.recv().await {
             match mempool_for_handler.add_transaction(tx.clone(), &state_for_handler) {
                 Ok(hash) => {
+                    metrics::TRANSACTIONS_PENDING.set(mempool_for_handler.size() as i64);
                     println!("📥 Transaction added to mempool: {}...", &hash[..16]);
                     let stats = mempool_for_handler.get_stats();
                     println!("   Mempool: {} txs from {} senders, avg gas: {}",
@@ -159,115 +236,55 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     });
 
-    // Block proposer - create blocks with transactions from mempool
-    let engine_for_blocks = consensus_engine.clone();
-    let mempool_for_blocks = mempool.clone();
-    let state_for_blocks = state_manager.clone();
-    let staking_for_blocks = staking_manager.clone();
-    let governance_for_blocks = governance_manager.clone();
-    
+    // Consensus - agree on each block with the other validators, then apply it
+    let (consensus_sender, consensus_receiver) = tokio::sync::mpsc::channel::<ConsensusEvent>(1000);
+    let (gossip_sender, mut gossip_receiver) = tokio::sync::mpsc::channel::<Gossip>(1000);
+    let executor = Executor::with_runtime(state_manager.clone(), runtime);
+    let driver = ConsensusDriver {
+        keypair: validator_key,
+        engine: consensus_engine.clone(),
+        executor,
+        mempool: mempool.clone(),
+        state: state_manager.clone(),
+        rpc: rpc_state.clone(),
+        events: consensus_sender.clone(),
+        gossip: gossip_sender,
+    };
+    tokio::spawn(driver.run(consensus_receiver));
+    // Give peers a moment to connect before the first height
+    let first_height = consensus_sender.clone();
     tokio::spawn(async move {
-        let mut block_num = 0;
-        loop {
-            tokio::time::sleep(Duration::from_secs(30)).await;
-            block_num += 1;
-            
-            // Update block height in staking and governance
-            {
-                let mut staking = staking_for_blocks.lock().await;
-                staking.set_block_height(block_num);
-            }
-            {
-                let mut governance = governance_for_blocks.lock().await;
-                governance.set_block_height(block_num);
-            }
-            
-            // Get transactions from mempool
-            let txs = mempool_for_blocks.get_transactions_for_block(100, &state_for_blocks);
-            
-            let mut total_tx_fees = 0u64;
-            
-            if !txs.is_empty() {
-                println!("\n🔨 Creating block {} with {} transactions", block_num, txs.len());
-                
-                // Process transactions and update state
-                for tx in &txs {
-                    let tx_hash = tx.hash();
-                    println!("  ⚡ Including tx {}... from {}", &tx_hash[..16], tx.from.to_string());
-                    
-                    // Calculate transaction fee
-                    let tx_fee = (tx.gas_limit as u64) * (tx.gas_price as u64);
-                    total_tx_fees += tx_fee;
-                    
-                    // Execute transaction
-                    match &tx.tx_type {
-                        TransactionType::Transfer { to, amount } => {
-                            if let Err(e) = state_for_blocks.transfer(&tx.from.to_string(), to, *amount) {
-                                eprintln!("     ⚠️  Transfer failed: {}", e);
-                                continue;
-                            }
-                        }
-                        _ => {}
-                    }
-                    
-                    // Remove from mempool
-                    mempool_for_blocks.remove_transaction(&tx_hash);
-                }
-                
-                println!("📊 Block {} processed {} transactions, fees: {} ACT", 
-                    block_num, txs.len(), total_tx_fees / 1_000_000_000);
-            }
-            
-            // Distribute block rewards to validator (simplified - using first validator)
-            let validator_address = "ACT-validator1";
-            {
-                let mut staking = staking_for_blocks.lock().await;
-                staking.distribute_block_reward(validator_address, total_tx_fees);
-                println!("💰 Block rewards distributed to {}", validator_address);
-            }
-            
-            // Update active governance proposals
-            {
-                let mut governance = governance_for_blocks.lock().await;
-                let active_proposals: Vec<u64> = governance
-                    .list_proposals(None)
-                    .iter()
-                    .filter(|p| matches!(p.status, governance::ProposalStatus::Review | governance::ProposalStatus::Active))
-                    .map(|p| p.id)
-                    .collect();
-                
-                for proposal_id in active_proposals {
-                    if let Err(e) = governance.update_proposal_status(proposal_id) {
-                        eprintln!("⚠️  Failed to update proposal {}: {}", proposal_id, e);
-                    }
-                }
-            }
-            
-            // Still propose block (even if empty) for consensus
-            let action = Action {
-                actor: format!("block_proposer_{}", block_num),
-                payload: format!("block_{}_data", block_num).into_bytes(),
-                nonce: block_num,
-            };
-            
-            match engine_for_blocks.propose_block(action).await {
-                Ok(header) => {
-                    println!("📦 Block {} finalized at height {}", block_num, header.height);
-                }
-                Err(e) => {
-                    eprintln!("❌ Failed to propose block: {}", e);
-                }
-            }
-        }
+        tokio::time::sleep(bft::BLOCK_INTERVAL).await;
+        let _ = first_height.send(ConsensusEvent::StartHeight).await;
     });
 
     // Event loop
     loop {
         select! {
+            Some(gossip) = gossip_receiver.recv() => {
+                let (topic, data) = match &gossip {
+                    Gossip::Consensus(message) => (&consensus_topic, serde_json::to_vec(message)),
+                    Gossip::Block(certified) => (&blocks_topic, serde_json::to_vec(certified)),
+                    Gossip::Transaction(tx) => (&tx_topic, serde_json::to_vec(tx)),
+                    Gossip::RequestBlocks { from } => {
+                        let nonce = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+                        (&sync_topic, serde_json::to_vec(&SyncMessage::Request { from: *from, nonce }))
+                    }
+                };
+                // Publishing fails without peers, e.g. on a single-validator network
+                if let Ok(data) = data {
+                    let _ = swarm.behaviour_mut().gossipsub.publish(topic.clone(), data);
+                }
+            }
             event = swarm.select_next_some() => match event {
                 SwarmEvent::NewListenAddr { address, .. } => {
                     println!("🎧 Listening on {address}");
                 }
+                SwarmEvent::ConnectionEstablished { .. } | SwarmEvent::ConnectionClosed { .. } => {
+                    let peers = swarm.connected_peers().count();
+                    *rpc_state.peer_count.write().await = peers;
+                    metrics::PEER_COUNT.set(peers as i64);
+                }
                 SwarmEvent::Behaviour(NodeBehaviourEvent::Mdns(mdns::Event::Discovered(list))) => {
                     for (peer_id, _multiaddr) in list {
                         println!("🔍 mDNS discovered a new peer: {peer_id}");
@@ -289,11 +306,44 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     if message.topic.to_string().contains("transactions") {
                         // Deserialize transaction
                         if let Ok(tx) = serde_json::from_slice::<Transaction>(&message.data) {
+                            metrics::MESSAGES_RECEIVED.with_label_values(&["transaction"]).inc();
                             println!("📨 Received transaction from peer: {}", peer_id);
                             let _ = tx_sender.send(tx).await;
                         }
+                    } else if message.topic == consensus_topic.hash() {
+                        if let Ok(consensus_message) = serde_json::from_slice::<ConsensusMessage>(&message.data) {
+                            metrics::MESSAGES_RECEIVED.with_label_values(&["consensus"]).inc();
+                            let _ = consensus_sender.send(ConsensusEvent::Message(consensus_message)).await;
+                        }
+                    } else if message.topic == sync_topic.hash() {
+                        metrics::MESSAGES_RECEIVED.with_label_values(&["sync"]).inc();
+                        match serde_json::from_slice::<SyncMessage>(&message.data) {
+                            // A peer is behind: send what we have of the blocks it asked for.
+                            // Every peer answering sends the same messages, which gossip
+                            // delivers once.
+                            Ok(SyncMessage::Request { from, nonce }) => {
+                                for height in from..from + bft::MAX_SYNC_BLOCKS {
+                                    let Ok(Some(block)) = storage.get_certified_block(height) else { break };
+                                    let answer = SyncMessage::Block { nonce, block: Box::new(block) };
+                                    if let Ok(data) = serde_json::to_vec(&answer) {
+                                        let _ = swarm.behaviour_mut().gossipsub.publish(sync_topic.clone(), data);
+                                    }
+                                }
+                            }
+                            Ok(SyncMessage::Block { block, .. }) => {
+                                let _ = consensus_sender.send(ConsensusEvent::Block(block)).await;
+                            }
+                            Err(_) => {}
+                        }
                     } else if message.topic.to_string().contains("blocks") {
+                        metrics::MESSAGES_RECEIVED.with_label_values(&["block"]).inc();
                         println!("📨 Received block from peer: {}", peer_id);
+                        // Peers' tips tell /health whether we are keeping up; a block we
+                        // missed the votes for is validated and imported
+                        if let Ok(certified) = serde_json::from_slice::<CertifiedBlock>(&message.data) {
+                            rpc_state.note_peer_height(certified.block.header.height).await;
+                            let _ = consensus_sender.send(ConsensusEvent::Block(Box::new(certified))).await;
+                        }
                     }
                 }
                 _ => {}
@@ -0,0 +1,104 @@
+use axum::http::HeaderMap;
+
+use crate::NamespaceConfig;
+
+/// API keys guarding privileged namespaces on one listener. With no keys configured
+/// every namespace the listener serves is open, as before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcAuth {
+    api_keys: Vec<String>,
+    protected: NamespaceConfig,
+}
+
+impl Default for RpcAuth {
+    fn default() -> Self {
+        Self {
+            api_keys: Vec::new(),
+            protected: NamespaceConfig::none().with("debug").with("admin"),
+        }
+    }
+}
+
+impl RpcAuth {
+    /// Require one of `api_keys` for the privileged namespaces. Empty keys are ignored.
+    pub fn with_api_keys<I, S>(mut self, api_keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.api_keys = api_keys
+            .into_iter()
+            .map(Into::into)
+            .filter(|key: &String| !key.is_empty())
+            .collect();
+        self
+    }
+
+    /// Namespaces that need a key, `debug` and `admin` by default
+    pub fn protecting(mut self, namespaces: NamespaceConfig) -> Self {
+        self.protected = namespaces;
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.api_keys.is_empty()
+    }
+
+    /// Whether `namespace` may only be called with a valid key
+    pub fn protects(&self, namespace: &str) -> bool {
+        self.enabled() && self.protected.enables(namespace)
+    }
+
+    /// Check the key a request carries, as `Authorization: Bearer <key>` or `X-API-Key: <key>`
+    pub fn authorize(&self, headers: &HeaderMap) -> bool {
+        if !self.enabled() {
+            return true;
+        }
+
+        let bearer = headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let api_key = headers.get("x-api-key").and_then(|value| value.to_str().ok());
+
+        [bearer, api_key]
+            .into_iter()
+            .flatten()
+            .any(|presented| self.api_keys.iter().any(|key| constant_time_eq(key.as_bytes(), presented.trim().as_bytes())))
+    }
+}
+
+/// Compare without returning early, so timing does not reveal how much of a key matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_auth() {
+        let open = RpcAuth::default();
+        assert!(open.authorize(&HeaderMap::new()));
+        assert!(!open.protects("debug"));
+
+        let auth = RpcAuth::default().with_api_keys(["s3cret", ""]);
+        assert!(auth.protects("debug"));
+        assert!(auth.protects("admin"));
+        assert!(!auth.protects("eth"));
+        assert!(!auth.authorize(&HeaderMap::new()));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer s3cret".parse().unwrap());
+        assert!(auth.authorize(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "s3cret".parse().unwrap());
+        assert!(auth.authorize(&headers));
+
+        headers.insert("x-api-key", "s3cre".parse().unwrap());
+        assert!(!auth.authorize(&headers));
+        assert!(!RpcAuth::default().with_api_keys([""]).enabled());
+    }
+}
@@ -0,0 +1,452 @@
+use serde_json::{json, Value};
+use types::{eth::EthTransaction, Block, EventLog, Transaction, TransactionType};
+
+use crate::RpcError;
+
+/// Gas limit reported for blocks: the node packs up to 100 transactions of at most
+/// `MAX_GAS_LIMIT` each
+pub const BLOCK_GAS_LIMIT: u64 = 100 * mempool::MAX_GAS_LIMIT;
+
+/// Gas price suggested when recent blocks and the mempool have none to sample (1 gwei)
+pub const DEFAULT_GAS_PRICE: u128 = 1_000_000_000;
+
+/// Recent blocks sampled by `eth_gasPrice`
+pub const GAS_PRICE_SAMPLE_BLOCKS: usize = 20;
+
+/// Widest block range a single `eth_getLogs` request may scan
+pub const MAX_LOG_BLOCK_RANGE: u64 = 10_000;
+
+/// Most blocks a single `eth_feeHistory` request may cover
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// keccak256 of the RLP of an empty list: there are never any uncles
+const EMPTY_UNCLES_HASH: &str = "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347";
+
+/// Hex quantity without leading zeros
+pub fn quantity(value: impl std::fmt::LowerHex) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Hex bytes
+pub fn data(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// 32-byte hash in `0x` form; ACT hashes are stored as bare hex
+pub fn hash(value: &str) -> String {
+    format!("0x{}", value.trim_start_matches("0x"))
+}
+
+fn zero_bytes(len: usize) -> String {
+    format!("0x{}", "00".repeat(len))
+}
+
+/// Account key for an address parameter. Ethereum addresses may carry an EIP-55 mixed-case
+/// checksum, while accounts are keyed by the lowercase form; ACT addresses are kept as given.
+pub fn address(value: &str) -> String {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(digits) => format!("0x{}", digits.to_lowercase()),
+        None => value.to_string(),
+    }
+}
+
+/// Event topics are strings such as event names; Ethereum tooling expects 32-byte
+/// words, so anything that is not already one is hashed like a Solidity event signature
+pub fn topic(value: &str) -> String {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if value.starts_with("0x") && digits.len() == 64 && hex::decode(digits).is_ok() {
+        return value.to_lowercase();
+    }
+    data(&crypto::keccak256(value.as_bytes()))
+}
+
+/// Gas used by all of a block's transactions
+pub fn block_gas_used(block: &Block) -> u64 {
+    block.receipts.iter().map(|r| r.gas_used).sum()
+}
+
+/// Ethereum block object, with full transactions or only their hashes
+pub fn block_json(block: &Block, full_transactions: bool) -> Value {
+    let transactions: Vec<Value> = block
+        .transactions
+        .iter()
+        .enumerate()
+        .map(|(index, tx)| {
+            if full_transactions {
+                transaction_json(tx, Some((block, index)))
+            } else {
+                json!(hash(&tx.hash()))
+            }
+        })
+        .collect();
+    let size = serde_json::to_vec(block).map(|bytes| bytes.len()).unwrap_or(0);
+
+    json!({
+        "number": quantity(block.header.height),
+        "hash": hash(&block.hash()),
+        "parentHash": hash(&block.header.parent_hash),
+        "nonce": zero_bytes(8),
+        "mixHash": zero_bytes(32),
+        "sha3Uncles": EMPTY_UNCLES_HASH,
+        "logsBloom": zero_bytes(256),
+        "transactionsRoot": hash(&block.header.tx_root),
+        "stateRoot": hash(&block.header.state_root),
+        "receiptsRoot": hash(&block.header.receipts_root),
+        "miner": block.header.actor_pubkey,
+        "difficulty": "0x0",
+        "totalDifficulty": "0x0",
+        "extraData": "0x",
+        "size": quantity(size),
+        "gasLimit": quantity(BLOCK_GAS_LIMIT),
+        "gasUsed": quantity(block_gas_used(block)),
+        "baseFeePerGas": "0x0",
+        "timestamp": quantity(block.header.timestamp),
+        "transactions": transactions,
+        "uncles": [],
+    })
+}
+
+/// Ethereum transaction object; `location` is the including block and position,
+/// absent for pending transactions
+pub fn transaction_json(tx: &Transaction, location: Option<(&Block, usize)>) -> Value {
+    let (to, value, input) = match &tx.tx_type {
+        TransactionType::Transfer { to, amount } => (Some(to.clone()), *amount, Vec::new()),
+        TransactionType::ContractDeploy { code, .. } => (None, 0, code.clone()),
+        TransactionType::ContractCall { contract, args, .. } => (Some(contract.clone()), 0, args.clone()),
+        TransactionType::EthereumLegacy { to, value, data, .. } => {
+            ((!to.is_empty()).then(|| to.clone()), *value, data.clone())
+        }
+        // Staking and governance actions: shown as a call to the staking pool with the action as input
+        action => (
+            Some(staking::STAKING_POOL_ACCOUNT.to_string()),
+            action.value(),
+            serde_json::to_vec(action).unwrap_or_default(),
+        ),
+    };
+
+    let (tx_type, chain_id, v, r, s) = match envelope(tx) {
+        Some(eth) => (
+            eth.kind.type_id(),
+            eth.chain_id,
+            eth.v(),
+            data(&eth.signature[..32]),
+            data(&eth.signature[32..]),
+        ),
+        None => {
+            let half = tx.signature.len().min(32);
+            let (r, s) = tx.signature.split_at(half);
+            (0, types::eth::CHAIN_ID, 0, data(r), data(s))
+        }
+    };
+
+    let (block_hash, block_number, index) = match location {
+        Some((block, index)) => (
+            json!(hash(&block.hash())),
+            json!(quantity(block.header.height)),
+            json!(quantity(index)),
+        ),
+        None => (Value::Null, Value::Null, Value::Null),
+    };
+
+    json!({
+        "hash": hash(&tx.hash()),
+        "nonce": quantity(tx.nonce),
+        "blockHash": block_hash,
+        "blockNumber": block_number,
+        "transactionIndex": index,
+        "from": tx.from.to_string(),
+        "to": to,
+        "value": quantity(value),
+        "gas": quantity(tx.gas_limit),
+        "gasPrice": quantity(tx.gas_price),
+        "input": data(&input),
+        "type": quantity(tx_type),
+        "chainId": quantity(chain_id),
+        "v": quantity(v),
+        "r": r,
+        "s": s,
+    })
+}
+
+/// Signed envelope of a raw Ethereum transaction; native transactions carry an ed25519 signature
+fn envelope(tx: &Transaction) -> Option<EthTransaction> {
+    match tx.tx_type {
+        TransactionType::EthereumLegacy { .. } => EthTransaction::decode(&tx.signature).ok(),
+        _ => None,
+    }
+}
+
+/// Ethereum receipt for the transaction at `index` in `block`
+pub fn receipt_json(block: &Block, index: usize) -> Option<Value> {
+    let tx = block.transactions.get(index)?;
+    let receipt = block.receipts.get(index)?;
+
+    let cumulative_gas_used: u64 = block.receipts[..=index].iter().map(|r| r.gas_used).sum();
+    let first_log_index: usize = block.receipts[..index].iter().map(|r| r.logs.len()).sum();
+    let logs: Vec<Value> = receipt
+        .logs
+        .iter()
+        .enumerate()
+        .map(|(i, log)| log_json(log, block, index, first_log_index + i))
+        .collect();
+    let tx_type = envelope(tx).map_or(0, |eth| eth.kind.type_id());
+
+    Some(json!({
+        "transactionHash": hash(&receipt.transaction_hash),
+        "transactionIndex": quantity(index),
+        "blockHash": hash(&block.hash()),
+        "blockNumber": quantity(block.header.height),
+        "from": receipt.from,
+        "to": receipt.to,
+        "contractAddress": receipt.contract_address,
+        "cumulativeGasUsed": quantity(cumulative_gas_used),
+        "gasUsed": quantity(receipt.gas_used),
+        "effectiveGasPrice": quantity(tx.gas_price),
+        "logs": logs,
+        "logsBloom": zero_bytes(256),
+        "status": if receipt.status { "0x1" } else { "0x0" },
+        "type": quantity(tx_type),
+    }))
+}
+
+fn log_json(log: &EventLog, block: &Block, tx_index: usize, log_index: usize) -> Value {
+    json!({
+        "address": log.address,
+        "topics": log.topics.iter().map(|t| topic(t)).collect::<Vec<_>>(),
+        "data": data(&log.data),
+        "blockNumber": quantity(block.header.height),
+        "blockHash": hash(&block.hash()),
+        "transactionHash": hash(&log.transaction_hash),
+        "transactionIndex": quantity(tx_index),
+        "logIndex": quantity(log_index),
+        "removed": false,
+    })
+}
+
+/// Address and topic criteria of an `eth_getLogs` filter
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub addresses: Vec<String>,             // Empty: any address
+    pub topics: Vec<Option<Vec<String>>>,   // Per position: None matches anything, else any of
+}
+
+impl LogFilter {
+    pub fn from_json(filter: &Value) -> Result<Self, RpcError> {
+        let addresses = match filter.get("address") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::String(address)) => vec![address.to_lowercase()],
+            Some(Value::Array(addresses)) => addresses
+                .iter()
+                .map(|a| a.as_str().map(str::to_lowercase).ok_or_else(|| RpcError::invalid_params("Invalid address in filter")))
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(RpcError::invalid_params("Invalid address in filter")),
+        };
+
+        let topics = match filter.get("topics") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(positions)) => positions
+                .iter()
+                .map(|position| match position {
+                    Value::Null => Ok(None),
+                    Value::String(t) => Ok(Some(vec![topic(t)])),
+                    Value::Array(options) => options
+                        .iter()
+                        .map(|t| t.as_str().map(topic).ok_or_else(|| RpcError::invalid_params("Invalid topic in filter")))
+                        .collect::<Result<_, _>>()
+                        .map(Some),
+                    _ => Err(RpcError::invalid_params("Invalid topic in filter")),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(RpcError::invalid_params("Invalid topics in filter")),
+        };
+
+        Ok(Self { addresses, topics })
+    }
+
+    pub fn matches(&self, log: &EventLog) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address.to_lowercase()) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, options)| match options {
+            None => true,
+            Some(options) => log.topics.get(position).is_some_and(|t| options.contains(&topic(t))),
+        })
+    }
+}
+
+/// Logs of `block` that match `filter`
+pub fn block_logs(block: &Block, filter: &LogFilter) -> Vec<Value> {
+    let mut logs = Vec::new();
+    let mut log_index = 0;
+    for (tx_index, receipt) in block.receipts.iter().enumerate() {
+        for log in &receipt.logs {
+            if filter.matches(log) {
+                logs.push(log_json(log, block, tx_index, log_index));
+            }
+            log_index += 1;
+        }
+    }
+    logs
+}
+
+/// Gas price paid at each requested percentile of a block's gas, weighted by gas used as
+/// geth does. With no base fee the whole price is the priority fee.
+pub fn block_rewards(block: &Block, percentiles: &[f64]) -> Vec<String> {
+    let mut prices: Vec<(u128, u64)> = block
+        .transactions
+        .iter()
+        .zip(&block.receipts)
+        .map(|(tx, receipt)| (tx.gas_price, receipt.gas_used))
+        .collect();
+    if prices.is_empty() {
+        return percentiles.iter().map(|_| "0x0".to_string()).collect();
+    }
+    prices.sort_unstable();
+
+    let total_gas: u64 = prices.iter().map(|(_, gas)| gas).sum();
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = (total_gas as f64 * percentile / 100.0) as u64;
+            let mut cumulative = 0;
+            let price = prices
+                .iter()
+                .find(|(_, gas)| {
+                    cumulative += gas;
+                    cumulative >= threshold
+                })
+                .or(prices.last())
+                .map_or(0, |(price, _)| *price);
+            quantity(price)
+        })
+        .collect()
+}
+
+/// Median gas price of the transactions in `blocks`, if any
+pub fn median_gas_price(blocks: &[Block]) -> Option<u128> {
+    let mut prices: Vec<u128> = blocks
+        .iter()
+        .flat_map(|block| block.transactions.iter().map(|tx| tx.gas_price))
+        .collect();
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort_unstable();
+    Some(prices[prices.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ActAddress;
+    use types::{BlockHeader, TransactionReceipt};
+
+    fn transfer(nonce: u64, gas_price: u128) -> Transaction {
+        Transaction {
+            from: ActAddress("ACT-sender".to_string()),
+            nonce,
+            tx_type: TransactionType::Transfer { to: "ACT-receiver".to_string(), amount: 5 },
+            gas_limit: 21000,
+            gas_price,
+            signature: vec![7; 64],
+            pubkey: vec![],
+        }
+    }
+
+    fn receipt(tx: &Transaction, gas_used: u64, topics: &[&str]) -> TransactionReceipt {
+        let logs = topics
+            .iter()
+            .enumerate()
+            .map(|(i, t)| EventLog::new("ACT-token".to_string(), vec![t.to_string()], vec![1], 7, tx.hash(), i as u32))
+            .collect();
+        TransactionReceipt {
+            transaction_hash: tx.hash(),
+            block_height: 7,
+            from: tx.from.to_string(),
+            to: Some("ACT-receiver".to_string()),
+            contract_address: None,
+            status: true,
+            gas_used,
+            logs,
+            logs_bloom: None,
+        }
+    }
+
+    fn block() -> Block {
+        let txs = vec![transfer(0, 10), transfer(1, 30)];
+        let receipts = vec![receipt(&txs[0], 21000, &["Transfer"]), receipt(&txs[1], 63000, &["Approval", "Transfer"])];
+        let header = BlockHeader {
+            parent_hash: "0".repeat(64),
+            tx_root: Block::calculate_tx_root(&txs),
+            actor_pubkey: "ACT-proposer".to_string(),
+            state_root: "ab".repeat(32),
+            receipts_root: Block::calculate_receipts_root(&receipts),
+            timestamp: 1_700_000_000,
+            validator_commitment: String::new(),
+            reward: 0,
+            height: 7,
+            round: 0,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: "0".repeat(64),
+        };
+        Block::new(header, txs, receipts)
+    }
+
+    #[test]
+    fn test_block_and_receipt_encoding() {
+        let block = block();
+
+        let json = block_json(&block, false);
+        assert_eq!(json["number"], "0x7");
+        assert_eq!(json["gasUsed"], quantity(84000u64));
+        assert_eq!(json["transactions"][1], hash(&block.transactions[1].hash()));
+        assert_eq!(block_json(&block, true)["transactions"][1]["transactionIndex"], "0x1");
+
+        // Cumulative gas and log indices run across the whole block
+        let second = receipt_json(&block, 1).unwrap();
+        assert_eq!(second["cumulativeGasUsed"], quantity(84000u64));
+        assert_eq!(second["logs"][0]["logIndex"], "0x1");
+        assert_eq!(second["logs"][1]["logIndex"], "0x2");
+        assert_eq!(second["status"], "0x1");
+        assert!(receipt_json(&block, 2).is_none());
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let block = block();
+        let transfer_topic = topic("Transfer");
+        assert_eq!(transfer_topic, data(&crypto::keccak256(b"Transfer")));
+        assert_eq!(topic(&transfer_topic), transfer_topic);
+
+        let all = LogFilter::from_json(&json!({})).unwrap();
+        assert_eq!(block_logs(&block, &all).len(), 3);
+
+        let transfers = LogFilter::from_json(&json!({ "address": "ACT-token", "topics": [transfer_topic] })).unwrap();
+        let logs = block_logs(&block, &transfers);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1]["logIndex"], "0x2");
+
+        let either = LogFilter::from_json(&json!({ "topics": [["Approval", "Transfer"]] })).unwrap();
+        assert_eq!(block_logs(&block, &either).len(), 3);
+
+        let other = LogFilter::from_json(&json!({ "address": ["ACT-other"] })).unwrap();
+        assert!(block_logs(&block, &other).is_empty());
+    }
+
+    #[test]
+    fn test_address_normalization() {
+        assert_eq!(address("0x52908400098527886E0F7030069857D2E4169EE7"), "0x52908400098527886e0f7030069857d2e4169ee7");
+        assert_eq!(address("0x52908400098527886e0f7030069857d2e4169ee7"), "0x52908400098527886e0f7030069857d2e4169ee7");
+        assert_eq!(address("ACT-Validator1"), "ACT-Validator1");
+    }
+
+    #[test]
+    fn test_fee_statistics() {
+        let block = block();
+        // 25% of the gas was paid at 10, the rest at 30
+        assert_eq!(block_rewards(&block, &[10.0, 50.0, 90.0]), vec!["0xa", "0x1e", "0x1e"]);
+        assert_eq!(median_gas_price(&[block]), Some(30));
+        assert_eq!(median_gas_price(&[]), None);
+    }
+}
@@ -1,7 +1,16 @@
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{metrics, RpcState};
+
+/// Seconds without a new block before the node is reported unhealthy (four block times)
+pub const MAX_BLOCK_AGE_SECS: u64 = 120;
+
+/// Blocks a node may trail its peers by and still count as synced
+pub const SYNC_TOLERANCE_BLOCKS: u64 = 2;
+
+/// Pending transactions above which the mempool is reported as backed up
+pub const MEMPOOL_WARNING_SIZE: usize = 10_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeHealth {
@@ -9,6 +18,7 @@ pub struct NodeHealth {
     pub uptime_seconds: u64,
     pub current_block: u64,
     pub last_block_time: u64,
+    pub last_block_age_seconds: Option<u64>,  // None before the first block
     pub peer_count: usize,
     pub mempool_size: usize,
     pub sync_status: SyncStatus,
@@ -16,136 +26,134 @@ pub struct NodeHealth {
     pub warnings: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     Healthy,
-    Degraded,
-    Unhealthy,
+    Degraded,   // Serving, but something needs attention
+    Unhealthy,  // Not keeping up with the chain
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SyncStatus {
     pub is_synced: bool,
     pub behind_blocks: u64,
+    pub highest_peer_block: Option<u64>,
+}
+
+impl SyncStatus {
+    /// Compare our tip with the highest block peers have announced
+    pub fn new(current_block: u64, highest_peer_block: Option<u64>) -> Self {
+        let behind_blocks = highest_peer_block.map_or(0, |peer| peer.saturating_sub(current_block));
+        Self {
+            is_synced: behind_blocks <= SYNC_TOLERANCE_BLOCKS,
+            behind_blocks,
+            highest_peer_block,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidatorStatus {
-    pub is_validator: bool,
+    pub address: String,
+    pub is_validator: bool,  // Registered with the staking module
     pub active: bool,
     pub blocks_proposed: u64,
     pub last_proposed: Option<u64>,
     pub stake_amount: String,
 }
 
+/// Reports node health from the shared RPC state and keeps the health gauges current
 pub struct HealthMonitor {
-    start_time: SystemTime,
-    state: Arc<RwLock<crate::JsonRpcState>>,
+    start_time: Instant,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HealthMonitor {
-    pub fn new(state: Arc<RwLock<crate::JsonRpcState>>) -> Self {
-        Self {
-            start_time: SystemTime::now(),
-            state,
-        }
+    pub fn new() -> Self {
+        Self { start_time: Instant::now() }
     }
 
-    pub async fn check_health(&self) -> NodeHealth {
+    pub async fn check_health(&self, state: &RpcState) -> NodeHealth {
         let mut warnings = Vec::new();
-        let state = self.state.read().await;
-
-        // Calculate uptime
-        let uptime_seconds = self
-            .start_time
-            .elapsed()
-            .unwrap_or_default()
-            .as_secs();
-
-        // Get current block info
-        let current_block = state.storage.get_latest_block_number().unwrap_or(0);
-        
-        let last_block_time = if let Ok(Some(block)) = state.storage.get_block(current_block) {
-            block.timestamp
-        } else {
-            0
-        };
+        let uptime_seconds = self.start_time.elapsed().as_secs();
+
+        let current_block = state.storage.get_latest_height().ok().flatten();
+        let last_block_time = current_block
+            .and_then(|height| state.storage.get_block(height).ok().flatten())
+            .map_or(0, |block| block.header.timestamp);
+        let current_block = current_block.unwrap_or(0);
 
-        // Check if we're producing blocks
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        
-        let time_since_last_block = now.saturating_sub(last_block_time);
-        
-        if time_since_last_block > 120 {
-            warnings.push(format!(
-                "No new blocks in {} seconds (expected 30s block time)",
-                time_since_last_block
-            ));
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let last_block_age_seconds = (last_block_time > 0).then(|| now.saturating_sub(last_block_time));
+        let stalled = match last_block_age_seconds {
+            Some(age) => age > MAX_BLOCK_AGE_SECS,
+            None => uptime_seconds > MAX_BLOCK_AGE_SECS,
+        };
+        if stalled {
+            warnings.push(match last_block_age_seconds {
+                Some(age) => format!("No new blocks in {} seconds", age),
+                None => "No blocks produced yet".to_string(),
+            });
         }
 
-        // Get peer count from shared state
         let peer_count = *state.peer_count.read().await;
-
         if peer_count == 0 {
             warnings.push("No peers connected".to_string());
         }
 
-        // Get mempool size
-        let mempool_size = state.mempool.get_pending_count();
-
-        if mempool_size > 10000 {
-            warnings.push(format!(
-                "Large mempool size: {} transactions",
-                mempool_size
-            ));
+        let mempool_size = state.mempool.size();
+        if mempool_size > MEMPOOL_WARNING_SIZE {
+            warnings.push(format!("Large mempool size: {} transactions", mempool_size));
         }
 
-        // Check sync status from shared state
-        let is_synced = *state.sync_status.read().await;
-        let sync_status = SyncStatus {
-            is_synced,
-            behind_blocks: if is_synced { 0 } else { 100 }, // Estimate if not synced
-        };
-        
-        if !is_synced {
-            warnings.push("Node is syncing".to_string());
+        let sync_status = SyncStatus::new(current_block, *state.peer_height.read().await);
+        if !sync_status.is_synced {
+            warnings.push(format!("Node is syncing, {} blocks behind", sync_status.behind_blocks));
         }
 
-        // Check validator status
-        let validator_status = state.staking.get_validators()
-            .into_iter()
-            .find(|v| v.address == state.validator_address)
-            .map(|v| ValidatorStatus {
-                is_validator: true,
-                active: v.active,
-                blocks_proposed: v.blocks_proposed,
-                last_proposed: Some(current_block),
-                stake_amount: v.stake.to_string(),
-            });
-
-        if let Some(ref vs) = validator_status {
-            if !vs.active {
-                warnings.push("Validator is inactive".to_string());
+        let validator_status = state.validator_address.as_deref().map(|address| validator_status(state, address));
+        if let Some(validator) = &validator_status {
+            if !validator.is_validator {
+                warnings.push(format!("{} is not a registered validator", validator.address));
+            } else if !validator.active {
+                warnings.push(format!("Validator {} is inactive", validator.address));
             }
         }
 
-        // Determine overall health status
-        let status = if warnings.is_empty() {
+        // A stalled or lagging node serves stale data; anything else only degrades it
+        let status = if stalled || !sync_status.is_synced {
+            HealthStatus::Unhealthy
+        } else if warnings.is_empty() {
             HealthStatus::Healthy
-        } else if warnings.len() <= 2 {
-            HealthStatus::Degraded
         } else {
-            HealthStatus::Unhealthy
+            HealthStatus::Degraded
         };
 
+        metrics::NODE_HEALTH.set(i64::from(status != HealthStatus::Unhealthy));
+        metrics::NODE_UPTIME.set(uptime_seconds as i64);
+        metrics::SYNC_STATUS.set(i64::from(sync_status.is_synced));
+        metrics::PEER_COUNT.set(peer_count as i64);
+        metrics::TRANSACTIONS_PENDING.set(mempool_size as i64);
+        if last_block_time > 0 {
+            metrics::CURRENT_BLOCK_NUMBER.set(current_block as i64);
+            metrics::LAST_BLOCK_TIME.set(last_block_time as i64);
+        }
+
         NodeHealth {
             status,
             uptime_seconds,
             current_block,
             last_block_time,
+            last_block_age_seconds,
             peer_count,
             mempool_size,
             sync_status,
@@ -154,60 +162,57 @@ impl HealthMonitor {
         }
     }
 
-    pub async fn get_stats(&self) -> NodeStats {
-        let state = self.state.read().await;
-        
-        let current_block = state.storage.get_latest_block_number().unwrap_or(0);
-        
-        // Calculate TPS (transactions per second) over last 100 blocks
-        let mut total_txs = 0u64;
-        let mut total_time = 0u64;
-        
-        for i in current_block.saturating_sub(100)..=current_block {
-            if let Ok(Some(block)) = state.storage.get_block(i) {
-                total_txs += block.transactions.len() as u64;
-                if i > 0 {
-                    if let Ok(Some(prev_block)) = state.storage.get_block(i - 1) {
-                        total_time += block.timestamp.saturating_sub(prev_block.timestamp);
-                    }
-                }
-            }
-        }
+    pub async fn get_stats(&self, state: &RpcState) -> NodeStats {
+        let current_block = state.storage.get_latest_height().ok().flatten().unwrap_or(0);
 
-        let tps = if total_time > 0 {
-            total_txs as f64 / total_time as f64
-        } else {
-            0.0
-        };
+        // Transactions per second over the last 100 blocks
+        let recent = state.storage.get_recent_blocks(100).unwrap_or_default();
+        let total_txs: u64 = recent.iter().map(|block| block.transactions.len() as u64).sum();
+        let timestamps = recent.iter().map(|block| block.header.timestamp);
+        let total_time = timestamps.clone().max().unwrap_or(0).saturating_sub(timestamps.min().unwrap_or(0));
+        let tps = if total_time > 0 { total_txs as f64 / total_time as f64 } else { 0.0 };
+
+        // Records of the latest state; none if they cannot be read
+        let records = crate::system_state(state, None).ok();
+        let (validator_count, total_staked) = records
+            .as_ref()
+            .map_or((0, 0), |r| (r.staking.get_all_validators().len(), r.staking.get_total_staked()));
 
-        // Get staking stats
-        let validators = state.staking.get_validators();
-        let total_staked: u128 = validators.iter().map(|v| v.stake).sum();
-        
-        // Get governance stats
-        let proposals = state.governance.get_proposals();
+        let proposals = records.map(|r| r.governance.list_proposals(None)).unwrap_or_default();
         let active_proposals = proposals
             .iter()
-            .filter(|p| matches!(p.status, crate::governance::ProposalStatus::Active))
+            .filter(|p| matches!(p.status, governance::ProposalStatus::Active))
             .count();
 
         NodeStats {
             current_block,
             total_transactions: total_txs,
             tps,
-            validator_count: validators.len(),
+            validator_count,
             total_staked: total_staked.to_string(),
             proposal_count: proposals.len(),
             active_proposals,
-            mempool_size: state.mempool.get_pending_count(),
+            mempool_size: state.mempool.size(),
         }
     }
 }
 
+fn validator_status(state: &RpcState, address: &str) -> ValidatorStatus {
+    let validator = crate::system_state(state, None).ok().and_then(|r| r.staking.get_validator(address));
+    ValidatorStatus {
+        address: address.to_string(),
+        is_validator: validator.is_some(),
+        active: validator.as_ref().is_some_and(|v| v.active),
+        blocks_proposed: validator.as_ref().map_or(0, |v| v.total_blocks),
+        last_proposed: validator.as_ref().filter(|v| v.total_blocks > 0).map(|v| v.last_block),
+        stake_amount: validator.as_ref().map_or(0, |v| v.total_stake()).to_string(),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeStats {
     pub current_block: u64,
-    pub total_transactions: u64,
+    pub total_transactions: u64,  // In the blocks `tps` is measured over
     pub tps: f64,
     pub validator_count: usize,
     pub total_staked: String,
@@ -215,3 +220,19 @@ pub struct NodeStats {
     pub active_proposals: usize,
     pub mempool_size: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_status() {
+        assert!(SyncStatus::new(10, None).is_synced);
+        assert!(SyncStatus::new(10, Some(12)).is_synced);
+        assert!(SyncStatus::new(20, Some(12)).is_synced);
+
+        let behind = SyncStatus::new(10, Some(50));
+        assert!(!behind.is_synced);
+        assert_eq!(behind.behind_blocks, 40);
+    }
+}
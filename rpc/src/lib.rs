@@ -1,34 +1,87 @@
 use anyhow::Result;
 use axum::{
-    extract::State as AxumState,
-    http::StatusCode,
+    body::Body,
+    extract::{ConnectInfo, State as AxumState},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Once};
 use tower_http::cors::CorsLayer;
 
-use mempool::Mempool;
-use state::StateManager;
-use staking::StakingManager;
-use governance::GovernanceManager;
-use types::{ActAmount, Transaction};
-
+use executor::{BlockContext, CallRequest, Executor, SystemState};
+use runtime::{ExecutionResult, WasmRuntime};
+use mempool::{Mempool, MAX_GAS_LIMIT, MIN_GAS_LIMIT};
+use state::{StateManager, StateOverlay};
+use storage::BlockchainStorage;
+use types::{ActAmount, Block, Transaction};
+
+mod auth;
+mod ethereum;
+pub mod health;
+mod limits;
 pub mod metrics;
+mod namespaces;
+pub mod openrpc;
+pub mod subscriptions;
 
 use metrics::init_metrics;
+use health::{HealthMonitor, HealthStatus};
+use limits::RateLimiter;
+use namespaces::Registry;
+pub use auth::RpcAuth;
+pub use limits::{RateLimit, Rejection, RpcLimits, DEFAULT_MAX_BODY_BYTES, MAX_BATCH_SIZE};
+pub use namespaces::{NamespaceConfig, NAMESPACES};
 
 /// RPC Server state
 #[derive(Clone)]
 pub struct RpcState {
     pub state_manager: Arc<StateManager>,
+    pub storage: Arc<BlockchainStorage>,
     pub mempool: Arc<Mempool>,
-    pub staking_manager: Arc<tokio::sync::Mutex<StakingManager>>,
-    pub governance_manager: Arc<tokio::sync::Mutex<GovernanceManager>>,
     pub peer_count: Arc<tokio::sync::RwLock<usize>>,
-    pub sync_status: Arc<tokio::sync::RwLock<bool>>,
+    pub peer_height: Arc<tokio::sync::RwLock<Option<u64>>>,  // Highest block height announced by peers
+    pub validator_address: Option<String>,  // Validator this node runs, if any
+    pub health: Arc<HealthMonitor>,
+    pub new_blocks: tokio::sync::broadcast::Sender<Arc<Block>>,
+    pub runtime: WasmRuntime,  // Shared with block execution, so calls reuse its compiled modules
+    registry: Arc<Registry>,  // Methods this listener serves
+    limiter: Arc<RateLimiter>,  // Size caps and per-client rate limits of this listener
+    auth: Arc<RpcAuth>,
+}
+
+/// An address the RPC server listens on, the namespaces served there and who may use them
+#[derive(Debug, Clone)]
+pub struct RpcListener {
+    pub addr: SocketAddr,
+    pub namespaces: NamespaceConfig,
+    pub limits: RpcLimits,
+    pub auth: RpcAuth,
+}
+
+impl RpcListener {
+    /// All interfaces, serving what is safe to expose to anyone, rate limited per client
+    pub fn public(port: u16) -> Self {
+        Self {
+            addr: SocketAddr::from(([0, 0, 0, 0], port)),
+            namespaces: NamespaceConfig::public(),
+            limits: RpcLimits::default(),
+            auth: RpcAuth::default(),
+        }
+    }
+
+    /// Loopback only, serving every namespace without a rate limit
+    pub fn local(port: u16) -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            namespaces: NamespaceConfig::all(),
+            limits: RpcLimits::unlimited(),
+            auth: RpcAuth::default(),
+        }
+    }
 }
 
 /// JSON-RPC 2.0 Request
@@ -36,7 +89,9 @@ pub struct RpcState {
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
     pub method: String,
+    #[serde(default)]
     pub params: serde_json::Value,
+    #[serde(default)]
     pub id: serde_json::Value,
 }
 
@@ -55,6 +110,8 @@ pub struct JsonRpcResponse {
 pub struct JsonRpcError {
     pub code: i32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 /// Balance query parameters
@@ -92,82 +149,44 @@ pub struct MempoolStatus {
     pub avg_gas_price: ActAmount,
 }
 
-/// Staking deposit parameters
-#[derive(Debug, Deserialize)]
-pub struct StakeDepositParams {
-    pub address: String,
-    pub amount: u64,
-    pub commission_rate: u8,
-}
-
-/// Delegate parameters
-#[derive(Debug, Deserialize)]
-pub struct DelegateParams {
-    pub delegator_address: String,
-    pub validator_address: String,
-    pub amount: u64,
-}
-
-/// Unstake parameters
-#[derive(Debug, Deserialize)]
-pub struct UnstakeParams {
-    pub address: String,
-    pub amount: u64,
-}
-
-/// Undelegate parameters
-#[derive(Debug, Deserialize)]
-pub struct UndelegateParams {
-    pub delegator_address: String,
-    pub validator_address: String,
-    pub amount: u64,
-}
-
 /// Claim parameters
 #[derive(Debug, Deserialize)]
 pub struct ClaimParams {
     pub address: String,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// Get validator parameters
 #[derive(Debug, Deserialize)]
 pub struct GetValidatorParams {
     pub address: String,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// Get validators parameters
 #[derive(Debug, Deserialize)]
 pub struct GetValidatorsParams {
     pub active_only: bool,
-}
-
-/// Governance propose parameters
-#[derive(Debug, Deserialize)]
-pub struct ProposeParams {
-    pub proposer: String,
-    pub proposal_type: serde_json::Value,
-    pub title: String,
-    pub description: String,
-}
-
-/// Governance vote parameters
-#[derive(Debug, Deserialize)]
-pub struct VoteParams {
-    pub proposal_id: u64,
-    pub voter: String,
-    pub vote_option: String,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// Get proposal parameters
 #[derive(Debug, Deserialize)]
 pub struct GetProposalParams {
     pub proposal_id: u64,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// List proposals parameters
 #[derive(Debug, Deserialize)]
 pub struct ListProposalsParams {
     pub status: Option<String>,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// Get vote parameters
@@ -175,6 +194,8 @@ pub struct ListProposalsParams {
 pub struct GetVoteParams {
     pub proposal_id: u64,
     pub voter: String,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// Get voting power parameters
@@ -188,6 +209,8 @@ pub struct GetVotingPowerParams {
 #[derive(Debug, Deserialize)]
 pub struct GetTallyParams {
     pub proposal_id: u64,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
 }
 
 /// Get logs parameters
@@ -207,616 +230,349 @@ pub struct GetReceiptParams {
     pub tx_hash: String,
 }
 
+/// Read-only contract call parameters
+#[derive(Debug, Deserialize)]
+pub struct ContractCallParams {
+    pub to: String,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default = "default_call_method")]
+    pub method: String,
+    #[serde(default)]
+    pub data: Option<String>,  // hex-encoded input
+    #[serde(default)]
+    pub value: ActAmount,
+    #[serde(default)]
+    pub gas: Option<u64>,
+    #[serde(default)]
+    pub block: Option<u64>,  // Latest state when absent
+}
+
+fn default_call_method() -> String {
+    "execute".to_string()
+}
+
+/// Gas available to read-only calls; requests asking for more are capped
+pub const CALL_GAS_CAP: u64 = 50_000_000;
+
+/// Caller used by read-only calls that do not set `from`
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Headroom added to gas estimates, in percent
+pub const GAS_ESTIMATE_MARGIN_PERCENT: u64 = 10;
+
+/// Get block by height parameters
+#[derive(Debug, Deserialize)]
+pub struct GetBlockParams {
+    pub height: u64,
+}
+
+/// Get block by hash parameters
+#[derive(Debug, Deserialize)]
+pub struct GetBlockByHashParams {
+    pub hash: String,
+}
+
 impl RpcState {
-    pub fn new(state_manager: Arc<StateManager>, mempool: Arc<Mempool>, staking_manager: Arc<tokio::sync::Mutex<StakingManager>>, governance_manager: Arc<tokio::sync::Mutex<GovernanceManager>>) -> Self {
+    pub fn new(state_manager: Arc<StateManager>, storage: Arc<BlockchainStorage>, mempool: Arc<Mempool>, runtime: WasmRuntime) -> Self {
         Self {
             state_manager,
+            storage,
             mempool,
-            staking_manager,
-            governance_manager,
             peer_count: Arc::new(tokio::sync::RwLock::new(0)),
-            sync_status: Arc::new(tokio::sync::RwLock::new(true)),
+            peer_height: Arc::new(tokio::sync::RwLock::new(None)),
+            validator_address: None,
+            health: Arc::new(HealthMonitor::new()),
+            new_blocks: tokio::sync::broadcast::channel(subscriptions::BLOCK_EVENTS_CAPACITY).0,
+            runtime,
+            registry: Arc::new(Registry::new(NamespaceConfig::public())),
+            limiter: Arc::new(RateLimiter::new(RpcLimits::default())),
+            auth: Arc::new(RpcAuth::default()),
         }
     }
-}
-
-/// Custom error type for RPC responses
-struct RpcError(String);
 
-impl IntoResponse for RpcError {
-    fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32000,
-                    message: self.0,
-                }),
-                id: serde_json::Value::Null,
-            }),
-        )
-            .into_response()
+    /// Same node state, serving the given namespaces
+    pub fn with_namespaces(mut self, namespaces: NamespaceConfig) -> Self {
+        self.registry = Arc::new(Registry::new(namespaces));
+        self
     }
-}
-
-/// Health check endpoint
-async fn health_check() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "healthy",
-        "service": "ACT Blockchain RPC",
-        "version": "0.1.0"
-    }))
-}
 
-/// Handle JSON-RPC requests
-async fn handle_rpc(
-    AxumState(state): AxumState<RpcState>,
-    Json(request): Json<JsonRpcRequest>,
-) -> Result<Json<JsonRpcResponse>, RpcError> {
-    println!("📨 RPC request: {} (id: {})", request.method, request.id);
-
-    let result = match request.method.as_str() {
-        "act_getBalance" => {
-            let params: GetBalanceParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let balance = state
-                .state_manager
-                .get_balance(&params.address)
-                .map_err(|e| RpcError(format!("Failed to get balance: {}", e)))?;
-            
-            serde_json::to_value(balance)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    /// Same node state, with its own rate limit buckets
+    pub fn with_limits(mut self, limits: RpcLimits) -> Self {
+        self.limiter = Arc::new(RateLimiter::new(limits));
+        self
+    }
 
-        "act_getAccount" => {
-            let params: GetBalanceParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let balance = state
-                .state_manager
-                .get_balance(&params.address)
-                .unwrap_or(0);
-            
-            let nonce = state
-                .state_manager
-                .get_nonce(&params.address)
-                .unwrap_or(0);
-            
-            let account_info = AccountInfo {
-                address: params.address,
-                balance,
-                nonce,
-            };
-            
-            serde_json::to_value(account_info)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    pub fn with_auth(mut self, auth: RpcAuth) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
 
-        "act_getNonce" => {
-            let params: GetBalanceParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let nonce = state
-                .state_manager
-                .get_nonce(&params.address)
-                .map_err(|e| RpcError(format!("Failed to get nonce: {}", e)))?;
-            
-            serde_json::to_value(nonce)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    /// Report the status of this validator in `/health`
+    pub fn with_validator(mut self, address: impl Into<String>) -> Self {
+        self.validator_address = Some(address.into());
+        self
+    }
 
-        "act_sendTransaction" => {
-            let params: SendTransactionParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let tx_hash = state
-                .mempool
-                .add_transaction(params.transaction, &state.state_manager)
-                .map_err(|e| RpcError(format!("Transaction rejected: {}", e)))?;
-            
-            println!("✅ Transaction accepted: {}...", &tx_hash[..16]);
-            
-            let receipt = TransactionReceipt {
-                tx_hash,
-                status: "pending".to_string(),
-            };
-            
-            serde_json::to_value(receipt)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+    /// Record the height of a block a peer announced
+    pub async fn note_peer_height(&self, height: u64) {
+        let mut highest = self.peer_height.write().await;
+        if highest.is_none_or(|highest| height > highest) {
+            *highest = Some(height);
         }
+    }
 
-        "act_getTransaction" => {
-            let params: serde_json::Map<String, serde_json::Value> =
-                serde_json::from_value(request.params)
-                    .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let tx_hash = params
-                .get("tx_hash")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| RpcError("Missing tx_hash parameter".to_string()))?;
-            
-            let tx = state.mempool.get_transaction(tx_hash);
-            
-            serde_json::to_value(tx)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    /// Notify `newHeads` and `logs` subscribers of a block that has been stored
+    pub fn publish_block(&self, block: &Block) {
+        // Nobody may be listening
+        let _ = self.new_blocks.send(Arc::new(block.clone()));
+    }
+}
 
-        "act_getPendingTransactions" => {
-            let params: GetBalanceParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let txs = state.mempool.get_pending_transactions(&params.address);
-            
-            serde_json::to_value(txs)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// JSON-RPC 2.0 error codes
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+pub const SERVER_ERROR: i32 = -32000;  // A well-formed call that failed while executing
+pub const UNAUTHORIZED: i32 = -32001;  // Privileged method called without a valid API key
+pub const LIMIT_EXCEEDED: i32 = -32005;  // Rate limit or size cap hit
 
-        "act_getMempoolStatus" => {
-            let stats = state.mempool.get_stats();
-            
-            let status = MempoolStatus {
-                pending_transactions: stats.total_transactions,
-                unique_senders: stats.unique_senders,
-                avg_gas_price: stats.avg_gas_price,
-            };
-            
-            serde_json::to_value(status)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Error returned by a method, sent to the client as a JSON-RPC error object
+#[derive(Debug)]
+struct RpcError {
+    code: i32,
+    message: String,
+    data: Option<serde_json::Value>,
+}
 
-        "act_getLogs" => {
-            let params: GetLogsParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let logs = state
-                .state_manager
-                .query_logs(
-                    params.address.as_deref(),
-                    params.topics,
-                    params.from_block,
-                    params.to_block,
-                )
-                .map_err(|e| RpcError(format!("Failed to query logs: {}", e)))?;
-            
-            println!("📜 Queried {} event logs", logs.len());
-            
-            serde_json::to_value(logs)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
         }
+    }
 
-        "act_getTransactionReceipt" => {
-            let params: GetReceiptParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let receipt = state
-                .state_manager
-                .get_receipt(&params.tx_hash)
-                .map_err(|e| RpcError(format!("Failed to get receipt: {}", e)))?;
-            
-            serde_json::to_value(receipt)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(INVALID_REQUEST, message)
+    }
 
-        // Ethereum-compatible RPC methods
-        "eth_blockNumber" => {
-            // Return latest block height in hex
-            // StateManager doesn't track block height, use 0 for now
-            let height = 0u64;
-            serde_json::to_value(format!("0x{:x}", height))
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    fn method_not_found(method: &str) -> Self {
+        Self::new(METHOD_NOT_FOUND, format!("Method not found: {}", method))
+    }
 
-        "eth_getBalance" => {
-            let params: serde_json::Value = request.params;
-            let address = params
-                .get(0)
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| RpcError("Missing address parameter".to_string()))?;
-            
-            // Convert Ethereum address to ACT address or query directly
-            let balance = state
-                .state_manager
-                .get_balance(address)
-                .unwrap_or(0);
-            
-            // Return balance in hex
-            serde_json::to_value(format!("0x{:x}", balance))
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
 
-        "eth_getTransactionCount" => {
-            let params: serde_json::Value = request.params;
-            let address = params
-                .get(0)
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| RpcError("Missing address parameter".to_string()))?;
-            
-            let nonce = state
-                .state_manager
-                .get_nonce(address)
-                .unwrap_or(0);
-            
-            serde_json::to_value(format!("0x{:x}", nonce))
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    fn server(message: impl Into<String>) -> Self {
+        Self::new(SERVER_ERROR, message)
+    }
 
-        "eth_sendRawTransaction" => {
-            let params: serde_json::Value = request.params;
-            let raw_tx = params
-                .get(0)
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| RpcError("Missing raw transaction parameter".to_string()))?;
-            
-            // Remove 0x prefix if present
-            let raw_tx = raw_tx.strip_prefix("0x").unwrap_or(raw_tx);
-            
-            // Basic validation - in production would decode RLP
-            if raw_tx.len() < 64 {
-                return Err(RpcError("Invalid transaction data".to_string()));
-            }
-            
-            // For now, return a mock transaction hash
-            // Full implementation would decode RLP and submit to mempool
-            let tx_hash = format!("0x{}", raw_tx.chars().take(64).collect::<String>());
-            
-            serde_json::to_value(tx_hash)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    fn limit_exceeded(message: impl Into<String>) -> Self {
+        Self::new(LIMIT_EXCEEDED, message)
+    }
 
-        "eth_call" => {
-            let params: serde_json::Value = request.params;
-            let call_obj = params
-                .get(0)
-                .ok_or_else(|| RpcError("Missing call object parameter".to_string()))?;
-            
-            // Extract call parameters
-            let to = call_obj.get("to")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| RpcError("Missing 'to' address".to_string()))?;
-            
-            let _data = call_obj.get("data")
-                .and_then(|v| v.as_str())
-                .unwrap_or("0x");
-            
-            // Execute read-only call against state
-            // For now, return empty result (full contract execution would go here)
-            let _account = state.state_manager.get_account(to);
-            let result = "0x".to_string();
-            
-            serde_json::to_value(result)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    /// Attach machine-readable detail, such as the return data of a reverted call
+    fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
 
-        "eth_chainId" => {
-            // Return ACT Chain ID (e.g., 0xACT = 2755)
-            serde_json::to_value("0xac7")
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+impl JsonRpcResponse {
+    fn new(id: serde_json::Value, result: Result<serde_json::Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(e) => (None, Some(JsonRpcError { code: e.code, message: e.message, data: e.data })),
+        };
+        Self {
+            jsonrpc: "2.0".to_string(),
+            result,
+            error,
+            id,
         }
+    }
+}
 
-        "net_version" => {
-            // Network version (same as chain ID)
-            serde_json::to_value("2755")
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Body of an HTTP request or WebSocket message: one call or a batch of them
+enum RpcPayload {
+    Single(serde_json::Value),
+    Batch(Vec<serde_json::Value>),
+}
 
-        // Staking methods
-        "stake_deposit" => {
-            let params: StakeDepositParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let mut staking = state.staking_manager.lock().await;
-            let validator_address = staking
-                .stake(params.address, params.amount, params.commission_rate)
-                .map_err(|e| RpcError(format!("Stake failed: {}", e)))?;
-            
-            serde_json::to_value(validator_address)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+/// Parse a request body, failing for one that is not JSON-RPC at all
+fn parse_payload(body: &str, max_batch_size: usize) -> Result<RpcPayload, RpcError> {
+    match serde_json::from_str(body) {
+        Err(e) => Err(RpcError::new(PARSE_ERROR, format!("Parse error: {}", e))),
+        Ok(serde_json::Value::Array(calls)) if calls.is_empty() => {
+            Err(RpcError::invalid_request("Invalid request: empty batch"))
         }
-
-        "stake_delegate" => {
-            let params: DelegateParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let mut staking = state.staking_manager.lock().await;
-            staking
-                .delegate(params.delegator_address, params.validator_address, params.amount)
-                .map_err(|e| RpcError(format!("Delegation failed: {}", e)))?;
-            
-            serde_json::to_value("Delegation successful")
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+        Ok(serde_json::Value::Array(calls)) if calls.len() > max_batch_size => {
+            Rejection::BatchTooLarge.record();
+            Err(RpcError::invalid_request(format!("Invalid request: batch of {} calls exceeds {}", calls.len(), max_batch_size))
+                .with_data(serde_json::json!({ "maxBatchSize": max_batch_size })))
         }
+        Ok(serde_json::Value::Array(calls)) => Ok(RpcPayload::Batch(calls)),
+        Ok(call) => Ok(RpcPayload::Single(call)),
+    }
+}
 
-        "stake_unstake" => {
-            let params: UnstakeParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let mut staking = state.staking_manager.lock().await;
-            let request_id = staking
-                .unstake(params.address, params.amount)
-                .map_err(|e| RpcError(format!("Unstake failed: {}", e)))?;
-            
-            serde_json::to_value(request_id)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Validate one call of a payload, or produce the error reply for it. Also returns
+/// whether the caller expects a reply: calls without an `id` are notifications.
+fn parse_call(call: serde_json::Value) -> Result<(JsonRpcRequest, bool), Box<JsonRpcResponse>> {
+    let id = call.get("id").cloned();
+    let reject = |message: String| {
+        Box::new(JsonRpcResponse::new(id.clone().unwrap_or_default(), Err(RpcError::invalid_request(message))))
+    };
 
-        "stake_undelegate" => {
-            let params: UndelegateParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let mut staking = state.staking_manager.lock().await;
-            let request_id = staking
-                .undelegate(params.delegator_address, params.validator_address, params.amount)
-                .map_err(|e| RpcError(format!("Undelegation failed: {}", e)))?;
-            
-            serde_json::to_value(request_id)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    let request: JsonRpcRequest =
+        serde_json::from_value(call).map_err(|e| reject(format!("Invalid request: {}", e)))?;
+    if request.jsonrpc != "2.0" {
+        return Err(reject(format!("Invalid request: unsupported jsonrpc version {:?}", request.jsonrpc)));
+    }
+    Ok((request, id.is_some()))
+}
 
-        "stake_claimUnstaked" => {
-            let params: ClaimParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let mut staking = state.staking_manager.lock().await;
-            let amount = staking
-                .claim_unstaked(params.address)
-                .map_err(|e| RpcError(format!("Claim failed: {}", e)))?;
-            
-            serde_json::to_value(amount)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Who sent a payload, as far as limits and authorization are concerned
+#[derive(Debug, Clone, Copy)]
+struct Caller {
+    ip: IpAddr,
+    authorized: bool,  // Presented a valid API key, or none is required
+}
 
-        "stake_claimRewards" => {
-            let params: ClaimParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let mut staking = state.staking_manager.lock().await;
-            let rewards = staking
-                .claim_rewards(params.address)
-                .map_err(|e| RpcError(format!("Claim rewards failed: {}", e)))?;
-            
-            serde_json::to_value(rewards)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+impl Caller {
+    fn new(state: &RpcState, peer: SocketAddr, headers: &HeaderMap) -> Self {
+        Self {
+            ip: peer.ip(),
+            authorized: state.auth.authorize(headers),
         }
+    }
+}
 
-        "stake_getValidator" => {
-            let params: GetValidatorParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let staking = state.staking_manager.lock().await;
-            let validator = staking.get_validator(&params.address);
-            
-            serde_json::to_value(validator)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Charge a payload to its sender's rate limit, with the error to reply when refused
+fn charge(state: &RpcState, caller: &Caller, payload: &Result<RpcPayload, RpcError>) -> Result<(), (RpcError, Option<std::time::Duration>)> {
+    let cost = match payload {
+        Ok(payload) => state.limiter.limits().payload_cost(payload),
+        Err(_) => 1,  // Unparseable requests still cost the node something
+    };
 
-        "stake_getValidators" => {
-            let params: GetValidatorsParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let staking = state.staking_manager.lock().await;
-            let validators = if params.active_only {
-                staking.get_active_validators()
-            } else {
-                staking.get_all_validators()
-            };
-            
-            serde_json::to_value(validators)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    state.limiter.check(caller.ip, cost).map_err(|retry_after| {
+        Rejection::RateLimited.record();
+        let error = match retry_after {
+            Some(wait) => RpcError::limit_exceeded("Rate limit exceeded")
+                .with_data(serde_json::json!({ "retryAfterMs": wait.as_millis() as u64 })),
+            None => RpcError::limit_exceeded(format!("Request cost {} exceeds the rate limit burst", cost)),
+        };
+        (error, retry_after)
+    })
+}
 
-        "stake_getDelegations" => {
-            let params: ClaimParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let staking = state.staking_manager.lock().await;
-            let delegations = staking.get_delegations(&params.address);
-            
-            serde_json::to_value(delegations)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Health check endpoint, answering 503 while the node is unhealthy so load balancers
+/// and alerting can act on the status code alone
+async fn health_check(AxumState(state): AxumState<RpcState>) -> Response {
+    let health = state.health.check_health(&state).await;
+    let status = match health.status {
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+    };
 
-        "stake_getUnstakeRequests" => {
-            let params: ClaimParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let staking = state.staking_manager.lock().await;
-            let requests = staking.get_unstake_requests(&params.address);
-            
-            serde_json::to_value(requests)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    let mut body = serde_json::json!(health);
+    body["service"] = serde_json::json!("ACT Blockchain RPC");
+    body["version"] = serde_json::json!(env!("CARGO_PKG_VERSION"));
+    (status, Json(body)).into_response()
+}
 
-        "stake_getRewards" => {
-            let params: ClaimParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let staking = state.staking_manager.lock().await;
-            let rewards = staking.get_unclaimed_rewards(&params.address);
-            
-            serde_json::to_value(rewards)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Error reply for a request refused as a whole
+fn reject(status: StatusCode, error: RpcError) -> Response {
+    (status, Json(JsonRpcResponse::new(serde_json::Value::Null, Err(error)))).into_response()
+}
 
-        // Governance methods
-        "gov_propose" => {
-            let params: ProposeParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let proposal_type: governance::ProposalType = serde_json::from_value(params.proposal_type)
-                .map_err(|e| RpcError(format!("Invalid proposal type: {}", e)))?;
-            
-            let proposer_balance = state
-                .state_manager
-                .get_balance(&params.proposer)
-                .unwrap_or(0);
-            
-            // Calculate total supply from circulating supply
-            // Genesis: 13B ACT, validators can mint through staking rewards
-            let total_supply = 13_000_000_000_000_000_000_000_000u128; // 13M ACT base supply
-            
-            let mut governance = state.governance_manager.lock().await;
-            let proposal_id = governance
-                .create_proposal(
-                    params.proposer,
-                    proposal_type,
-                    params.title,
-                    params.description,
-                    proposer_balance.try_into().unwrap_or(u64::MAX),
-                    total_supply.try_into().unwrap_or(u64::MAX),
-                )
-                .map_err(|e| RpcError(format!("Proposal creation failed: {}", e)))?;;
-            
-            serde_json::to_value(proposal_id)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Handle JSON-RPC requests, single or batched
+async fn handle_rpc(
+    AxumState(state): AxumState<RpcState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let max_body_bytes = state.limiter.limits().max_body_bytes;
+    let Ok(body) = axum::body::to_bytes(body, max_body_bytes).await else {
+        Rejection::BodyTooLarge.record();
+        let error = RpcError::limit_exceeded(format!("Request body exceeds {} bytes", max_body_bytes))
+            .with_data(serde_json::json!({ "maxBodyBytes": max_body_bytes }));
+        return reject(StatusCode::PAYLOAD_TOO_LARGE, error);
+    };
 
-        "gov_vote" => {
-            let params: VoteParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let vote_option = match params.vote_option.as_str() {
-                "Yes" => governance::VoteOption::Yes,
-                "No" => governance::VoteOption::No,
-                "Abstain" => governance::VoteOption::Abstain,
-                _ => return Err(RpcError("Invalid vote option. Use 'Yes', 'No', or 'Abstain'".to_string())),
-            };
-            
-            // Calculate vote power (balance + staking)
-            let balance = state
-                .state_manager
-                .get_balance(&params.voter)
-                .unwrap_or(0);
-            
-            let staking = state.staking_manager.lock().await;
-            let validator_stake = staking
-                .get_validator(&params.voter)
-                .map(|v| v.stake + v.delegated_stake)
-                .unwrap_or(0);
-            
-            let delegator_stake: u64 = staking
-                .get_delegations(&params.voter)
-                .iter()
-                .map(|d| d.amount)
-                .sum();
-            
-            let vote_power = balance + (validator_stake as u128) + (delegator_stake as u128);
-            drop(staking);
-            
-            let mut governance = state.governance_manager.lock().await;
-            governance
-                .cast_vote(params.proposal_id, params.voter, vote_option, vote_power.try_into().unwrap_or(u64::MAX))
-                .map_err(|e| RpcError(format!("Vote failed: {}", e)))?;;
-            
-            serde_json::to_value("Vote cast successfully")
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    let caller = Caller::new(&state, peer, &headers);
+    let payload = std::str::from_utf8(&body)
+        .map_err(|e| RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)))
+        .and_then(|body| parse_payload(body, state.limiter.limits().max_batch_size));
+    if let Err((error, retry_after)) = charge(&state, &caller, &payload) {
+        let mut response = reject(StatusCode::TOO_MANY_REQUESTS, error);
+        if let Some(wait) = retry_after {
+            let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            response.headers_mut().insert(header::RETRY_AFTER, seconds.into());
+        }
+        return response;
+    }
 
-        "gov_getProposal" => {
-            let params: GetProposalParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let governance = state.governance_manager.lock().await;
-            let proposal = governance.get_proposal(params.proposal_id);
-            
-            serde_json::to_value(proposal)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
+    let replies = match payload {
+        Err(error) => serde_json::json!(JsonRpcResponse::new(serde_json::Value::Null, Err(error))),
+        Ok(RpcPayload::Single(call)) => match handle_call(&state, &caller, call).await {
+            Some(reply) => serde_json::json!(reply),
+            None => return StatusCode::NO_CONTENT.into_response(),
+        },
+        Ok(RpcPayload::Batch(calls)) => {
+            let mut replies = Vec::new();
+            for call in calls {
+                replies.extend(handle_call(&state, &caller, call).await);
+            }
+            // A batch of notifications gets no reply at all
+            if replies.is_empty() {
+                return StatusCode::NO_CONTENT.into_response();
+            }
+            serde_json::json!(replies)
         }
+    };
 
-        "gov_listProposals" => {
-            let params: ListProposalsParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let status_filter = params.status.and_then(|s| {
-                match s.as_str() {
-                    "Review" => Some(governance::ProposalStatus::Review),
-                    "Active" => Some(governance::ProposalStatus::Active),
-                    "Passed" => Some(governance::ProposalStatus::Passed),
-                    "Rejected" => Some(governance::ProposalStatus::Rejected),
-                    "Expired" => Some(governance::ProposalStatus::Expired),
-                    "Executed" => Some(governance::ProposalStatus::Executed),
-                    "Failed" => Some(governance::ProposalStatus::Failed),
-                    "Vetoed" => Some(governance::ProposalStatus::Vetoed),
-                    _ => None,
-                }
-            });
-            
-            let governance = state.governance_manager.lock().await;
-            let proposals = governance.list_proposals(status_filter);
-            
-            serde_json::to_value(proposals)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    Json(replies).into_response()
+}
 
-        "gov_getVote" => {
-            let params: GetVoteParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let governance = state.governance_manager.lock().await;
-            let vote = governance.get_vote(params.proposal_id, &params.voter);
-            
-            serde_json::to_value(vote)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Answer one call of a payload; `None` for notifications
+async fn handle_call(state: &RpcState, caller: &Caller, call: serde_json::Value) -> Option<JsonRpcResponse> {
+    let (request, expects_reply) = match parse_call(call) {
+        Ok(parsed) => parsed,
+        Err(error) => return Some(*error),
+    };
+    let id = request.id.clone();
+    let result = dispatch(state, caller, request).await;
 
-        "gov_getVotingPower" => {
-            let params: GetVotingPowerParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            // Calculate voting power
-            let balance = state
-                .state_manager
-                .get_balance(&params.address)
-                .unwrap_or(0);
-            
-            let staking = state.staking_manager.lock().await;
-            let validator_stake = staking
-                .get_validator(&params.address)
-                .map(|v| v.stake + v.delegated_stake)
-                .unwrap_or(0);
-            
-            let delegator_stake: u64 = staking
-                .get_delegations(&params.address)
-                .iter()
-                .map(|d| d.amount)
-                .sum();
-            
-            let total_power = balance + (validator_stake as u128) + (delegator_stake as u128);
-            
-            serde_json::to_value(total_power)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+    expects_reply.then(|| JsonRpcResponse::new(id, result))
+}
 
-        "gov_getTallyResult" => {
-            let params: GetTallyParams = serde_json::from_value(request.params)
-                .map_err(|e| RpcError(format!("Invalid params: {}", e)))?;
-            
-            let governance = state.governance_manager.lock().await;
-            let tally = governance
-                .get_tally_result(params.proposal_id)
-                .map_err(|e| RpcError(format!("Failed to get tally: {}", e)))?;
-            
-            serde_json::to_value(tally)
-                .map_err(|e| RpcError(format!("Serialization error: {}", e)))?
-        }
+/// Execute a single JSON-RPC method call, over HTTP or WebSocket
+async fn dispatch(state: &RpcState, caller: &Caller, request: JsonRpcRequest) -> Result<serde_json::Value, RpcError> {
+    println!("📨 RPC request: {} (id: {})", request.method, request.id);
 
-        _ => {
-            return Err(RpcError(format!("Method not found: {}", request.method)));
-        }
-    };
+    let protected = state.registry.namespace_of(&request.method).is_some_and(|namespace| state.auth.protects(namespace));
+    if protected && !caller.authorized {
+        Rejection::Unauthorized.record();
+        return Err(RpcError::new(UNAUTHORIZED, format!("{} requires an API key", request.method)));
+    }
 
-    Ok(Json(JsonRpcResponse {
-        jsonrpc: "2.0".to_string(),
-        result: Some(result),
-        error: None,
-        id: request.id,
-    }))
+    match request.method.as_str() {
+        "rpc_discover" => Ok(openrpc::document(|method| state.registry.exposes(method))),
+        "rpc_modules" => Ok(state.registry.modules()),
+        method => state.registry.call(state, method, request.params).await,
+    }
 }
 
 /// Prometheus metrics endpoint
-async fn metrics_handler() -> impl IntoResponse {
+async fn metrics_handler(AxumState(state): AxumState<RpcState>) -> impl IntoResponse {
+    // Sampled gauges (uptime, peers, sync, mempool) are refreshed on each scrape
+    state.health.check_health(&state).await;
     (
         StatusCode::OK,
         [("Content-Type", "text/plain; version=0.0.4")],
@@ -824,93 +580,190 @@ async fn metrics_handler() -> impl IntoResponse {
     )
 }
 
-/// Node statistics endpoint
-async fn stats_handler(AxumState(state): AxumState<RpcState>) -> impl IntoResponse {
-    // Collect current stats using existing public methods
-    let validators = state.staking_manager.lock().await.get_all_validators();
-    let total_staked: u64 = validators.iter().map(|v| v.stake).sum();
+/// Parse a `0x`-prefixed hex quantity
+fn parse_quantity(value: &str) -> Result<ActAmount, RpcError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    ActAmount::from_str_radix(digits, 16).map_err(|_| RpcError::invalid_params(format!("Invalid quantity: {}", value)))
+}
+
+/// Parse `0x`-prefixed hex bytes
+fn parse_hex_data(value: &str) -> Result<Vec<u8>, RpcError> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|_| RpcError::invalid_params(format!("Invalid hex data: {}", value)))
+}
+
+/// Run `f` on an executor sharing the node's runtime, on a blocking thread so contract
+/// execution does not stall the async workers
+pub(crate) async fn with_executor<T: Send + 'static>(
+    state: &RpcState,
+    f: impl FnOnce(&mut Executor) -> Result<T, RpcError> + Send + 'static,
+) -> Result<T, RpcError> {
+    let mut executor = Executor::with_runtime(state.state_manager.clone(), state.runtime.clone());
+    tokio::task::spawn_blocking(move || f(&mut executor))
+        .await
+        .map_err(|e| RpcError::server(format!("Execution task failed: {}", e)))?
+}
+
+/// Read-only view of the latest state, or of the state after block `height`
+pub(crate) fn overlay_at_height(state: &RpcState, height: Option<u64>) -> Result<StateOverlay, RpcError> {
+    match height {
+        Some(height) => {
+            let block = state
+                .storage
+                .get_block(height)
+                .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?
+                .ok_or_else(|| RpcError::server(format!("Block {} not found", height)))?;
+            state
+                .state_manager
+                .overlay_at(&block.header.state_root)
+                .map_err(|e| RpcError::server(format!("Failed to open state: {}", e)))
+        }
+        None => Ok(state.state_manager.overlay()),
+    }
+}
+
+/// Staking and governance records as of the latest state, or the state after block `height`
+pub(crate) fn system_state(state: &RpcState, height: Option<u64>) -> Result<SystemState, RpcError> {
+    SystemState::load(&overlay_at_height(state, height)?)
+        .map_err(|e| RpcError::server(format!("Failed to load records: {}", e)))
+}
+
+/// Run a contract call against the latest state or the state after block `height`, discarding writes
+async fn execute_read_only(state: &RpcState, call: &CallRequest, height: Option<u64>) -> Result<ExecutionResult, RpcError> {
+    let (state_root, block_height) = match height {
+        Some(height) => {
+            let block = state
+                .storage
+                .get_block(height)
+                .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?
+                .ok_or_else(|| RpcError::server(format!("Block {} not found", height)))?;
+            (Some(block.header.state_root), height)
+        }
+        None => {
+            let latest = state
+                .storage
+                .get_latest_height()
+                .map_err(|e| RpcError::server(format!("Failed to get block height: {}", e)))?
+                .unwrap_or(0);
+            (None, latest)
+        }
+    };
     
-    let proposals = state.governance_manager.lock().await.list_proposals(None);
+    let call = call.clone();
+    with_executor(state, move |executor| {
+        executor
+            .call(&call, state_root.as_deref(), block_height)
+            .map_err(|e| RpcError::server(format!("Call failed: {}", e)))
+    })
+    .await
+}
+
+/// Gas limit to use for `tx`: a dry run against the pending state plus a safety margin,
+/// within the bounds the mempool accepts
+async fn estimate_gas(state: &RpcState, tx: &Transaction) -> Result<u64, RpcError> {
+    // The sender's queued transactions run first, as they will on chain
+    let sender = tx.from.to_string();
+    let pending: Vec<Transaction> = state
+        .mempool
+        .get_pending_transactions(&sender)
+        .into_iter()
+        .filter(|pending| pending.nonce < tx.nonce)
+        .collect();
+    
+    let latest = state
+        .storage
+        .get_latest_height()
+        .map_err(|e| RpcError::server(format!("Failed to get block height: {}", e)))?;
+    let ctx = BlockContext {
+        height: latest.map_or(0, |height| height + 1),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        coinbase: String::new(),
+        last_commit: None,
+    };
     
-    let stats = serde_json::json!({
-        "validator_count": validators.len(),
-        "total_staked": total_staked.to_string(),
-        "proposal_count": proposals.len(),
-    });
+    let tx = tx.clone();
+    let used = with_executor(state, move |executor| {
+        executor
+            .estimate_gas(&tx, &pending, &ctx, MAX_GAS_LIMIT)
+            .map_err(|e| RpcError::server(format!("Gas estimation failed: {}", e)))
+    })
+    .await?;
+    
+    Ok(gas_limit_with_margin(used))
+}
+
+/// Gas limit to suggest for a transaction that needs `used`: the estimate plus
+/// `GAS_ESTIMATE_MARGIN_PERCENT`, within the bounds the mempool accepts
+fn gas_limit_with_margin(used: u64) -> u64 {
+    let with_margin = used.saturating_add(used * GAS_ESTIMATE_MARGIN_PERCENT / 100);
+    with_margin.clamp(MIN_GAS_LIMIT, MAX_GAS_LIMIT)
+}
+
+/// Node statistics endpoint
+async fn stats_handler(AxumState(state): AxumState<RpcState>) -> impl IntoResponse {
+    let stats = state.health.get_stats(&state).await;
     
     Json(JsonRpcResponse {
         jsonrpc: "2.0".to_string(),
-        result: Some(stats),
+        result: Some(serde_json::json!(stats)),
         error: None,
         id: serde_json::Value::Number(1.into()),
     })
 }
 
-/// Start the RPC server
-pub async fn start_rpc_server(state: RpcState, port: u16) -> Result<()> {
+/// Serve JSON-RPC over HTTP and WebSocket on one listener. Call once per listener; all of
+/// them share the node state in `state`.
+pub async fn start_rpc_server(state: RpcState, listener: RpcListener) -> Result<()> {
     // Initialize Prometheus metrics
-    init_metrics();
-    metrics::NODE_HEALTH.set(1);
+    static METRICS: Once = Once::new();
+    METRICS.call_once(init_metrics);
+
+    let limits = listener.limits.clone();
+    let auth_enabled = listener.auth.enabled();
+    let state = state
+        .with_namespaces(listener.namespaces)
+        .with_limits(listener.limits)
+        .with_auth(listener.auth);
+    let registry = state.registry.clone();
 
     let app = Router::new()
         .route("/", post(handle_rpc))
+        .route("/ws", get(subscriptions::ws_handler))
         .route("/health", get(health_check))
         .route("/metrics", get(metrics_handler))
         .route("/stats", get(stats_handler))
         .layer(CorsLayer::permissive())
         .with_state(state);
 
-    let addr = format!("0.0.0.0:{}", port);
+    let addr = listener.addr;
     println!("🌐 RPC server starting on http://{}", addr);
     println!("📡 Available methods:");
-    println!("   ACT Native:");
-    println!("   - act_getBalance");
-    println!("   - act_getAccount");
-    println!("   - act_getNonce");
-    println!("   - act_sendTransaction");
-    println!("   - act_getTransaction");
-    println!("   - act_getPendingTransactions");
-    println!("   - act_getMempoolStatus");
-    println!("   - act_getLogs");
-    println!("   - act_getTransactionReceipt");
-    println!("   Ethereum Compatible:");
-    println!("   - eth_blockNumber");
-    println!("   - eth_getBalance");
-    println!("   - eth_getTransactionCount");
-    println!("   - eth_sendRawTransaction");
-    println!("   - eth_call");
-    println!("   - eth_chainId");
-    println!("   - net_version");
-    println!();
-    println!("   Staking:");
-    println!("   - stake_deposit");
-    println!("   - stake_delegate");
-    println!("   - stake_unstake");
-    println!("   - stake_undelegate");
-    println!("   - stake_claimUnstaked");
-    println!("   - stake_claimRewards");
-    println!("   - stake_getValidator");
-    println!("   - stake_getValidators");
-    println!("   - stake_getDelegations");
-    println!("   - stake_getUnstakeRequests");
-    println!("   - stake_getRewards");
-    println!();
-    println!("   Governance:");
-    println!("   - gov_propose");
-    println!("   - gov_vote");
-    println!("   - gov_getProposal");
-    println!("   - gov_listProposals");
-    println!("   - gov_getVote");
-    println!("   - gov_getVotingPower");
-    println!("   - gov_getTallyResult");
+    for namespace in NAMESPACES {
+        let methods = registry.methods_of(namespace);
+        if !methods.is_empty() {
+            println!("   {}: {}", namespace, methods.join(", "));
+        }
+    }
+    println!("   rpc: rpc_discover (OpenRPC document), rpc_modules; batches of up to {} calls", limits.max_batch_size);
+    println!("   Subscriptions (ws://{}/ws): eth_subscribe / act_subscribe for newHeads, logs, newPendingTransactions", addr);
     println!();
     println!("📊 Monitoring endpoints:");
     println!("   GET /health   - Node health check");
     println!("   GET /metrics  - Prometheus metrics");
     println!("   GET /stats    - Node statistics");
+    match limits.rate {
+        Some(rate) => println!("🚦 Rate limit: {} requests/s per client, bursts of {}", rate.per_second, rate.burst),
+        None => println!("🚦 Rate limit: off"),
+    }
+    if auth_enabled {
+        println!("🔑 API key required for privileged namespaces");
+    }
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -918,6 +771,49 @@ pub async fn start_rpc_server(state: RpcState, port: u16) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use types::{BlockHeader, TransactionType};
+
+    fn test_block(height: u64, transactions: Vec<Transaction>) -> Block {
+        let header = BlockHeader {
+            parent_hash: "0".repeat(64),
+            tx_root: Block::calculate_tx_root(&transactions),
+            actor_pubkey: "pubkey".to_string(),
+            state_root: "state".to_string(),
+            receipts_root: "receipts".to_string(),
+            timestamp: 1_700_000_000 + height,
+            validator_commitment: "validator1".to_string(),
+            reward: 0,
+            height,
+            round: 0,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: "0".repeat(64),
+        };
+        Block::new(header, transactions, Vec::new())
+    }
+
+    fn transfer(nonce: u64) -> Transaction {
+        Transaction {
+            from: crypto::ActAddress("ACT-sender".to_string()),
+            nonce,
+            tx_type: TransactionType::Transfer { to: "ACT-receiver".to_string(), amount: 1 },
+            gas_limit: 21000,
+            gas_price: 1,
+            signature: Vec::new(),
+            pubkey: Vec::new(),
+        }
+    }
+
+    async fn call(state: &RpcState, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let caller = Caller { ip: IpAddr::from([127, 0, 0, 1]), authorized: true };
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+            id: serde_json::json!(1),
+        };
+        dispatch(state, &caller, request).await.unwrap()
+    }
 
     #[test]
     fn test_json_rpc_request_deserialization() {
@@ -931,4 +827,138 @@ mod tests {
         let req: JsonRpcRequest = serde_json::from_str(json).unwrap();
         assert_eq!(req.method, "act_getBalance");
     }
+
+    #[test]
+    fn test_gas_estimate_margin() {
+        assert_eq!(gas_limit_with_margin(21_000), 23_100);
+        assert_eq!(gas_limit_with_margin(100_000), 100_000 + 100_000 * GAS_ESTIMATE_MARGIN_PERCENT / 100);
+        // Within the limits the mempool accepts
+        assert_eq!(gas_limit_with_margin(1_000), MIN_GAS_LIMIT);
+        assert_eq!(gas_limit_with_margin(MAX_GAS_LIMIT - 1), MAX_GAS_LIMIT);
+    }
+
+    #[test]
+    fn test_batch_and_error_codes() {
+        assert_eq!(parse_payload("{", MAX_BATCH_SIZE).err().map(|e| e.code), Some(PARSE_ERROR));
+        assert_eq!(parse_payload("[]", MAX_BATCH_SIZE).err().map(|e| e.code), Some(INVALID_REQUEST));
+        let oversized = serde_json::to_string(&vec![serde_json::json!({}); MAX_BATCH_SIZE + 1]).unwrap();
+        assert_eq!(parse_payload(&oversized, MAX_BATCH_SIZE).err().map(|e| e.code), Some(INVALID_REQUEST));
+        assert!(parse_payload(&oversized, MAX_BATCH_SIZE + 1).is_ok());
+
+        let batch = r#"[
+            {"jsonrpc": "2.0", "method": "eth_chainId", "id": 1},
+            {"jsonrpc": "2.0", "method": "eth_chainId"},
+            {"jsonrpc": "1.0", "method": "eth_chainId", "id": 3},
+            42
+        ]"#;
+        let Ok(RpcPayload::Batch(calls)) = parse_payload(batch, MAX_BATCH_SIZE) else { panic!("expected a batch") };
+        let parsed: Vec<_> = calls.into_iter().map(parse_call).collect();
+        assert!(matches!(&parsed[0], Ok((request, true)) if request.id == 1 && request.params.is_null()));
+        assert!(matches!(&parsed[1], Ok((_, false))));  // Notification
+        assert!(matches!(&parsed[2], Err(reply) if reply.id == 3 && reply.error.as_ref().unwrap().code == INVALID_REQUEST));
+        assert!(matches!(&parsed[3], Err(reply) if reply.id.is_null()));
+
+        let reverted = JsonRpcResponse::new(
+            serde_json::json!(7),
+            Err(RpcError::server("Execution reverted").with_data(serde_json::json!("0x01"))),
+        );
+        let reverted = serde_json::to_value(reverted).unwrap();
+        assert_eq!(reverted["error"]["code"], SERVER_ERROR);
+        assert_eq!(reverted["error"]["data"], "0x01");
+        assert!(reverted.get("result").is_none());
+        assert_eq!(RpcError::method_not_found("eth_mine").code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_call_params_parsing() {
+        assert_eq!(parse_quantity("0x1f").ok(), Some(31));
+        assert!(parse_quantity("0xzz").is_err());
+        assert_eq!(parse_hex_data("0x0102").ok(), Some(vec![1, 2]));
+        assert!(parse_hex_data("0x012").is_err());
+
+        let params: ContractCallParams = serde_json::from_value(serde_json::json!({
+            "to": "ACT-CONTRACT-abc",
+            "data": "0x01",
+        }))
+        .unwrap();
+        assert_eq!(params.method, "execute");
+        assert_eq!(params.value, 0);
+        assert!(params.block.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_queries() {
+        let path = "./test_rpc_blocks_db";
+        let _ = std::fs::remove_dir_all(path);
+        let storage = Arc::new(BlockchainStorage::new(path).unwrap());
+        let state = RpcState::new(
+            Arc::new(StateManager::new(storage.clone()).unwrap()),
+            storage.clone(),
+            Arc::new(Mempool::new(100)),
+            WasmRuntime::new().unwrap(),
+        );
+        assert_eq!(call(&state, "act_blockNumber", serde_json::json!([])).await, 0);
+
+        let genesis = test_block(0, Vec::new());
+        let block = test_block(1, vec![transfer(0), transfer(1)]);
+        storage.store_block(&genesis).unwrap();
+        storage.store_block(&block).unwrap();
+        assert_eq!(call(&state, "act_blockNumber", serde_json::json!([])).await, 1);
+
+        // With or without the 0x prefix
+        let expected = serde_json::to_value(&block).unwrap();
+        let by_hash = |hash: String| serde_json::json!({ "hash": hash });
+        assert_eq!(call(&state, "act_getBlockByHash", by_hash(block.hash())).await, expected);
+        assert_eq!(call(&state, "act_getBlockByHash", by_hash(format!("0x{}", block.hash()))).await, expected);
+        assert_eq!(call(&state, "act_getBlockByHash", by_hash(genesis.hash())).await["header"]["height"], 0);
+        assert!(call(&state, "act_getBlockByHash", by_hash("ab".repeat(32))).await.is_null());
+
+        // Included transactions are found through the tx_to_block_ index
+        let second = &block.transactions[1];
+        let (tx, height) = storage.get_transaction(&second.hash()).unwrap().unwrap();
+        assert_eq!((tx.hash(), height), (second.hash(), 1));
+        let by_tx_hash = |hash: String| serde_json::json!({ "tx_hash": hash });
+        assert_eq!(
+            call(&state, "act_getTransaction", by_tx_hash(second.hash())).await,
+            serde_json::to_value(second).unwrap()
+        );
+        assert!(call(&state, "act_getTransaction", by_tx_hash("cd".repeat(32))).await.is_null());
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_records_read_from_state() {
+        use governance::GovernanceManager;
+        use staking::{StakingManager, MIN_VALIDATOR_STAKE};
+
+        let path = "./test_rpc_records_db";
+        let _ = std::fs::remove_dir_all(path);
+        let storage = Arc::new(BlockchainStorage::new(path).unwrap());
+        let state_manager = Arc::new(StateManager::new(storage.clone()).unwrap());
+        let state = RpcState::new(state_manager.clone(), storage.clone(), Arc::new(Mempool::new(100)), WasmRuntime::new().unwrap());
+
+        // Each block adds a validator to the records in the state
+        let mut staking = StakingManager::new();
+        for (height, validator) in ["ACT-a", "ACT-b"].into_iter().enumerate() {
+            staking.stake(validator.to_string(), MIN_VALIDATOR_STAKE, 10).unwrap();
+            let mut overlay = state_manager.overlay();
+            SystemState::new(staking.clone(), GovernanceManager::new()).store(&mut overlay).unwrap();
+            overlay.update_storage_roots().unwrap();
+            let mut block = test_block(height as u64, Vec::new());
+            block.header.state_root = overlay.state_root().unwrap();
+            state_manager.commit(overlay).unwrap();
+            storage.store_block(&block).unwrap();
+        }
+
+        let validators = |block: Option<u64>| serde_json::json!({ "active_only": false, "block": block });
+        assert_eq!(call(&state, "stake_getValidators", validators(None)).await.as_array().unwrap().len(), 2);
+        assert_eq!(call(&state, "stake_getValidators", validators(Some(0))).await.as_array().unwrap().len(), 1);
+        let at_genesis = serde_json::json!({ "address": "ACT-b", "block": 0 });
+        assert!(call(&state, "stake_getValidator", at_genesis).await.is_null());
+        assert_eq!(call(&state, "stake_getValidator", serde_json::json!({ "address": "ACT-b" })).await["address"], "ACT-b");
+        assert_eq!(call(&state, "gov_listProposals", serde_json::json!({})).await, serde_json::json!([]));
+
+        let _ = std::fs::remove_dir_all(path);
+    }
 }
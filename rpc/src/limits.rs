@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{metrics, RpcPayload};
+
+/// Largest request body, or WebSocket message, accepted by default
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Calls accepted in one batch request by default
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Clients tracked before idle ones are forgotten
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Methods that cost more than one request, by how much work they do for the node
+const DEFAULT_METHOD_COSTS: [(&str, u32); 12] = [
+    ("act_call", 5),
+    ("eth_call", 5),
+    ("act_estimateGas", 5),
+    ("eth_estimateGas", 5),
+    ("act_getLogs", 10),
+    ("eth_getLogs", 10),
+    ("act_getProof", 5),
+    ("eth_feeHistory", 5),
+    ("act_sendTransaction", 2),
+    ("eth_sendRawTransaction", 2),
+    ("debug_traceTransaction", 20),
+    ("act_traceTransaction", 20),
+];
+
+/// Why a request was turned away before being served, the `reason` label of
+/// `act_rpc_rejections_total`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    RateLimited,
+    BodyTooLarge,
+    BatchTooLarge,
+    Unauthorized,
+}
+
+impl Rejection {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rejection::RateLimited => "rate_limited",
+            Rejection::BodyTooLarge => "body_too_large",
+            Rejection::BatchTooLarge => "batch_too_large",
+            Rejection::Unauthorized => "unauthorized",
+        }
+    }
+
+    pub(crate) fn record(self) {
+        metrics::RPC_REJECTIONS.with_label_values(&[self.label()]).inc();
+    }
+}
+
+/// Token bucket refilled at `per_second` requests, holding at most `burst`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub per_second: u32,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Parse an operator setting: `off`, `<per_second>` or `<per_second>/<burst>`.
+    /// The burst defaults to twice the rate.
+    pub fn parse(spec: &str) -> Result<Option<Self>> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("off") {
+            return Ok(None);
+        }
+
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| anyhow!("Invalid rate limit '{}'", spec))
+        };
+        let (per_second, burst) = match spec.split_once('/') {
+            Some((rate, burst)) => (number(rate)?, number(burst)?),
+            None => {
+                let rate = number(spec)?;
+                (rate, rate.saturating_mul(2))
+            }
+        };
+        Ok(Some(Self { per_second, burst }))
+    }
+}
+
+/// Limits applied to the requests of one listener
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcLimits {
+    pub rate: Option<RateLimit>,  // Per client IP; unlimited when `None`
+    pub max_body_bytes: usize,
+    pub max_batch_size: usize,
+    method_costs: HashMap<String, u32>,
+}
+
+impl Default for RpcLimits {
+    fn default() -> Self {
+        Self {
+            rate: Some(RateLimit { per_second: 50, burst: 100 }),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            max_batch_size: MAX_BATCH_SIZE,
+            method_costs: DEFAULT_METHOD_COSTS
+                .iter()
+                .map(|(method, cost)| (method.to_string(), *cost))
+                .collect(),
+        }
+    }
+}
+
+impl RpcLimits {
+    /// Default size caps and method costs without a rate limit
+    pub fn unlimited() -> Self {
+        Self { rate: None, ..Self::default() }
+    }
+
+    /// Tokens one call of `method` takes from its client's bucket
+    pub fn cost(&self, method: &str) -> u32 {
+        self.method_costs.get(method).copied().unwrap_or(1)
+    }
+
+    pub fn with_method_cost(mut self, method: &str, cost: u32) -> Self {
+        self.method_costs.insert(method.to_string(), cost);
+        self
+    }
+
+    /// Override method costs from an operator setting such as `eth_getLogs=20,eth_call=10`
+    pub fn with_method_costs(self, spec: &str) -> Result<Self> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .try_fold(self, |limits, entry| {
+                let (method, cost) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Invalid method cost '{}', expected method=cost", entry))?;
+                let cost = cost
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("Invalid cost in '{}'", entry))?;
+                Ok(limits.with_method_cost(method.trim(), cost))
+            })
+    }
+
+    /// Tokens a whole payload takes. Calls without a method name still count as one.
+    pub(crate) fn payload_cost(&self, payload: &RpcPayload) -> u32 {
+        let call_cost = |call: &serde_json::Value| {
+            call.get("method")
+                .and_then(serde_json::Value::as_str)
+                .map_or(1, |method| self.cost(method))
+        };
+        match payload {
+            RpcPayload::Single(call) => call_cost(call),
+            RpcPayload::Batch(calls) => calls.iter().map(call_cost).fold(0, u32::saturating_add),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Per-IP token buckets for one listener
+pub(crate) struct RateLimiter {
+    limits: RpcLimits,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: RpcLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn limits(&self) -> &RpcLimits {
+        &self.limits
+    }
+
+    /// Take `cost` tokens from the bucket of `client`. On refusal, returns how long until
+    /// enough tokens are back, or `None` when the cost exceeds the burst and never will be.
+    pub(crate) fn check(&self, client: IpAddr, cost: u32) -> Result<(), Option<Duration>> {
+        self.check_at(client, cost, Instant::now())
+    }
+
+    fn check_at(&self, client: IpAddr, cost: u32, now: Instant) -> Result<(), Option<Duration>> {
+        let Some(rate) = self.limits.rate else {
+            return Ok(());
+        };
+        let (refill, burst, cost) = (rate.per_second as f64, rate.burst as f64, cost as f64);
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&client) {
+            // A bucket that has refilled completely holds nothing worth remembering
+            buckets.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * refill < burst);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket { tokens: burst, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill).min(burst);
+        bucket.updated = now;
+
+        if cost > burst {
+            return Err(None);
+        }
+        if bucket.tokens < cost {
+            return Err(Some(Duration::from_secs_f64((cost - bucket.tokens) / refill)));
+        }
+        bucket.tokens -= cost;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limits = RpcLimits {
+            rate: Some(RateLimit { per_second: 10, burst: 20 }),
+            ..RpcLimits::default()
+        };
+        let limiter = RateLimiter::new(limits);
+        let (alice, bob) = (IpAddr::from([10, 0, 0, 1]), IpAddr::from([10, 0, 0, 2]));
+        let start = Instant::now();
+
+        assert!(limiter.check_at(alice, 15, start).is_ok());
+        assert_eq!(limiter.check_at(alice, 10, start), Err(Some(Duration::from_millis(500))));
+        assert!(limiter.check_at(bob, 10, start).is_ok());  // Separate bucket
+
+        // Refilled at 10 tokens a second, never past the burst
+        assert!(limiter.check_at(alice, 10, start + Duration::from_millis(500)).is_ok());
+        assert!(limiter.check_at(alice, 20, start + Duration::from_secs(60)).is_ok());
+        assert_eq!(limiter.check_at(alice, 21, start + Duration::from_secs(120)), Err(None));
+
+        let unlimited = RateLimiter::new(RpcLimits::unlimited());
+        assert!(unlimited.check_at(alice, 1_000_000, start).is_ok());
+    }
+
+    #[test]
+    fn test_method_costs() {
+        let limits = RpcLimits::default().with_method_costs("eth_getLogs=25, eth_chainId=0").unwrap();
+        assert_eq!(limits.cost("eth_getLogs"), 25);
+        assert_eq!(limits.cost("eth_chainId"), 0);
+        assert_eq!(limits.cost("debug_traceTransaction"), 20);
+        assert_eq!(limits.cost("eth_blockNumber"), 1);
+        assert!(RpcLimits::default().with_method_costs("eth_call").is_err());
+
+        let batch = RpcPayload::Batch(vec![
+            serde_json::json!({ "method": "eth_getLogs" }),
+            serde_json::json!({ "method": "eth_blockNumber" }),
+            serde_json::json!(42),
+        ]);
+        assert_eq!(limits.payload_cost(&batch), 27);
+
+        assert_eq!(RateLimit::parse("off").unwrap(), None);
+        assert_eq!(RateLimit::parse("20").unwrap(), Some(RateLimit { per_second: 20, burst: 40 }));
+        assert_eq!(RateLimit::parse("20/25").unwrap(), Some(RateLimit { per_second: 20, burst: 25 }));
+        assert!(RateLimit::parse("0").is_err());
+        assert!(RateLimit::parse("fast").is_err());
+    }
+}
@@ -2,7 +2,11 @@ use prometheus::{
     Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec,
     IntGauge, IntGaugeVec, Opts, Registry,
 };
-use std::sync::Arc;
+use std::time::Duration;
+
+use governance::{GovernanceManager, ProposalStatus};
+use staking::StakingManager;
+use types::{Block, TransactionType};
 
 lazy_static::lazy_static! {
     pub static ref REGISTRY: Registry = Registry::new();
@@ -126,6 +130,11 @@ lazy_static::lazy_static! {
         &["method", "error_type"]
     ).expect("metric can be created");
 
+    pub static ref RPC_REJECTIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new("act_rpc_rejections_total", "RPC requests refused before being served, by reason"),
+        &["reason"]
+    ).expect("metric can be created");
+
     // State metrics
     pub static ref STATE_SIZE: IntGauge = IntGauge::new(
         "act_state_size_bytes",
@@ -194,6 +203,7 @@ pub fn init_metrics() {
     REGISTRY.register(Box::new(RPC_REQUESTS.clone())).expect("collector can be registered");
     REGISTRY.register(Box::new(RPC_REQUEST_DURATION.clone())).expect("collector can be registered");
     REGISTRY.register(Box::new(RPC_ERRORS.clone())).expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_REJECTIONS.clone())).expect("collector can be registered");
 
     REGISTRY.register(Box::new(STATE_SIZE.clone())).expect("collector can be registered");
     REGISTRY.register(Box::new(ACCOUNT_COUNT.clone())).expect("collector can be registered");
@@ -214,3 +224,57 @@ pub fn export_metrics() -> String {
     encoder.encode(&metric_families, &mut buffer).unwrap();
     String::from_utf8(buffer).unwrap()
 }
+
+/// Count a block this node produced, `production_time` after it started building it
+pub fn record_block(block: &Block, production_time: Duration) {
+    BLOCKS_PRODUCED.inc();
+    BLOCKS_PRODUCED_BY_VALIDATOR
+        .with_label_values(&[&block.header.actor_pubkey])
+        .inc();
+    CURRENT_BLOCK_NUMBER.set(block.header.height as i64);
+    LAST_BLOCK_TIME.set(block.header.timestamp as i64);
+    BLOCK_PRODUCTION_TIME.observe(production_time.as_secs_f64());
+
+    TRANSACTIONS_TOTAL.inc_by(block.transactions.len() as u64);
+    for (tx, receipt) in block.transactions.iter().zip(&block.receipts) {
+        if !receipt.status {
+            TRANSACTIONS_FAILED.inc();
+            continue;
+        }
+        match tx.tx_type {
+            TransactionType::Delegate { .. } => DELEGATIONS_TOTAL.inc(),
+            TransactionType::Propose { .. } => PROPOSALS_TOTAL.inc(),
+            TransactionType::Vote { .. } => VOTES_TOTAL.inc(),
+            _ => {}
+        }
+    }
+}
+
+/// Refresh the staking and governance gauges from the committed records
+pub fn record_system_state(staking: &StakingManager, governance: &GovernanceManager) {
+    const RECORD_UNITS_PER_ACT: f64 = 1e9;
+
+    let validators = staking.get_all_validators();
+    VALIDATOR_COUNT.set(validators.iter().filter(|v| v.active).count() as i64);
+    TOTAL_STAKED.set(staking.get_total_staked() as f64 / RECORD_UNITS_PER_ACT);
+    VALIDATOR_STAKE.reset();
+    for validator in &validators {
+        VALIDATOR_STAKE
+            .with_label_values(&[&validator.address])
+            .set(validator.total_stake() as f64 / RECORD_UNITS_PER_ACT);
+    }
+
+    let proposals = governance.list_proposals(None);
+    let active: Vec<_> = proposals.iter().filter(|p| p.status == ProposalStatus::Active).collect();
+    PROPOSALS_ACTIVE.set(active.len() as i64);
+
+    // Share of the quorum supply that voted on the currently active proposals
+    let participation: Vec<f64> = active
+        .iter()
+        .filter(|p| p.total_supply_snapshot > 0)
+        .map(|p| p.total_votes() as f64 / p.total_supply_snapshot as f64)
+        .collect();
+    if !participation.is_empty() {
+        VOTING_PARTICIPATION.set(participation.iter().sum::<f64>() / participation.len() as f64);
+    }
+}
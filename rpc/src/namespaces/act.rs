@@ -0,0 +1,276 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::Namespace;
+use crate::{
+    estimate_gas, execute_read_only, parse_hex_data, AccountInfo, ContractCallParams, GetBalanceParams,
+    GetBlockByHashParams, GetBlockParams, GetLogsParams, GetReceiptParams, MempoolStatus, RpcError, RpcState,
+    SendTransactionParams, TransactionReceipt, CALL_GAS_CAP, ZERO_ADDRESS,
+};
+use executor::CallRequest;
+
+/// `act_*`: native accounts, transactions, blocks, logs and read-only contract calls
+pub struct Act;
+
+#[async_trait]
+impl Namespace for Act {
+    fn name(&self) -> &'static str {
+        "act"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "act_getBalance",
+            "act_getAccount",
+            "act_getNonce",
+            "act_getProof",
+            "act_sendTransaction",
+            "act_getTransaction",
+            "act_blockNumber",
+            "act_getBlock",
+            "act_getBlockByNumber",
+            "act_getBlockByHash",
+            "act_getPendingTransactions",
+            "act_getMempoolStatus",
+            "act_getLogs",
+            "act_getTransactionReceipt",
+            "act_estimateGas",
+            "act_call",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "act_getBalance" => {
+                let params: GetBalanceParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let balance = state
+                    .state_manager
+                    .get_balance(&params.address)
+                    .map_err(|e| RpcError::server(format!("Failed to get balance: {}", e)))?;
+
+                serde_json::to_value(balance)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getAccount" => {
+                let params: GetBalanceParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let balance = state
+                    .state_manager
+                    .get_balance(&params.address)
+                    .unwrap_or(0);
+
+                let nonce = state
+                    .state_manager
+                    .get_nonce(&params.address)
+                    .unwrap_or(0);
+
+                let account_info = AccountInfo {
+                    address: params.address,
+                    balance,
+                    nonce,
+                };
+
+                serde_json::to_value(account_info)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getNonce" => {
+                let params: GetBalanceParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let nonce = state
+                    .state_manager
+                    .get_nonce(&params.address)
+                    .map_err(|e| RpcError::server(format!("Failed to get nonce: {}", e)))?;
+
+                serde_json::to_value(nonce)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getProof" => {
+                let params: GetBalanceParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let proof = state
+                    .state_manager
+                    .get_proof(&params.address)
+                    .map_err(|e| RpcError::server(format!("Failed to get proof: {}", e)))?;
+
+                serde_json::to_value(proof)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_sendTransaction" => {
+                let params: SendTransactionParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let tx_hash = state
+                    .mempool
+                    .add_transaction(params.transaction, &state.state_manager)
+                    .map_err(|e| RpcError::server(format!("Transaction rejected: {}", e)))?;
+
+                println!("✅ Transaction accepted: {}...", &tx_hash[..16]);
+
+                let receipt = TransactionReceipt {
+                    tx_hash,
+                    status: "pending".to_string(),
+                };
+
+                serde_json::to_value(receipt)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getTransaction" => {
+                let params: GetReceiptParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                // Pending transactions live in the mempool, included ones in block storage
+                let tx = match state.mempool.get_transaction(&params.tx_hash) {
+                    Some(tx) => Some(tx),
+                    None => state
+                        .storage
+                        .get_transaction(&params.tx_hash)
+                        .map_err(|e| RpcError::server(format!("Failed to get transaction: {}", e)))?
+                        .map(|(tx, _height)| tx),
+                };
+
+                serde_json::to_value(tx)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_blockNumber" => {
+                let height = state
+                    .storage
+                    .get_latest_height()
+                    .map_err(|e| RpcError::server(format!("Failed to get block height: {}", e)))?
+                    .unwrap_or(0);
+
+                serde_json::to_value(height)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getBlock" | "act_getBlockByNumber" => {
+                let params: GetBlockParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let block = state
+                    .storage
+                    .get_block(params.height)
+                    .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?;
+
+                serde_json::to_value(block)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getBlockByHash" => {
+                let params: GetBlockByHashParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let block = state
+                    .storage
+                    .get_block_by_hash(params.hash.trim_start_matches("0x"))
+                    .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?;
+
+                serde_json::to_value(block)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getPendingTransactions" => {
+                let params: GetBalanceParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let txs = state.mempool.get_pending_transactions(&params.address);
+
+                serde_json::to_value(txs)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getMempoolStatus" => {
+                let stats = state.mempool.get_stats();
+
+                let status = MempoolStatus {
+                    pending_transactions: stats.total_transactions,
+                    unique_senders: stats.unique_senders,
+                    avg_gas_price: stats.avg_gas_price,
+                };
+
+                serde_json::to_value(status)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getLogs" => {
+                let params: GetLogsParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let logs = state
+                    .state_manager
+                    .query_logs(
+                        params.address.as_deref(),
+                        params.topics,
+                        params.from_block,
+                        params.to_block,
+                    )
+                    .map_err(|e| RpcError::server(format!("Failed to query logs: {}", e)))?;
+
+                println!("📜 Queried {} event logs", logs.len());
+
+                serde_json::to_value(logs)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_getTransactionReceipt" => {
+                let params: GetReceiptParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let receipt = state
+                    .state_manager
+                    .get_receipt(&params.tx_hash)
+                    .map_err(|e| RpcError::server(format!("Failed to get receipt: {}", e)))?;
+
+                serde_json::to_value(receipt)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_estimateGas" => {
+                let params: SendTransactionParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let gas = estimate_gas(state, &params.transaction).await?;
+                serde_json::to_value(gas)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "act_call" => {
+                let params: ContractCallParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let call = CallRequest {
+                    from: params.from.unwrap_or_else(|| ZERO_ADDRESS.to_string()),
+                    to: params.to,
+                    method: params.method,
+                    input: params.data.as_deref().map(parse_hex_data).transpose()?.unwrap_or_default(),
+                    value: params.value,
+                    gas_limit: params.gas.map_or(CALL_GAS_CAP, |gas| gas.min(CALL_GAS_CAP)),
+                };
+
+                let result = execute_read_only(state, &call, params.block).await?;
+
+                serde_json::json!({
+                    "success": result.success,
+                    "gas_used": result.gas_used,
+                    "return_data": format!("0x{}", hex::encode(&result.return_data)),
+                    "logs": result.logs,
+                    "events": result.events,
+                })
+            }
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::Namespace;
+use crate::health::SyncStatus;
+use crate::{GetReceiptParams, RpcError, RpcState};
+use types::eth;
+
+/// `admin_*`: node operator tools
+pub struct Admin;
+
+#[async_trait]
+impl Namespace for Admin {
+    fn name(&self) -> &'static str {
+        "admin"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "admin_nodeInfo",
+            "admin_removeTransaction",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "admin_nodeInfo" => {
+                let height = state
+                    .storage
+                    .get_latest_height()
+                    .map_err(|e| RpcError::server(format!("Failed to get block height: {}", e)))?
+                    .unwrap_or(0);
+                let stats = state.mempool.get_stats();
+
+                json!({
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "chain_id": eth::CHAIN_ID,
+                    "block_height": height,
+                    "peer_count": *state.peer_count.read().await,
+                    "synced": SyncStatus::new(height, *state.peer_height.read().await).is_synced,
+                    "pending_transactions": stats.total_transactions,
+                    "namespaces": state.registry.modules(),
+                })
+            }
+
+            // Evict a stuck transaction from this node's mempool
+            "admin_removeTransaction" => {
+                let params: GetReceiptParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+                let tx_hash = params.tx_hash.trim_start_matches("0x");
+
+                let removed = state.mempool.remove_transaction(tx_hash);
+                if removed.is_some() {
+                    println!("🗑️  Removed tx {} from the mempool", tx_hash);
+                }
+                Value::Bool(removed.is_some())
+            }
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
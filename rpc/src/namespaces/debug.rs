@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::Namespace;
+use crate::{with_executor, GetReceiptParams, RpcError, RpcState};
+
+/// `debug_*`: transaction tracing. Re-execution is expensive, so it is off on public listeners.
+pub struct Debug;
+
+#[async_trait]
+impl Namespace for Debug {
+    fn name(&self) -> &'static str {
+        "debug"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "act_traceTransaction",
+            "debug_traceTransaction",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "act_traceTransaction" | "debug_traceTransaction" => {
+                // Accepts `{"tx_hash": ...}` or the Ethereum-style `["0x..."]`
+                let tx_hash = match params.get(0).and_then(|v| v.as_str()) {
+                    Some(hash) => hash.to_string(),
+                    None => {
+                        let params: GetReceiptParams = serde_json::from_value(params)
+                            .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+                        params.tx_hash
+                    }
+                };
+                let tx_hash = tx_hash.strip_prefix("0x").unwrap_or(&tx_hash);
+
+                trace_transaction(state, tx_hash).await?
+            }
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
+
+/// Re-execute a historic transaction against its block's parent state, staking and governance
+/// records included, and return its call trace
+async fn trace_transaction(state: &RpcState, tx_hash: &str) -> Result<serde_json::Value, RpcError> {
+    let (_, height) = state
+        .storage
+        .get_transaction(tx_hash)
+        .map_err(|e| RpcError::server(format!("Failed to get transaction: {}", e)))?
+        .ok_or_else(|| RpcError::server(format!("Transaction {} not found", tx_hash)))?;
+    
+    let load_block = |height: u64| {
+        state
+            .storage
+            .get_block(height)
+            .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?
+            .ok_or_else(|| RpcError::server(format!("Block {} not found", height)))
+    };
+    
+    let block = load_block(height)?;
+    let parent_state_root = if height == 0 {
+        state
+            .state_manager
+            .genesis_state_root()
+            .map_err(|e| RpcError::server(format!("Failed to get genesis state: {}", e)))?
+    } else {
+        load_block(height - 1)?.header.state_root
+    };
+    
+    let tx_hash = tx_hash.to_string();
+    let traced = with_executor(state, move |executor| {
+        executor
+            .trace_transaction(&block, &parent_state_root, &tx_hash)
+            .map_err(|e| RpcError::server(format!("Trace failed: {}", e)))
+    })
+    .await?;
+    
+    Ok(serde_json::json!({
+        "transaction_hash": traced.receipt.transaction_hash,
+        "block_height": height,
+        "status": traced.receipt.status,
+        "gas_used": traced.receipt.gas_used,
+        "receipt": traced.receipt,
+        "trace": traced.trace,
+    }))
+}
@@ -0,0 +1,439 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::Namespace;
+use crate::{
+    default_call_method, estimate_gas, ethereum, execute_read_only, parse_hex_data, parse_quantity, RpcError,
+    RpcState, CALL_GAS_CAP, ZERO_ADDRESS,
+};
+use crypto::ActAddress;
+use executor::CallRequest;
+use mempool::MAX_GAS_LIMIT;
+use state::StateOverlay;
+use types::{eth, ActAmount, Block, Transaction, TransactionType};
+
+/// `eth_*`: the Ethereum JSON-RPC subset wallets and tooling expect
+pub struct Eth;
+
+#[async_trait]
+impl Namespace for Eth {
+    fn name(&self) -> &'static str {
+        "eth"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "eth_blockNumber",
+            "eth_getBalance",
+            "eth_getTransactionCount",
+            "eth_getBlockByNumber",
+            "eth_getBlockByHash",
+            "eth_getTransactionByHash",
+            "eth_getTransactionReceipt",
+            "eth_getLogs",
+            "eth_gasPrice",
+            "eth_maxPriorityFeePerGas",
+            "eth_feeHistory",
+            "eth_getCode",
+            "eth_getStorageAt",
+            "eth_sendRawTransaction",
+            "eth_call",
+            "eth_estimateGas",
+            "eth_chainId",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "eth_blockNumber" => {
+                // Return latest block height in hex
+                let height = state
+                    .storage
+                    .get_latest_height()
+                    .map_err(|e| RpcError::server(format!("Failed to get block height: {}", e)))?
+                    .unwrap_or(0);
+                serde_json::to_value(format!("0x{:x}", height))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_getBalance" => {
+                let address = &address_param(&params)?;
+
+                let balance = state_at(state, params.get(1))?
+                    .get_balance(address)
+                    .unwrap_or(0);
+
+                // Return balance in hex
+                serde_json::to_value(format!("0x{:x}", balance))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_getTransactionCount" => {
+                let address = &address_param(&params)?;
+
+                let mut nonce = state_at(state, params.get(1))?
+                    .get_nonce(address)
+                    .unwrap_or(0);
+                if params.get(1).and_then(|v| v.as_str()) == Some("pending") {
+                    nonce += state.mempool.get_pending_transactions(address).len() as u64;
+                }
+
+                serde_json::to_value(format!("0x{:x}", nonce))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_getBlockByNumber" => {
+                let height = block_height(state, params.get(0))?;
+                let full = params.get(1).and_then(|v| v.as_bool()).unwrap_or(false);
+
+                load_block(state, height)?
+                    .map(|block| ethereum::block_json(&block, full))
+                    .unwrap_or(serde_json::Value::Null)
+            }
+
+            "eth_getBlockByHash" => {
+                let hash = params
+                    .get(0)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing block hash parameter"))?;
+                let full = params.get(1).and_then(|v| v.as_bool()).unwrap_or(false);
+
+                state
+                    .storage
+                    .get_block_by_hash(hash.trim_start_matches("0x"))
+                    .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?
+                    .map(|block| ethereum::block_json(&block, full))
+                    .unwrap_or(serde_json::Value::Null)
+            }
+
+            "eth_getTransactionByHash" => {
+                let hash = params
+                    .get(0)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing transaction hash parameter"))?;
+                let hash = hash.trim_start_matches("0x");
+
+                match state.mempool.get_transaction(hash) {
+                    Some(tx) => ethereum::transaction_json(&tx, None),
+                    None => locate_transaction(state, hash)?
+                        .map(|(block, index)| ethereum::transaction_json(&block.transactions[index], Some((&block, index))))
+                        .unwrap_or(serde_json::Value::Null),
+                }
+            }
+
+            "eth_getTransactionReceipt" => {
+                let hash = params
+                    .get(0)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing transaction hash parameter"))?;
+
+                locate_transaction(state, hash.trim_start_matches("0x"))?
+                    .and_then(|(block, index)| ethereum::receipt_json(&block, index))
+                    .unwrap_or(serde_json::Value::Null)
+            }
+
+            "eth_getLogs" => {
+                let filter_obj = params
+                    .get(0)
+                    .ok_or_else(|| RpcError::invalid_params("Missing filter parameter"))?;
+                let filter = ethereum::LogFilter::from_json(filter_obj)?;
+
+                // A block hash selects one block; otherwise a range that defaults to the latest block
+                let blocks = match filter_obj.get("blockHash").and_then(|v| v.as_str()) {
+                    Some(hash) => {
+                        let block = state
+                            .storage
+                            .get_block_by_hash(hash.trim_start_matches("0x"))
+                            .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))?
+                            .ok_or_else(|| RpcError::server(format!("Block {} not found", hash)))?;
+                        vec![block]
+                    }
+                    None => {
+                        let from = block_height(state, filter_obj.get("fromBlock"))?;
+                        let to = block_height(state, filter_obj.get("toBlock"))?;
+                        if to.saturating_sub(from) >= ethereum::MAX_LOG_BLOCK_RANGE {
+                            return Err(RpcError::invalid_params(format!(
+                                "Block range too large: at most {} blocks per query",
+                                ethereum::MAX_LOG_BLOCK_RANGE
+                            ))
+                            .with_data(serde_json::json!({ "maxBlockRange": ethereum::MAX_LOG_BLOCK_RANGE })));
+                        }
+                        let mut blocks = Vec::new();
+                        for height in from..=to {
+                            blocks.extend(load_block(state, height)?);
+                        }
+                        blocks
+                    }
+                };
+
+                let logs: Vec<serde_json::Value> = blocks
+                    .iter()
+                    .flat_map(|block| ethereum::block_logs(block, &filter))
+                    .collect();
+
+                println!("📜 Queried {} event logs", logs.len());
+                serde_json::Value::Array(logs)
+            }
+
+            "eth_gasPrice" | "eth_maxPriorityFeePerGas" => {
+                // Blocks carry no base fee, so the suggested tip is the whole price
+                let blocks = state
+                    .storage
+                    .get_recent_blocks(ethereum::GAS_PRICE_SAMPLE_BLOCKS)
+                    .map_err(|e| RpcError::server(format!("Failed to get blocks: {}", e)))?;
+                let mempool_price = state.mempool.get_stats().avg_gas_price;
+                let price = ethereum::median_gas_price(&blocks)
+                    .or((mempool_price > 0).then_some(mempool_price))
+                    .unwrap_or(ethereum::DEFAULT_GAS_PRICE);
+
+                serde_json::to_value(ethereum::quantity(price))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_feeHistory" => {
+                let block_count = match params.get(0) {
+                    Some(serde_json::Value::Number(n)) => n.as_u64(),
+                    Some(serde_json::Value::String(s)) => u64::try_from(parse_quantity(s)?).ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| RpcError::invalid_params("Missing block count parameter"))?
+                .min(ethereum::MAX_FEE_HISTORY_BLOCKS);
+                let newest = block_height(state, params.get(1))?;
+                let percentiles: Vec<f64> = params
+                    .get(2)
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default();
+
+                let oldest = (newest + 1).saturating_sub(block_count);
+                let mut gas_used_ratio = Vec::new();
+                let mut rewards = Vec::new();
+                for height in oldest..=newest {
+                    let block = load_block(state, height)?
+                        .ok_or_else(|| RpcError::server(format!("Block {} not found", height)))?;
+                    gas_used_ratio.push(ethereum::block_gas_used(&block) as f64 / ethereum::BLOCK_GAS_LIMIT as f64);
+                    rewards.push(ethereum::block_rewards(&block, &percentiles));
+                }
+
+                let mut history = serde_json::json!({
+                    "oldestBlock": ethereum::quantity(oldest),
+                    "baseFeePerGas": vec!["0x0"; gas_used_ratio.len() + 1],
+                    "gasUsedRatio": gas_used_ratio,
+                });
+                if !percentiles.is_empty() {
+                    history["reward"] = serde_json::json!(rewards);
+                }
+                history
+            }
+
+            "eth_getCode" => {
+                let address = &address_param(&params)?;
+
+                let code = state_at(state, params.get(1))?
+                    .get_code(address)
+                    .map_err(|e| RpcError::server(format!("Failed to get code: {}", e)))?
+                    .unwrap_or_default();
+
+                serde_json::to_value(ethereum::data(&code))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_getStorageAt" => {
+                let address = &address_param(&params)?;
+                let slot = params
+                    .get(1)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing storage slot parameter"))?;
+
+                // Contract storage keys are arbitrary bytes; the slot is taken as those bytes
+                let digits = slot.strip_prefix("0x").unwrap_or(slot);
+                let key = parse_hex_data(&format!("{}{}", "0".repeat(digits.len() % 2), digits))?;
+                let value = state_at(state, params.get(2))?
+                    .get_storage(address, &key)
+                    .map_err(|e| RpcError::server(format!("Failed to get storage: {}", e)))?
+                    .unwrap_or_default();
+
+                // Values shorter than a word are left-padded as Ethereum's 32-byte slots are
+                let mut word = vec![0u8; 32usize.saturating_sub(value.len())];
+                word.extend(value);
+                serde_json::to_value(ethereum::data(&word))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_sendRawTransaction" => {
+                let raw_tx = params
+                    .get(0)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing raw transaction parameter"))?;
+
+                let raw_tx = parse_hex_data(raw_tx)?;
+                let eth_tx = eth::decode_raw_transaction(&raw_tx)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid raw transaction: {}", e)))?;
+
+                let tx_hash = state
+                    .mempool
+                    .add_transaction(eth_tx.into_transaction(), &state.state_manager)
+                    .map_err(|e| RpcError::server(format!("Transaction rejected: {}", e)))?;
+
+                println!("✅ Ethereum transaction accepted: {}...", &tx_hash[..16]);
+
+                serde_json::to_value(format!("0x{}", tx_hash))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_call" => {
+                let call_obj = params
+                    .get(0)
+                    .ok_or_else(|| RpcError::invalid_params("Missing call object parameter"))?;
+
+                // Extract call parameters
+                let to = call_obj.get("to")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| RpcError::invalid_params("Missing 'to' address"))?;
+                let from = call_obj.get("from").and_then(|v| v.as_str()).unwrap_or(ZERO_ADDRESS);
+                let data = call_obj.get("data").or_else(|| call_obj.get("input")).and_then(|v| v.as_str());
+                let value = call_obj.get("value").and_then(|v| v.as_str()).map(parse_quantity).transpose()?;
+                let gas = call_obj.get("gas").and_then(|v| v.as_str()).map(parse_quantity).transpose()?;
+                let block = parse_block_tag(state, params.get(1))?;
+
+                let call = CallRequest {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    method: default_call_method(),
+                    input: data.map(parse_hex_data).transpose()?.unwrap_or_default(),
+                    value: value.unwrap_or(0),
+                    gas_limit: gas.map_or(CALL_GAS_CAP, |gas| gas.min(CALL_GAS_CAP as ActAmount) as u64),
+                };
+
+                let result = execute_read_only(state, &call, block).await?;
+                if !result.success {
+                    let reason = result.logs.last().cloned().unwrap_or_default();
+                    return Err(RpcError::server(format!("Execution reverted: {}", reason))
+                        .with_data(serde_json::json!(format!("0x{}", hex::encode(&result.return_data)))));
+                }
+
+                serde_json::to_value(format!("0x{}", hex::encode(&result.return_data)))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_estimateGas" => {
+                let call_obj = params
+                    .get(0)
+                    .ok_or_else(|| RpcError::invalid_params("Missing call object parameter"))?;
+
+                let from = call_obj.get("from").and_then(|v| v.as_str()).unwrap_or(ZERO_ADDRESS);
+                let to = call_obj.get("to").and_then(|v| v.as_str()).unwrap_or(""); // Empty: contract creation
+                let data = call_obj.get("data").or_else(|| call_obj.get("input")).and_then(|v| v.as_str());
+                let value = call_obj.get("value").and_then(|v| v.as_str()).map(parse_quantity).transpose()?;
+                let gas_price = call_obj.get("gasPrice").and_then(|v| v.as_str()).map(parse_quantity).transpose()?;
+
+                let tx = Transaction {
+                    from: ActAddress(from.to_string()),
+                    nonce: state.state_manager.get_nonce(from).unwrap_or(0)
+                        + state.mempool.get_pending_transactions(from).len() as u64,
+                    tx_type: TransactionType::EthereumLegacy {
+                        to: to.to_string(),
+                        value: value.unwrap_or(0),
+                        data: data.map(parse_hex_data).transpose()?.unwrap_or_default(),
+                        gas_price: gas_price.unwrap_or(0),
+                    },
+                    gas_limit: MAX_GAS_LIMIT,
+                    gas_price: gas_price.unwrap_or(0),
+                    signature: vec![],
+                    pubkey: vec![],
+                };
+
+                let gas = estimate_gas(state, &tx).await?;
+                serde_json::to_value(format!("0x{:x}", gas))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "eth_chainId" => {
+                serde_json::to_value(format!("0x{:x}", eth::CHAIN_ID))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
+
+/// Block height named by an Ethereum block tag; `None` means the latest state
+fn parse_block_tag(state: &RpcState, tag: Option<&serde_json::Value>) -> Result<Option<u64>, RpcError> {
+    match tag.and_then(|t| t.as_str()) {
+        None | Some("latest") | Some("pending") | Some("safe") | Some("finalized") => Ok(None),
+        Some("earliest") => Ok(Some(0)),
+        Some(number) => {
+            let height = u64::try_from(parse_quantity(number)?)
+                .map_err(|_| RpcError::invalid_params(format!("Invalid block number: {}", number)))?;
+            let latest = state.storage.get_latest_height().ok().flatten().unwrap_or(0);
+            if height > latest {
+                return Err(RpcError::server(format!("Block {} not found", height)));
+            }
+            Ok(Some(height))
+        }
+    }
+}
+
+/// Height named by a block tag or number, for looking up blocks. `pending`, `safe` and
+/// `finalized` resolve to the latest block.
+fn block_height(state: &RpcState, tag: Option<&serde_json::Value>) -> Result<u64, RpcError> {
+    match tag.and_then(|t| t.as_str()) {
+        Some("earliest") => Ok(0),
+        None | Some("latest") | Some("pending") | Some("safe") | Some("finalized") => Ok(state
+            .storage
+            .get_latest_height()
+            .map_err(|e| RpcError::server(format!("Failed to get block height: {}", e)))?
+            .unwrap_or(0)),
+        Some(number) => u64::try_from(parse_quantity(number)?)
+            .map_err(|_| RpcError::invalid_params(format!("Invalid block number: {}", number))),
+    }
+}
+
+fn load_block(state: &RpcState, height: u64) -> Result<Option<Block>, RpcError> {
+    state
+        .storage
+        .get_block(height)
+        .map_err(|e| RpcError::server(format!("Failed to get block: {}", e)))
+}
+
+/// Block including a transaction and the transaction's position in it
+fn locate_transaction(state: &RpcState, tx_hash: &str) -> Result<Option<(Block, usize)>, RpcError> {
+    let Some((_, height)) = state
+        .storage
+        .get_transaction(tx_hash)
+        .map_err(|e| RpcError::server(format!("Failed to get transaction: {}", e)))?
+    else {
+        return Ok(None);
+    };
+    
+    Ok(load_block(state, height)?.and_then(|block| {
+        let index = block.transactions.iter().position(|tx| tx.hash() == tx_hash)?;
+        Some((block, index))
+    }))
+}
+
+/// Account address in the first parameter, in the form accounts are keyed by
+fn address_param(params: &Value) -> Result<String, RpcError> {
+    params
+        .get(0)
+        .and_then(|v| v.as_str())
+        .map(ethereum::address)
+        .ok_or_else(|| RpcError::invalid_params("Missing address parameter"))
+}
+
+/// Read-only view of the latest state, or of the state after the block a tag names
+fn state_at(state: &RpcState, tag: Option<&serde_json::Value>) -> Result<StateOverlay, RpcError> {
+    match parse_block_tag(state, tag)? {
+        None => Ok(state.state_manager.overlay()),
+        Some(height) => {
+            let block = load_block(state, height)?
+                .ok_or_else(|| RpcError::server(format!("Block {} not found", height)))?;
+            state
+                .state_manager
+                .overlay_at(&block.header.state_root)
+                .map_err(|e| RpcError::server(format!("Failed to load state: {}", e)))
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use executor::SystemState;
+use serde_json::Value;
+
+use super::Namespace;
+use crate::{overlay_at_height, system_state, GetProposalParams, GetTallyParams, GetVoteParams, GetVotingPowerParams, ListProposalsParams, RpcError, RpcState};
+
+/// `gov_*`: governance proposal and vote queries. Proposals and votes are signed transactions.
+pub struct Gov;
+
+#[async_trait]
+impl Namespace for Gov {
+    fn name(&self) -> &'static str {
+        "gov"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "gov_getProposal",
+            "gov_listProposals",
+            "gov_getVote",
+            "gov_getVotingPower",
+            "gov_getTallyResult",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "gov_getProposal" => {
+                let params: GetProposalParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let governance = system_state(state, params.block)?.governance;
+                let proposal = governance.get_proposal(params.proposal_id);
+
+                serde_json::to_value(proposal)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "gov_listProposals" => {
+                let params: ListProposalsParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let status_filter = params.status.and_then(|s| {
+                    match s.as_str() {
+                        "Review" => Some(governance::ProposalStatus::Review),
+                        "Active" => Some(governance::ProposalStatus::Active),
+                        "Passed" => Some(governance::ProposalStatus::Passed),
+                        "Rejected" => Some(governance::ProposalStatus::Rejected),
+                        "Expired" => Some(governance::ProposalStatus::Expired),
+                        "Executed" => Some(governance::ProposalStatus::Executed),
+                        "Failed" => Some(governance::ProposalStatus::Failed),
+                        "Vetoed" => Some(governance::ProposalStatus::Vetoed),
+                        _ => None,
+                    }
+                });
+
+                let governance = system_state(state, params.block)?.governance;
+                let proposals = governance.list_proposals(status_filter);
+
+                serde_json::to_value(proposals)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "gov_getVote" => {
+                let params: GetVoteParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let governance = system_state(state, params.block)?.governance;
+                let vote = governance.get_vote(params.proposal_id, &params.voter);
+
+                serde_json::to_value(vote)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "gov_getVotingPower" => {
+                let params: GetVotingPowerParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                // Power held after block `snapshot_height`, or now. A vote carries the power
+                // held when the block creating its proposal started.
+                let overlay = overlay_at_height(state, params.snapshot_height)?;
+                let balance = overlay.get_balance(&params.address).unwrap_or(0);
+                let total_power = SystemState::load(&overlay)
+                    .map_err(|e| RpcError::server(format!("Failed to load records: {}", e)))?
+                    .voting_power(&params.address, balance);
+
+                serde_json::to_value(total_power)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "gov_getTallyResult" => {
+                let params: GetTallyParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let governance = system_state(state, params.block)?.governance;
+                let tally = governance
+                    .get_tally_result(params.proposal_id)
+                    .map_err(|e| RpcError::server(format!("Failed to get tally: {}", e)))?;
+
+                serde_json::to_value(tally)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
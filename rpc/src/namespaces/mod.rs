@@ -0,0 +1,223 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::{RpcError, RpcState};
+
+mod act;
+mod admin;
+mod debug;
+mod eth;
+mod gov;
+mod net;
+mod stake;
+
+/// Namespaces in the order they are listed
+pub const NAMESPACES: [&str; 7] = ["act", "eth", "net", "stake", "gov", "debug", "admin"];
+
+/// A group of JSON-RPC methods that operators enable or disable together
+#[async_trait]
+pub(crate) trait Namespace: Send + Sync {
+    /// Name operators refer to it by, also the prefix of most of its methods
+    fn name(&self) -> &'static str;
+
+    /// Every method served, aliases included
+    fn methods(&self) -> &'static [&'static str];
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+fn all() -> Vec<Box<dyn Namespace>> {
+    vec![
+        Box::new(act::Act),
+        Box::new(eth::Eth),
+        Box::new(net::Net),
+        Box::new(stake::Stake),
+        Box::new(gov::Gov),
+        Box::new(debug::Debug),
+        Box::new(admin::Admin),
+    ]
+}
+
+/// Namespaces enabled on one listener
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceConfig {
+    enabled: BTreeSet<&'static str>,
+}
+
+impl NamespaceConfig {
+    /// Safe for an internet-facing listener: no tracing or node administration. Staking
+    /// and governance changes arrive as signed transactions, so their queries are safe.
+    pub fn public() -> Self {
+        Self::none().with("act").with("eth").with("net").with("stake").with("gov")
+    }
+
+    /// Every namespace and method, for a listener only the operator can reach
+    pub fn all() -> Self {
+        NAMESPACES.iter().fold(Self::none(), |config, name| config.with(name))
+    }
+
+    pub fn none() -> Self {
+        Self { enabled: BTreeSet::new() }
+    }
+
+    /// Enable one namespace. Unknown names are ignored.
+    pub fn with(mut self, namespace: &str) -> Self {
+        if let Some(name) = NAMESPACES.iter().find(|name| **name == namespace) {
+            self.enabled.insert(name);
+        }
+        self
+    }
+
+    pub fn enables(&self, namespace: &str) -> bool {
+        self.enabled.contains(namespace)
+    }
+
+    /// Parse an operator setting such as `act,eth,net,stake,gov`. Namespaces not listed are disabled.
+    pub fn parse(spec: &str) -> Result<Self> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .try_fold(Self::none(), |config, name| {
+                if !NAMESPACES.contains(&name) {
+                    return Err(anyhow!("Unknown RPC namespace '{}'", name));
+                }
+                Ok(config.with(name))
+            })
+    }
+}
+
+/// Methods served on one listener, resolved from its `NamespaceConfig`
+pub(crate) struct Registry {
+    config: NamespaceConfig,
+    methods: HashMap<&'static str, Arc<dyn Namespace>>,
+}
+
+impl Registry {
+    pub(crate) fn new(config: NamespaceConfig) -> Self {
+        let mut methods = HashMap::new();
+        for namespace in all() {
+            if !config.enables(namespace.name()) {
+                continue;
+            }
+
+            let namespace: Arc<dyn Namespace> = Arc::from(namespace);
+            for method in namespace.methods() {
+                methods.insert(*method, namespace.clone());
+            }
+        }
+
+        Self { config, methods }
+    }
+
+    /// Whether a namespace is served at all
+    pub(crate) fn enabled(&self, namespace: &str) -> bool {
+        self.config.enables(namespace)
+    }
+
+    pub(crate) fn exposes(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+
+    /// Namespace serving `method`, if it is served here
+    pub(crate) fn namespace_of(&self, method: &str) -> Option<&'static str> {
+        self.methods.get(method).map(|namespace| namespace.name())
+    }
+
+    /// Methods served from one namespace, sorted
+    pub(crate) fn methods_of(&self, namespace: &str) -> Vec<&'static str> {
+        let mut names: Vec<_> = self
+            .methods
+            .iter()
+            .filter(|(_, owner)| owner.name() == namespace)
+            .map(|(name, _)| *name)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// `rpc_modules` reply: enabled namespaces with their API version
+    pub(crate) fn modules(&self) -> Value {
+        let modules: serde_json::Map<String, Value> = self
+            .config
+            .enabled
+            .iter()
+            .map(|name| (name.to_string(), json!("1.0")))
+            .collect();
+        Value::Object(modules)
+    }
+
+    /// Methods not served here are reported as not found, whether they exist or not
+    pub(crate) async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        match self.methods.get(method) {
+            Some(namespace) => namespace.call(state, method, params).await,
+            None => Err(RpcError::method_not_found(method)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_listener_hides_privileged_methods() {
+        let public = Registry::new(NamespaceConfig::public());
+        assert!(public.exposes("eth_call"));
+        assert!(public.exposes("stake_getValidators"));
+        assert!(public.exposes("gov_getProposal"));
+        assert!(!public.exposes("debug_traceTransaction"));
+        assert!(!public.exposes("act_traceTransaction"));
+        assert!(!public.exposes("admin_nodeInfo"));
+        assert!(!public.enabled("debug"));
+
+        let local = Registry::new(NamespaceConfig::all());
+        assert!(local.exposes("debug_traceTransaction"));
+        assert!(local.exposes("admin_nodeInfo"));
+        assert_eq!(local.namespace_of("admin_nodeInfo"), Some("admin"));
+        assert_eq!(public.namespace_of("admin_nodeInfo"), None);
+
+        // Staking and governance changes are signed transactions, not RPC methods
+        for method in ["stake_deposit", "stake_delegate", "stake_unstake", "gov_propose", "gov_vote"] {
+            assert!(!local.exposes(method));
+        }
+
+        // Every method belongs to exactly one namespace
+        let total: usize = all().iter().map(|namespace| namespace.methods().len()).sum();
+        let served: usize = NAMESPACES.iter().map(|namespace| local.methods_of(namespace).len()).sum();
+        assert_eq!(served, total);
+
+        // rpc_discover documents exactly what a listener serves
+        let documented = |registry: &Registry| -> Vec<String> {
+            crate::openrpc::document(|method| registry.exposes(method))["methods"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|method| method["name"].as_str())
+                .filter(|name| !name.starts_with("rpc_"))
+                .map(str::to_string)
+                .collect()
+        };
+        let mut all_methods: Vec<String> =
+            NAMESPACES.iter().flat_map(|namespace| local.methods_of(namespace)).map(str::to_string).collect();
+        let mut local_documented = documented(&local);
+        all_methods.sort();
+        local_documented.sort();
+        assert_eq!(local_documented, all_methods);
+        assert!(!documented(&public).contains(&"debug_traceTransaction".to_string()));
+    }
+
+    #[test]
+    fn test_parse_namespace_config() {
+        let config = NamespaceConfig::parse("act, eth,net,stake,gov").unwrap();
+        assert_eq!(config, NamespaceConfig::public());
+        assert!(!config.enables("debug"));
+        assert!(NamespaceConfig::parse("debug").unwrap().enables("debug"));
+
+        assert!(NamespaceConfig::parse("personal").is_err());
+        assert!(NamespaceConfig::parse("eth:read").is_err());
+        assert_eq!(NamespaceConfig::parse("").unwrap(), NamespaceConfig::none());
+    }
+}
@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::Namespace;
+use crate::{RpcError, RpcState};
+use types::eth;
+
+/// `net_*`: network identity and peers
+pub struct Net;
+
+#[async_trait]
+impl Namespace for Net {
+    fn name(&self) -> &'static str {
+        "net"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "net_version",
+            "net_peerCount",
+            "net_listening",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, _params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "net_version" => {
+                // Network version (same as chain ID)
+                serde_json::to_value(eth::CHAIN_ID.to_string())
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "net_peerCount" => {
+                let peers = *state.peer_count.read().await;
+                serde_json::to_value(format!("0x{:x}", peers))
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            // Answering at all means the RPC listener is up
+            "net_listening" => Value::Bool(true),
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::Namespace;
+use crate::{system_state, ClaimParams, GetValidatorParams, GetValidatorsParams, RpcError, RpcState};
+
+/// `stake_*`: validator and delegation queries. Staking itself is done with signed transactions.
+pub struct Stake;
+
+#[async_trait]
+impl Namespace for Stake {
+    fn name(&self) -> &'static str {
+        "stake"
+    }
+
+    fn methods(&self) -> &'static [&'static str] {
+        &[
+            "stake_getValidator",
+            "stake_getValidators",
+            "stake_getDelegations",
+            "stake_getUnstakeRequests",
+            "stake_getRewards",
+        ]
+    }
+
+    async fn call(&self, state: &RpcState, method: &str, params: Value) -> Result<Value, RpcError> {
+        let result = match method {
+            "stake_getValidator" => {
+                let params: GetValidatorParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let staking = system_state(state, params.block)?.staking;
+                let validator = staking.get_validator(&params.address);
+
+                serde_json::to_value(validator)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "stake_getValidators" => {
+                let params: GetValidatorsParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let staking = system_state(state, params.block)?.staking;
+                let validators = if params.active_only {
+                    staking.get_active_validators()
+                } else {
+                    staking.get_all_validators()
+                };
+
+                serde_json::to_value(validators)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "stake_getDelegations" => {
+                let params: ClaimParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let staking = system_state(state, params.block)?.staking;
+                let delegations = staking.get_delegations(&params.address);
+
+                serde_json::to_value(delegations)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "stake_getUnstakeRequests" => {
+                let params: ClaimParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let staking = system_state(state, params.block)?.staking;
+                let requests = staking.get_unstake_requests(&params.address);
+
+                serde_json::to_value(requests)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            "stake_getRewards" => {
+                let params: ClaimParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+                let staking = system_state(state, params.block)?.staking;
+                let rewards = staking.get_unclaimed_rewards(&params.address);
+
+                serde_json::to_value(rewards)
+                    .map_err(|e| RpcError::server(format!("Serialization error: {}", e)))?
+            }
+
+            _ => return Err(RpcError::method_not_found(method)),
+        };
+
+        Ok(result)
+    }
+}
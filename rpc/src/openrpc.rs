@@ -0,0 +1,389 @@
+use serde_json::{json, Value};
+
+/// OpenRPC specification version the document follows
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// OpenRPC description of the methods for which `exposes` holds, as returned by
+/// `rpc_discover` for the namespaces a listener serves. `rpc_*` methods are always listed.
+pub fn document(exposes: impl Fn(&str) -> bool) -> Value {
+    let methods: Vec<Value> = methods()
+        .into_iter()
+        .filter(|method| method["name"].as_str().is_some_and(|name| name.starts_with("rpc_") || exposes(name)))
+        .collect();
+
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": "ACT Chain JSON-RPC API",
+            "description": "Native act_*, staking and governance methods plus the Ethereum-compatible eth_* subset. \
+                Subscriptions (eth_subscribe, act_subscribe) are available over the /ws endpoint only.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods,
+        "components": {
+            "schemas": schemas(),
+            "errors": errors(),
+        },
+    })
+}
+
+fn methods() -> Vec<Value> {
+    vec![
+        // ACT native
+        native("act_getBalance", "Balance of an account", vec![param("address", reference("Address"))], reference("Amount")),
+        native("act_getAccount", "Balance and nonce of an account", vec![param("address", reference("Address"))], reference("AccountInfo")),
+        native("act_getNonce", "Next nonce of an account", vec![param("address", reference("Address"))], integer()),
+        native("act_getProof", "Merkle proof of an account against the state root", vec![param("address", reference("Address"))], object()),
+        native("act_sendTransaction", "Submit a signed transaction to the mempool", vec![param("transaction", reference("Transaction"))], reference("PendingTransaction")),
+        native("act_getTransaction", "Transaction by hash, pending or included", vec![param("tx_hash", reference("Hash"))], nullable(reference("Transaction"))),
+        native("act_blockNumber", "Height of the latest block", vec![], integer()),
+        native("act_getBlock", "Block by height", vec![param("height", integer())], nullable(reference("Block"))),
+        native("act_getBlockByNumber", "Block by height", vec![param("height", integer())], nullable(reference("Block"))),
+        native("act_getBlockByHash", "Block by hash", vec![param("hash", reference("Hash"))], nullable(reference("Block"))),
+        native("act_getPendingTransactions", "Mempool transactions sent by an account", vec![param("address", reference("Address"))], array(reference("Transaction"))),
+        native("act_getMempoolStatus", "Mempool size and average gas price", vec![], reference("MempoolStatus")),
+        native(
+            "act_getLogs",
+            "Event logs in a block range, optionally filtered by emitter and topics",
+            vec![
+                optional("address", reference("Address")),
+                optional("topics", array(string())),
+                param("from_block", integer()),
+                param("to_block", integer()),
+            ],
+            array(reference("EventLog")),
+        ),
+        native("act_getTransactionReceipt", "Receipt of an included transaction", vec![param("tx_hash", reference("Hash"))], nullable(reference("Receipt"))),
+        native("act_traceTransaction", "Re-execute an included transaction and return its trace", vec![param("tx_hash", reference("Hash"))], reference("Trace")),
+        native("act_estimateGas", "Gas limit for a transaction, from a dry run against pending state", vec![param("transaction", reference("Transaction"))], integer()),
+        native(
+            "act_call",
+            "Execute a contract method read-only against latest or historic state",
+            vec![
+                param("to", reference("Address")),
+                optional("from", reference("Address")),
+                optional("method", string()),
+                optional("data", reference("Data")),
+                optional("value", reference("Amount")),
+                optional("gas", integer()),
+                optional("block", integer()),
+            ],
+            reference("CallResult"),
+        ),
+        // Ethereum compatible
+        ethereum("eth_chainId", "EIP-155 chain id", vec![], reference("Quantity")),
+        ethereum("eth_blockNumber", "Height of the latest block", vec![], reference("Quantity")),
+        ethereum("eth_getBalance", "Balance of an account", vec![param("address", reference("Address")), optional("block", reference("BlockTag"))], reference("Quantity")),
+        ethereum("eth_getTransactionCount", "Nonce of an account", vec![param("address", reference("Address")), optional("block", reference("BlockTag"))], reference("Quantity")),
+        ethereum("eth_getBlockByNumber", "Block by number or tag", vec![param("block", reference("BlockTag")), optional("fullTransactions", boolean())], nullable(reference("EthBlock"))),
+        ethereum("eth_getBlockByHash", "Block by hash", vec![param("hash", reference("Hash")), optional("fullTransactions", boolean())], nullable(reference("EthBlock"))),
+        ethereum("eth_getTransactionByHash", "Transaction by hash, pending or included", vec![param("hash", reference("Hash"))], nullable(reference("EthTransaction"))),
+        ethereum("eth_getTransactionReceipt", "Receipt of an included transaction", vec![param("hash", reference("Hash"))], nullable(reference("EthReceipt"))),
+        ethereum("eth_getLogs", "Logs matching a filter", vec![param("filter", reference("EthFilter"))], array(reference("EthLog"))),
+        ethereum("eth_gasPrice", "Suggested gas price, the median of recent blocks", vec![], reference("Quantity")),
+        ethereum("eth_maxPriorityFeePerGas", "Suggested priority fee; there is no base fee", vec![], reference("Quantity")),
+        ethereum(
+            "eth_feeHistory",
+            "Gas used and price percentiles of recent blocks",
+            vec![
+                param("blockCount", reference("Quantity")),
+                param("newestBlock", reference("BlockTag")),
+                optional("rewardPercentiles", array(json!({ "type": "number" }))),
+            ],
+            reference("FeeHistory"),
+        ),
+        ethereum("eth_getCode", "Contract code at an address", vec![param("address", reference("Address")), optional("block", reference("BlockTag"))], reference("Data")),
+        ethereum(
+            "eth_getStorageAt",
+            "Contract storage value, left-padded to 32 bytes",
+            vec![param("address", reference("Address")), param("slot", reference("Data")), optional("block", reference("BlockTag"))],
+            reference("Data"),
+        ),
+        ethereum("eth_sendRawTransaction", "Submit a signed legacy, EIP-2930 or EIP-1559 transaction", vec![param("transaction", reference("Data"))], reference("Hash")),
+        ethereum("eth_call", "Execute a call read-only; reverts carry the return data", vec![param("call", reference("EthCall")), optional("block", reference("BlockTag"))], reference("Data")),
+        ethereum("eth_estimateGas", "Gas limit for a call or deployment", vec![param("call", reference("EthCall"))], reference("Quantity")),
+        ethereum("net_version", "Network id, the chain id in decimal", vec![], string()),
+        ethereum("net_peerCount", "Connected peers", vec![], reference("Quantity")),
+        ethereum("net_listening", "Always true while the node answers", vec![], boolean()),
+        ethereum("debug_traceTransaction", "Re-execute an included transaction and return its trace", vec![param("hash", reference("Hash"))], reference("Trace")),
+        // Staking
+        native("stake_getValidator", "Validator by address", vec![param("address", reference("Address")), optional("block", integer())], nullable(reference("Validator"))),
+        native("stake_getValidators", "All or only active validators", vec![param("active_only", boolean()), optional("block", integer())], array(reference("Validator"))),
+        native("stake_getDelegations", "Delegations made by an account", vec![param("address", reference("Address")), optional("block", integer())], array(object())),
+        native("stake_getUnstakeRequests", "Pending unbonding requests of an account", vec![param("address", reference("Address")), optional("block", integer())], array(object())),
+        native("stake_getRewards", "Unclaimed staking rewards of an account", vec![param("address", reference("Address")), optional("block", integer())], integer()),
+        // Governance
+        native("gov_getProposal", "Proposal by id", vec![param("proposal_id", integer()), optional("block", integer())], nullable(reference("Proposal"))),
+        native("gov_listProposals", "Proposals, optionally by status", vec![optional("status", reference("ProposalStatus")), optional("block", integer())], array(reference("Proposal"))),
+        native("gov_getVote", "Vote cast by an account on a proposal", vec![param("proposal_id", integer()), param("voter", reference("Address")), optional("block", integer())], nullable(object())),
+        native("gov_getVotingPower", "Voting power of an account", vec![param("address", reference("Address")), optional("snapshot_height", integer())], integer()),
+        native("gov_getTallyResult", "Current tally of a proposal", vec![param("proposal_id", integer()), optional("block", integer())], object()),
+        // Node administration
+        native("admin_nodeInfo", "Version, chain id, height, peers and served namespaces", vec![], object()),
+        native("admin_removeTransaction", "Evict a transaction from this node's mempool", vec![param("tx_hash", reference("Hash"))], boolean()),
+        // Discovery
+        ethereum("rpc_discover", "This OpenRPC document, listing the methods this listener serves", vec![], object()),
+        ethereum("rpc_modules", "Namespaces this listener serves, with their API version", vec![], object()),
+    ]
+}
+
+fn schemas() -> Value {
+    json!({
+        "Address": { "type": "string", "description": "ACT address (ACT-...) or 0x-prefixed Ethereum address" },
+        "Hash": { "type": "string", "pattern": "^(0x)?[0-9a-f]{64}$" },
+        "Amount": { "type": "integer", "minimum": 0, "description": "Amount in the smallest ACT unit" },
+        "Quantity": { "type": "string", "pattern": "^0x(0|[1-9a-f][0-9a-f]*)$" },
+        "Data": { "type": "string", "pattern": "^0x([0-9a-f]{2})*$" },
+        "BlockTag": {
+            "oneOf": [
+                { "type": "string", "enum": ["earliest", "latest", "pending", "safe", "finalized"] },
+                { "$ref": "#/components/schemas/Quantity" },
+            ],
+        },
+        "AccountInfo": {
+            "type": "object",
+            "properties": {
+                "address": { "$ref": "#/components/schemas/Address" },
+                "balance": { "$ref": "#/components/schemas/Amount" },
+                "nonce": { "type": "integer" },
+            },
+        },
+        "Transaction": {
+            "type": "object",
+            "required": ["from", "nonce", "tx_type", "gas_limit", "gas_price", "signature", "pubkey"],
+            "properties": {
+                "from": { "$ref": "#/components/schemas/Address" },
+                "nonce": { "type": "integer" },
+                "tx_type": {
+                    "type": ["object", "string"],
+                    "description": "Transfer, ContractDeploy, ContractCall, EthereumLegacy, or a staking or governance action: \
+                        Stake, Unstake, Delegate, Undelegate, ClaimUnstaked, ClaimRewards, Propose, Vote, ReportEvidence, Unjail",
+                },
+                "gas_limit": { "type": "integer" },
+                "gas_price": { "$ref": "#/components/schemas/Amount" },
+                "signature": { "type": "array", "items": { "type": "integer" } },
+                "pubkey": { "type": "array", "items": { "type": "integer" } },
+            },
+        },
+        "PendingTransaction": {
+            "type": "object",
+            "properties": {
+                "tx_hash": { "$ref": "#/components/schemas/Hash" },
+                "status": { "type": "string", "enum": ["pending"] },
+            },
+        },
+        "Block": {
+            "type": "object",
+            "properties": {
+                "header": { "type": "object" },
+                "transactions": { "type": "array", "items": { "$ref": "#/components/schemas/Transaction" } },
+                "receipts": { "type": "array", "items": { "$ref": "#/components/schemas/Receipt" } },
+            },
+        },
+        "Receipt": {
+            "type": "object",
+            "properties": {
+                "transaction_hash": { "$ref": "#/components/schemas/Hash" },
+                "block_height": { "type": "integer" },
+                "from": { "$ref": "#/components/schemas/Address" },
+                "to": { "type": ["string", "null"] },
+                "contract_address": { "type": ["string", "null"] },
+                "status": { "type": "boolean" },
+                "gas_used": { "type": "integer" },
+                "logs": { "type": "array", "items": { "$ref": "#/components/schemas/EventLog" } },
+            },
+        },
+        "EventLog": {
+            "type": "object",
+            "properties": {
+                "address": { "$ref": "#/components/schemas/Address" },
+                "topics": { "type": "array", "items": { "type": "string" } },
+                "data": { "type": "array", "items": { "type": "integer" } },
+                "block_height": { "type": "integer" },
+                "transaction_hash": { "$ref": "#/components/schemas/Hash" },
+                "log_index": { "type": "integer" },
+            },
+        },
+        "MempoolStatus": {
+            "type": "object",
+            "properties": {
+                "pending_transactions": { "type": "integer" },
+                "unique_senders": { "type": "integer" },
+                "avg_gas_price": { "$ref": "#/components/schemas/Amount" },
+            },
+        },
+        "CallResult": {
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "gas_used": { "type": "integer" },
+                "return_data": { "$ref": "#/components/schemas/Data" },
+                "logs": { "type": "array", "items": { "type": "string" } },
+                "events": { "type": "array" },
+            },
+        },
+        "Trace": { "type": "object", "description": "Structured call and storage trace of one transaction" },
+        "EthBlock": { "type": "object", "description": "Ethereum block object; transactions are hashes unless full objects were requested" },
+        "EthTransaction": { "type": "object", "description": "Ethereum transaction object" },
+        "EthReceipt": { "type": "object", "description": "Ethereum transaction receipt object" },
+        "EthLog": { "type": "object", "description": "Ethereum log object; topics are 32-byte words" },
+        "EthFilter": {
+            "type": "object",
+            "properties": {
+                "blockHash": { "$ref": "#/components/schemas/Hash" },
+                "fromBlock": { "$ref": "#/components/schemas/BlockTag" },
+                "toBlock": { "$ref": "#/components/schemas/BlockTag" },
+                "address": { "oneOf": [{ "$ref": "#/components/schemas/Address" }, { "type": "array", "items": { "$ref": "#/components/schemas/Address" } }] },
+                "topics": { "type": "array" },
+            },
+        },
+        "EthCall": {
+            "type": "object",
+            "properties": {
+                "from": { "$ref": "#/components/schemas/Address" },
+                "to": { "$ref": "#/components/schemas/Address" },
+                "gas": { "$ref": "#/components/schemas/Quantity" },
+                "gasPrice": { "$ref": "#/components/schemas/Quantity" },
+                "value": { "$ref": "#/components/schemas/Quantity" },
+                "data": { "$ref": "#/components/schemas/Data" },
+                "input": { "$ref": "#/components/schemas/Data" },
+            },
+        },
+        "FeeHistory": {
+            "type": "object",
+            "properties": {
+                "oldestBlock": { "$ref": "#/components/schemas/Quantity" },
+                "baseFeePerGas": { "type": "array", "items": { "$ref": "#/components/schemas/Quantity" } },
+                "gasUsedRatio": { "type": "array", "items": { "type": "number" } },
+                "reward": { "type": "array", "items": { "type": "array", "items": { "$ref": "#/components/schemas/Quantity" } } },
+            },
+        },
+        "Validator": { "type": "object", "description": "Validator stake, commission, status and delegations" },
+        "Proposal": { "type": "object", "description": "Governance proposal with its status and voting window" },
+        "VoteOption": { "type": "string", "enum": ["Yes", "No", "Abstain"] },
+        "ProposalStatus": {
+            "type": "string",
+            "enum": ["Review", "Active", "Passed", "Rejected", "Expired", "Executed", "Failed", "Vetoed"],
+        },
+    })
+}
+
+fn errors() -> Value {
+    json!({
+        "ParseError": { "code": crate::PARSE_ERROR, "message": "Parse error" },
+        "InvalidRequest": { "code": crate::INVALID_REQUEST, "message": "Invalid request" },
+        "MethodNotFound": { "code": crate::METHOD_NOT_FOUND, "message": "Method not found" },
+        "InvalidParams": { "code": crate::INVALID_PARAMS, "message": "Invalid params" },
+        "ServerError": { "code": crate::SERVER_ERROR, "message": "Execution failed; `data` carries revert output or limits where available" },
+        "Unauthorized": { "code": crate::UNAUTHORIZED, "message": "Privileged method called without a valid API key" },
+        "LimitExceeded": { "code": crate::LIMIT_EXCEEDED, "message": "Rate limit or request size cap exceeded" },
+    })
+}
+
+/// Native methods take their parameters by name or, in declaration order, by position
+fn native(name: &str, summary: &str, params: Vec<Value>, result: Value) -> Value {
+    method(name, summary, params, result, "either")
+}
+
+/// Ethereum-style methods take positional parameters only
+fn ethereum(name: &str, summary: &str, params: Vec<Value>, result: Value) -> Value {
+    method(name, summary, params, result, "by-position")
+}
+
+fn method(name: &str, summary: &str, params: Vec<Value>, result: Value, param_structure: &str) -> Value {
+    json!({
+        "name": name,
+        "summary": summary,
+        "paramStructure": param_structure,
+        "params": params,
+        "result": { "name": "result", "schema": result },
+        "errors": [
+            { "$ref": "#/components/errors/InvalidParams" },
+            { "$ref": "#/components/errors/ServerError" },
+        ],
+    })
+}
+
+fn param(name: &str, schema: Value) -> Value {
+    json!({ "name": name, "required": true, "schema": schema })
+}
+
+fn optional(name: &str, schema: Value) -> Value {
+    json!({ "name": name, "required": false, "schema": schema })
+}
+
+fn reference(schema: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", schema) })
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({ "oneOf": [schema, { "type": "null" }] })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn object() -> Value {
+    json!({ "type": "object" })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn integer() -> Value {
+    json!({ "type": "integer" })
+}
+
+fn boolean() -> Value {
+    json!({ "type": "boolean" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Every `$ref` in `value`
+    fn references<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(target)) = map.get("$ref") {
+                    found.push(target);
+                }
+                map.values().for_each(|v| references(v, found));
+            }
+            Value::Array(items) => items.iter().for_each(|v| references(v, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_is_consistent() {
+        let document = document(|_| true);
+        let methods = document["methods"].as_array().unwrap();
+
+        let mut names = HashSet::new();
+        for method in methods {
+            let name = method["name"].as_str().unwrap();
+            assert!(names.insert(name), "{} documented twice", name);
+
+            // Required parameters cannot follow optional ones when passed by position
+            let required: Vec<bool> = method["params"].as_array().unwrap().iter().map(|p| p["required"] == true).collect();
+            if method["paramStructure"] == "by-position" {
+                assert!(required.windows(2).all(|w| w[0] || !w[1]), "{} has a required param after an optional one", name);
+            }
+        }
+        for namespace in ["act_", "eth_", "stake_", "gov_"] {
+            assert!(names.iter().any(|name| name.starts_with(namespace)));
+        }
+
+        let mut targets = Vec::new();
+        references(&document, &mut targets);
+        for target in targets {
+            let path = target.strip_prefix("#/").unwrap();
+            assert!(document.pointer(&format!("/{}", path)).is_some(), "dangling reference {}", target);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, State as AxumState,
+    },
+    http::HeaderMap,
+    response::Response,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::task::JoinHandle;
+use types::{Block, EventLog, Transaction};
+
+use crate::{charge, dispatch, ethereum, parse_call, parse_payload, Caller, JsonRpcResponse, RpcError, RpcPayload, RpcState};
+
+/// Stored blocks buffered for subscribers that have not caught up yet
+pub const BLOCK_EVENTS_CAPACITY: usize = 64;
+
+/// Outgoing messages queued per connection. A client that lets notifications pile up
+/// past this is disconnected rather than buffered without bound.
+pub const CONNECTION_QUEUE_SIZE: usize = 256;
+
+/// Active subscriptions allowed per connection
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 32;
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// `act_subscribe("logs", ...)` filter, matched like `StateManager::query_logs`
+#[derive(Debug, Default, Deserialize)]
+pub struct LogSubscriptionParams {
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+/// Which API a subscription was made through, deciding the notification format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flavor {
+    Act,
+    Eth,
+}
+
+impl Flavor {
+    fn notification_method(self) -> &'static str {
+        match self {
+            Flavor::Act => "act_subscription",
+            Flavor::Eth => "eth_subscription",
+        }
+    }
+}
+
+/// Per-connection subscription state
+struct Connection {
+    caller: Caller,  // Fixed when the connection is upgraded
+    outgoing: mpsc::Sender<String>,
+    overflow: Arc<Notify>,  // Signalled when a subscription cannot keep up
+    subscriptions: HashMap<String, JoinHandle<()>>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in self.subscriptions.values() {
+            task.abort();
+        }
+    }
+}
+
+/// Upgrade `GET /ws` to a WebSocket speaking the same JSON-RPC as `POST /`. Messages are
+/// held to the listener's body size cap and charged to the client's rate limit like requests.
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    AxumState(state): AxumState<RpcState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Response {
+    let caller = Caller::new(&state, peer, &headers);
+    let max_message_size = state.limiter.limits().max_body_bytes;
+    ws.max_message_size(max_message_size)
+        .on_upgrade(move |socket| serve_connection(socket, state, caller))
+}
+
+async fn serve_connection(socket: WebSocket, state: RpcState, caller: Caller) {
+    let (mut sink, mut stream) = socket.split();
+    let (outgoing, mut queue) = mpsc::channel::<String>(CONNECTION_QUEUE_SIZE);
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = queue.recv().await {
+            if sink.send(Message::Text(message)).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let mut connection = Connection {
+        caller,
+        outgoing,
+        overflow: Arc::new(Notify::new()),
+        subscriptions: HashMap::new(),
+    };
+    let overflow = connection.overflow.clone();
+
+    loop {
+        let text = tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,  // Pings are answered by the socket itself
+            },
+            _ = overflow.notified() => {
+                println!("⚠️  Closing WebSocket connection that fell behind its subscriptions");
+                writer.abort();
+                return;
+            }
+        };
+
+        // Replies wait for queue space; only notifications are refused when it is full
+        if let Some(reply) = handle_message(&state, &mut connection, &text).await {
+            if connection.outgoing.send(reply).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    // Let queued replies drain before the socket closes
+    drop(connection);
+    let _ = writer.await;
+}
+
+/// Answer one WebSocket message, a single call or a batch, subscription requests included.
+/// `None` when there is nothing to reply, as for notifications.
+async fn handle_message(state: &RpcState, connection: &mut Connection, text: &str) -> Option<String> {
+    let payload = parse_payload(text, state.limiter.limits().max_batch_size);
+    if let Err((error, _)) = charge(state, &connection.caller, &payload) {
+        return Some(json!(JsonRpcResponse::new(Value::Null, Err(error))).to_string());
+    }
+
+    let reply = match payload {
+        Err(error) => json!(JsonRpcResponse::new(Value::Null, Err(error))),
+        Ok(RpcPayload::Single(call)) => json!(handle_call(state, connection, call).await?),
+        Ok(RpcPayload::Batch(calls)) => {
+            let mut replies = Vec::new();
+            for call in calls {
+                replies.extend(handle_call(state, connection, call).await);
+            }
+            if replies.is_empty() {
+                return None;
+            }
+            json!(replies)
+        }
+    };
+    Some(reply.to_string())
+}
+
+async fn handle_call(state: &RpcState, connection: &mut Connection, call: Value) -> Option<JsonRpcResponse> {
+    let (request, expects_reply) = match parse_call(call) {
+        Ok(parsed) => parsed,
+        Err(error) => return Some(*error),
+    };
+    let id = request.id.clone();
+
+    let result = match request.method.as_str() {
+        "eth_subscribe" if state.registry.enabled("eth") => subscribe(state, connection, Flavor::Eth, &request.params),
+        "act_subscribe" if state.registry.enabled("act") => subscribe(state, connection, Flavor::Act, &request.params),
+        "eth_unsubscribe" | "act_unsubscribe" => unsubscribe(connection, &request.params),
+        _ => dispatch(state, &connection.caller, request).await,
+    };
+
+    expects_reply.then(|| JsonRpcResponse::new(id, result))
+}
+
+fn subscribe(state: &RpcState, connection: &mut Connection, flavor: Flavor, params: &Value) -> Result<Value, RpcError> {
+    if connection.subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+        return Err(RpcError::server(format!(
+            "Too many subscriptions: at most {} per connection",
+            MAX_SUBSCRIPTIONS_PER_CONNECTION
+        )));
+    }
+
+    let kind = params
+        .get(0)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("Missing subscription type"))?;
+    let id = format!("0x{:x}", NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed));
+    let sink = Subscriber {
+        id: id.clone(),
+        flavor,
+        outgoing: connection.outgoing.clone(),
+        overflow: connection.overflow.clone(),
+    };
+
+    let task = match kind {
+        "newHeads" => sink.forward(state.new_blocks.subscribe(), move |block: &Block| {
+            vec![head_json(block, flavor)]
+        }),
+        "logs" => {
+            let filter = params.get(1).cloned().unwrap_or_else(|| json!({}));
+            match flavor {
+                Flavor::Eth => {
+                    let filter = ethereum::LogFilter::from_json(&filter)?;
+                    sink.forward(state.new_blocks.subscribe(), move |block: &Block| {
+                        ethereum::block_logs(block, &filter)
+                    })
+                }
+                Flavor::Act => {
+                    let filter: LogSubscriptionParams = serde_json::from_value(filter)
+                        .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+                    sink.forward(state.new_blocks.subscribe(), move |block: &Block| {
+                        block_logs(block)
+                            .filter(|log| log.matches(filter.address.as_deref(), &filter.topics))
+                            .map(|log| json!(log))
+                            .collect()
+                    })
+                }
+            }
+        }
+        "newPendingTransactions" | "pendingTransactions" => {
+            sink.forward(state.mempool.subscribe(), move |tx: &Transaction| match flavor {
+                Flavor::Eth => vec![json!(ethereum::hash(&tx.hash()))],
+                Flavor::Act => vec![json!(tx)],
+            })
+        }
+        other => return Err(RpcError::invalid_params(format!("Unsupported subscription type: {}", other))),
+    };
+
+    println!("🔔 New {} subscription {}", kind, id);
+    connection.subscriptions.insert(id.clone(), task);
+    Ok(json!(id))
+}
+
+fn unsubscribe(connection: &mut Connection, params: &Value) -> Result<Value, RpcError> {
+    let id = params
+        .get(0)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("Missing subscription id"))?;
+
+    let removed = connection.subscriptions.remove(id);
+    if let Some(task) = &removed {
+        task.abort();
+    }
+    Ok(json!(removed.is_some()))
+}
+
+/// Delivery end of one subscription
+struct Subscriber {
+    id: String,
+    flavor: Flavor,
+    outgoing: mpsc::Sender<String>,
+    overflow: Arc<Notify>,
+}
+
+impl Subscriber {
+    /// Turn each event into zero or more notifications until the connection goes away.
+    /// Falling behind, on the event channel or the connection queue, ends the connection.
+    fn forward<T, F>(self, mut events: broadcast::Receiver<Arc<T>>, render: F) -> JoinHandle<()>
+    where
+        T: Send + Sync + 'static,
+        F: Fn(&T) -> Vec<Value> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        self.overflow.notify_one();
+                        return;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+
+                for result in render(&event) {
+                    let notification = json!({
+                        "jsonrpc": "2.0",
+                        "method": self.flavor.notification_method(),
+                        "params": { "subscription": self.id, "result": result },
+                    });
+                    match self.outgoing.try_send(notification.to_string()) {
+                        Ok(()) => {}
+                        Err(mpsc::error::TrySendError::Full(_)) => {
+                            self.overflow.notify_one();
+                            return;
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => return,
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// `newHeads` payload: the Ethereum header object, or the ACT header with its hash
+fn head_json(block: &Block, flavor: Flavor) -> Value {
+    match flavor {
+        Flavor::Eth => {
+            let mut head = ethereum::block_json(block, false);
+            if let Some(head) = head.as_object_mut() {
+                head.remove("transactions");
+                head.remove("uncles");
+            }
+            head
+        }
+        Flavor::Act => {
+            let mut head = json!(block.header);
+            head["hash"] = json!(block.hash());
+            head
+        }
+    }
+}
+
+fn block_logs(block: &Block) -> impl Iterator<Item = &EventLog> {
+    block.receipts.iter().flat_map(|receipt| receipt.logs.iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::BlockHeader;
+
+    fn block(height: u64) -> Block {
+        let header = BlockHeader {
+            parent_hash: "0".repeat(64),
+            tx_root: Block::calculate_tx_root(&[]),
+            actor_pubkey: "ACT-proposer".to_string(),
+            state_root: "ab".repeat(32),
+            receipts_root: Block::calculate_receipts_root(&[]),
+            timestamp: 1_700_000_000,
+            validator_commitment: String::new(),
+            reward: 0,
+            height,
+            round: 0,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: "0".repeat(64),
+        };
+        Block::new(header, vec![], vec![])
+    }
+
+    fn subscriber(queue_size: usize) -> (Subscriber, mpsc::Receiver<String>, Arc<Notify>) {
+        let (outgoing, queue) = mpsc::channel(queue_size);
+        let overflow = Arc::new(Notify::new());
+        let subscriber = Subscriber {
+            id: "0x1".to_string(),
+            flavor: Flavor::Eth,
+            outgoing,
+            overflow: overflow.clone(),
+        };
+        (subscriber, queue, overflow)
+    }
+
+    #[tokio::test]
+    async fn test_new_heads_notification() {
+        let (blocks, _) = broadcast::channel(BLOCK_EVENTS_CAPACITY);
+        let (subscriber, mut queue, _) = subscriber(CONNECTION_QUEUE_SIZE);
+        let task = subscriber.forward(blocks.subscribe(), |block: &Block| vec![head_json(block, Flavor::Eth)]);
+
+        blocks.send(Arc::new(block(5))).unwrap();
+        let notification: Value = serde_json::from_str(&queue.recv().await.unwrap()).unwrap();
+        assert_eq!(notification["method"], "eth_subscription");
+        assert_eq!(notification["params"]["subscription"], "0x1");
+        assert_eq!(notification["params"]["result"]["number"], "0x5");
+        assert!(notification["params"]["result"].get("transactions").is_none());
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_slow_connection_overflows() {
+        let (blocks, _) = broadcast::channel(BLOCK_EVENTS_CAPACITY);
+        let (subscriber, _queue, overflow) = subscriber(1);
+        let task = subscriber.forward(blocks.subscribe(), |block: &Block| vec![head_json(block, Flavor::Act)]);
+
+        // Nothing drains the queue, so the second notification does not fit
+        blocks.send(Arc::new(block(1))).unwrap();
+        blocks.send(Arc::new(block(2))).unwrap();
+        overflow.notified().await;
+        task.await.unwrap();
+    }
+}
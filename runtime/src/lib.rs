@@ -1,10 +1,20 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::sync::Arc;
-use types::{Action, EventLog};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use state::GasConfig;
+use types::{Action, ActAmount, EventLog};
 use wasmtime::*;
 
+mod module_cache;
+mod trace;
+mod validation;
+pub use module_cache::{code_hash, ModuleCache, DEFAULT_MODULE_CACHE_SIZE};
+pub use trace::{CallTrace, TraceOp, TraceStep};
+pub use validation::{validate_contract, ContractLimits, HOST_FUNCTIONS, REQUIRED_EXPORTS};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionResult {
     pub success: bool,
@@ -13,45 +23,209 @@ pub struct ExecutionResult {
     pub logs: Vec<String>,
     pub events: Vec<EventLog>,  // Event logs emitted during execution
     pub return_data: Vec<u8>,   // Data returned by contract
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<CallTrace>,  // Present when the call was made with `CallParams::trace`
 }
 
+/// Clones share the engine and compiled module cache
+#[derive(Clone)]
 pub struct WasmRuntime {
-    engine: Engine,
-    module_cache: HashMap<String, Module>,
+    vm: Vm,
+}
+
+/// Raised when a call exhausts its gas, either in WASM code or in a host function
+#[derive(Debug)]
+pub struct OutOfGas;
+
+impl std::fmt::Display for OutOfGas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Out of gas")
+    }
+}
+
+impl std::error::Error for OutOfGas {}
+
+/// Maximum nesting of contract-to-contract calls
+pub const MAX_CALL_DEPTH: u32 = 10;
+
+/// Status codes returned by the `call_contract` host function.
+/// Non-negative values mean success and give the length of the callee's return data.
+pub mod call_status {
+    pub const DEPTH_EXCEEDED: i32 = -1;
+    pub const CALLEE_FAILED: i32 = -2;
+    pub const NO_CONTRACT: i32 = -3;
+    pub const INSUFFICIENT_BALANCE: i32 = -4;
+}
+
+/// Parameters for invoking a deployed contract
+#[derive(Debug, Clone)]
+pub struct CallParams {
+    pub contract_address: String,
+    pub caller: String,
+    pub method: String,
+    pub input: Vec<u8>,
+    pub value: ActAmount,
+    pub gas_limit: u64,
+    pub transaction_hash: String,
+    pub block_height: u64,
+    pub trace: bool,  // Record a `CallTrace` of this call and its sub-calls
+}
+
+/// Chain state that host functions read and write on behalf of a contract
+pub trait HostState: Send + 'static {
+    /// Snapshot used to undo the effects of a failed nested call
+    type Checkpoint;
+    
+    fn storage_read(&self, contract: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    
+    /// Write a storage value. An empty value is stored like any other; only
+    /// `storage_delete` removes a key.
+    fn storage_write(&mut self, contract: &str, key: &[u8], value: &[u8]) -> Result<()>;
+    
+    fn storage_delete(&mut self, contract: &str, key: &[u8]) -> Result<()>;
+    
+    fn balance(&self, address: &str) -> Result<ActAmount>;
+    
+    fn get_code(&self, address: &str) -> Result<Option<Vec<u8>>>;
+    
+    fn transfer(&mut self, from: &str, to: &str, amount: ActAmount) -> Result<()>;
+    
+    fn checkpoint(&self) -> Self::Checkpoint;
+    
+    fn revert(&mut self, checkpoint: Self::Checkpoint);
+}
+
+/// Contract state held in memory, for running modules outside a chain
+#[derive(Debug, Clone, Default)]
+pub struct MemoryState {
+    pub storage: HashMap<(String, Vec<u8>), Vec<u8>>,
+    pub balances: HashMap<String, ActAmount>,
+    pub code: HashMap<String, Vec<u8>>,
+}
+
+impl HostState for MemoryState {
+    type Checkpoint = MemoryState;
+
+    fn storage_read(&self, contract: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.storage.get(&(contract.to_string(), key.to_vec())).cloned())
+    }
+
+    fn storage_write(&mut self, contract: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.storage.insert((contract.to_string(), key.to_vec()), value.to_vec());
+        Ok(())
+    }
+
+    fn storage_delete(&mut self, contract: &str, key: &[u8]) -> Result<()> {
+        self.storage.remove(&(contract.to_string(), key.to_vec()));
+        Ok(())
+    }
+
+    fn balance(&self, address: &str) -> Result<ActAmount> {
+        Ok(self.balances.get(address).copied().unwrap_or(0))
+    }
+
+    fn get_code(&self, address: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.code.get(address).cloned())
+    }
+
+    fn transfer(&mut self, from: &str, to: &str, amount: ActAmount) -> Result<()> {
+        let from_balance = self.balance(from)?;
+        if from_balance < amount {
+            return Err(anyhow!("Insufficient balance: has {}, needs {}", from_balance, amount));
+        }
+        self.balances.insert(from.to_string(), from_balance - amount);
+        *self.balances.entry(to.to_string()).or_insert(0) += amount;
+        Ok(())
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        self.clone()
+    }
+
+    fn revert(&mut self, checkpoint: Self::Checkpoint) {
+        *self = checkpoint;
+    }
 }
 
 /// Execution context for WASM runtime
-struct ExecutionContext {
+struct ExecutionContext<H> {
+    host: Option<H>,  // Lent to the child store while a nested call runs
+    limits: StoreLimits,
     events: Vec<EventLog>,
+    logs: Vec<String>,  // Messages passed to the `log` host function
     gas_used: u64,
     gas_limit: u64,
+    instruction_cost: u64,  // Gas per unit of fuel
+    fuel_granted: u64,      // Fuel in the store when it was last refilled
     contract_address: String,
+    caller: String,
+    input: Vec<u8>,
+    value: ActAmount,
+    return_data: Vec<u8>,       // Set by this contract via `set_return_data`
+    last_call_return: Vec<u8>,  // Return data of the latest nested call
     transaction_hash: String,
     block_height: u64,
     call_depth: u32,  // Track nested contract calls
+    trace: Option<Vec<TraceStep>>,  // Steps recorded when tracing
 }
 
-impl ExecutionContext {
-    fn new(contract_address: String, transaction_hash: String, block_height: u64, gas_limit: u64) -> Self {
+impl<H> ExecutionContext<H> {
+    fn new(host: H, params: &CallParams, instruction_cost: u64, call_depth: u32) -> Self {
         Self {
+            host: Some(host),
+            limits: ContractLimits::default().store_limits(),
             events: Vec::new(),
+            logs: Vec::new(),
             gas_used: 0,
-            gas_limit,
-            contract_address,
-            transaction_hash,
-            block_height,
-            call_depth: 0,
+            gas_limit: params.gas_limit,
+            instruction_cost: instruction_cost.max(1),
+            fuel_granted: 0,
+            contract_address: params.contract_address.clone(),
+            caller: params.caller.clone(),
+            input: params.input.clone(),
+            value: params.value,
+            return_data: Vec::new(),
+            last_call_return: Vec::new(),
+            transaction_hash: params.transaction_hash.clone(),
+            block_height: params.block_height,
+            call_depth,
+            trace: params.trace.then(Vec::new),
         }
     }
     
+    fn host(&self) -> &H {
+        self.host.as_ref().expect("host state is only lent out during nested calls")
+    }
+    
+    fn host_mut(&mut self) -> &mut H {
+        self.host.as_mut().expect("host state is only lent out during nested calls")
+    }
+    
     fn consume_gas(&mut self, amount: u64) -> Result<()> {
-        self.gas_used += amount;
+        self.gas_used = self.gas_used.saturating_add(amount);
         if self.gas_used > self.gas_limit {
-            return Err(anyhow!("Out of gas"));
+            self.gas_used = self.gas_limit;
+            return Err(OutOfGas.into());
         }
         Ok(())
     }
     
+    /// Charge for the instructions executed since the store was last refuelled
+    fn sync_fuel(&mut self, fuel_left: u64) {
+        let burned = self.fuel_granted.saturating_sub(fuel_left);
+        self.gas_used = self
+            .gas_used
+            .saturating_add(burned.saturating_mul(self.instruction_cost))
+            .min(self.gas_limit);
+        self.fuel_granted = fuel_left;
+    }
+    
+    /// Fuel that covers the gas still available
+    fn refuel(&mut self) -> u64 {
+        self.fuel_granted = (self.gas_limit - self.gas_used) / self.instruction_cost;
+        self.fuel_granted
+    }
+    
     fn emit_event(&mut self, topics: Vec<String>, data: Vec<u8>) {
         let log = EventLog::new(
             self.contract_address.clone(),
@@ -64,190 +238,592 @@ impl ExecutionContext {
         self.events.push(log);
     }
     
+    /// Record a trace step; `op` is only built when tracing
+    fn trace(&mut self, gas_before: u64, op: impl FnOnce() -> TraceOp) {
+        let gas_after = self.gas_used;
+        if let Some(steps) = &mut self.trace {
+            steps.push(TraceStep { gas_before, gas_after, op: op() });
+        }
+    }
+    
     fn can_call_contract(&self) -> bool {
-        self.call_depth < 10  // Max call depth to prevent infinite recursion
+        self.call_depth < MAX_CALL_DEPTH  // Max call depth to prevent infinite recursion
     }
 }
 
-impl WasmRuntime {
-    pub fn new() -> Result<Self> {
-        let mut config = Config::new();
-        config.wasm_threads(false);
-        config.wasm_reference_types(true);
-        config.wasm_bulk_memory(true);
-        
-        let engine = Engine::new(&config)?;
-        
-        Ok(Self {
-            engine,
-            module_cache: HashMap::new(),
-        })
+/// Charge a host function's gas, keeping the fuel budget in step.
+/// Returns the gas used before the charge; running out of gas traps the calling contract.
+fn charge_gas<H>(caller: &mut Caller<'_, ExecutionContext<H>>, amount: u64) -> Result<u64> {
+    let fuel_left = caller.get_fuel()?;
+    let ctx = caller.data_mut();
+    ctx.sync_fuel(fuel_left);
+    let gas_before = ctx.gas_used;
+    ctx.consume_gas(amount)?;
+    let fuel = ctx.refuel();
+    caller.set_fuel(fuel)?;
+    Ok(gas_before)
+}
+
+/// Gas for `len` bytes at `cost` each
+fn per_byte(cost: u64, len: usize) -> u64 {
+    cost.saturating_mul(len as u64)
+}
+
+/// Exported linear memory of the calling instance
+fn caller_memory<H>(caller: &mut Caller<'_, ExecutionContext<H>>) -> Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow!("Contract does not export memory"))
+}
+
+/// Copy `len` bytes out of the caller's memory, trapping on out-of-bounds access
+fn read_memory<H>(caller: &mut Caller<'_, ExecutionContext<H>>, ptr: i32, len: i32) -> Result<Vec<u8>> {
+    let memory = caller_memory(caller)?;
+    
+    // Check bounds before allocating so a bogus length cannot exhaust host memory
+    let end = (ptr as u32 as usize).checked_add(len as u32 as usize);
+    if !matches!(end, Some(end) if end <= memory.data_size(&*caller)) {
+        return Err(anyhow!("Invalid memory read at {} (len {}): out of bounds", ptr, len));
     }
+    
+    let mut buffer = vec![0u8; len as u32 as usize];
+    memory
+        .read(&*caller, ptr as u32 as usize, &mut buffer)
+        .map_err(|e| anyhow!("Invalid memory read at {} (len {}): {}", ptr, len, e))?;
+    Ok(buffer)
+}
 
-    pub fn execute_action(&mut self, action: &Action, wasm_bytes: &[u8]) -> Result<ExecutionResult> {
-        // Create execution context
-        let ctx = ExecutionContext::new(
-            action.actor.clone(),
-            "tx_hash_placeholder".to_string(),
-            0,
-            1_000_000,
-        );
+/// Read a UTF-8 string (such as an address) from the caller's memory
+fn read_string<H>(caller: &mut Caller<'_, ExecutionContext<H>>, ptr: i32, len: i32) -> Result<String> {
+    String::from_utf8(read_memory(caller, ptr, len)?)
+        .map_err(|_| anyhow!("Invalid UTF-8 string at {} (len {})", ptr, len))
+}
+
+/// Copy bytes into the caller's memory, trapping on out-of-bounds access
+fn write_memory<H>(caller: &mut Caller<'_, ExecutionContext<H>>, ptr: i32, data: &[u8]) -> Result<()> {
+    let memory = caller_memory(caller)?;
+    memory
+        .write(&mut *caller, ptr as u32 as usize, data)
+        .map_err(|e| anyhow!("Invalid memory write at {} (len {}): {}", ptr, data.len(), e))
+}
+
+/// Copy `data` into the caller's buffer of `capacity` bytes at `ptr` when it fits, and
+/// return its full length; a larger return value tells the contract how big a buffer to pass.
+/// The whole buffer must lie in memory, whether or not `data` is written.
+fn write_output<H>(caller: &mut Caller<'_, ExecutionContext<H>>, ptr: i32, capacity: i32, data: &[u8]) -> Result<i32> {
+    let memory = caller_memory(caller)?;
+    let end = (ptr as u32 as usize).checked_add(capacity as u32 as usize);
+    if !matches!(end, Some(end) if end <= memory.data_size(&*caller)) {
+        return Err(anyhow!("Invalid output buffer at {} (len {}): out of bounds", ptr, capacity));
+    }
+    
+    if data.len() <= capacity as u32 as usize {
+        write_memory(caller, ptr, data)?;
+    }
+    i32::try_from(data.len()).map_err(|_| anyhow!("Output of {} bytes is too large", data.len()))
+}
+
+/// Whether a call failed by exhausting fuel or gas in a host function
+fn is_out_of_gas(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<OutOfGas>().is_some() || matches!(error.downcast_ref::<Trap>(), Some(Trap::OutOfFuel))
+}
+
+/// Engine, gas schedule and module cache, shared with host functions so they can run nested calls
+#[derive(Clone)]
+struct Vm {
+    engine: Engine,
+    gas_config: Arc<GasConfig>,
+    modules: Arc<Mutex<ModuleCache>>,
+    limits: ContractLimits,
+}
+
+impl Vm {
+    /// Run one call frame; see `WasmRuntime::execute_call`
+    fn run<H: HostState>(&self, params: &CallParams, wasm_bytes: &[u8], host: H, call_depth: u32) -> (ExecutionResult, H) {
+        let compiled = self.modules.lock().unwrap().get_or_compile(&self.engine, wasm_bytes);
+        let module = match compiled {
+            Ok(module) => module,
+            Err(e) => {
+                let result = failed(params, params.gas_limit, format!("Invalid module: {}", e));
+                return (with_trace(result, params, None), host);
+            }
+        };
         
         // Create a new store with execution context
+        let mut ctx = ExecutionContext::new(host, params, self.gas_config.wasm_instruction_cost, call_depth);
+        ctx.limits = self.limits.store_limits();
         let mut store = Store::new(&self.engine, ctx);
+        store.limiter(|ctx| &mut ctx.limits);
+        let fuel = store.data_mut().refuel();
+        
+        let instance = match store
+            .set_fuel(fuel)
+            .and_then(|_| self.create_linker())
+            .and_then(|linker| linker.instantiate(&mut store, &module))
+        {
+            Ok(instance) => instance,
+            Err(e) => {
+                let host = store.into_data().host.expect("host state returned after instantiation");
+                let result = failed(params, params.gas_limit, format!("Instantiation failed: {}", e));
+                return (with_trace(result, params, None), host);
+            }
+        };
+        
+        // Call the requested export
+        let call_result = if let Ok(method_fn) = instance.get_typed_func::<(), i32>(&mut store, &params.method) {
+            method_fn.call(&mut store, ())
+        } else if let Ok(method_fn) = instance.get_typed_func::<(i32, i32), i32>(&mut store, &params.method) {
+            method_fn.call(&mut store, (0, 0))
+        } else {
+            Err(anyhow!("No compatible '{}' function found in WASM module", params.method))
+        };
         
-        // Load or compile the WASM module
-        let module = Module::new(&self.engine, wasm_bytes)?;
+        // Charge for instructions executed after the last host call
+        let fuel_left = store.get_fuel().unwrap_or(0);
+        let mut ctx = store.into_data();
+        ctx.sync_fuel(fuel_left);
+        let gas_used = ctx.gas_used;
+        let host = ctx.host.take().expect("host state returned after call");
+        let steps = ctx.trace.take();
+        
+        // Build execution result; events from a failed call are discarded
+        let execution_result = match call_result {
+            Ok(result_code) => ExecutionResult {
+                success: result_code == 0,
+                gas_used,
+                state_changes: HashMap::new(),
+                logs: ctx
+                    .logs
+                    .into_iter()
+                    .chain(std::iter::once(format!("{} returned {}", params.method, result_code)))
+                    .collect(),
+                events: if result_code == 0 { ctx.events } else { Vec::new() },
+                return_data: ctx.return_data,
+                trace: None,
+            },
+            Err(e) if is_out_of_gas(&e) => failed(params, params.gas_limit, format!("{} ran out of gas", params.method)),
+            Err(e) => failed(params, gas_used, format!("{} trapped: {}", params.method, e)),
+        };
         
+        (with_trace(execution_result, params, steps), host)
+    }
+
+    /// Build a linker exposing the `env` host ABI
+    fn create_linker<H: HostState>(&self) -> Result<Linker<ExecutionContext<H>>> {
         // Create a linker to provide host functions
         let mut linker = Linker::new(&self.engine);
         
+        let log_cost = self.gas_config.log_cost;
+        let log_byte_cost = self.gas_config.log_byte_cost;
+        let event_cost = self.gas_config.event_cost;
+        let event_byte_cost = self.gas_config.event_byte_cost;
+        let storage_read_cost = self.gas_config.storage_read_cost;
+        let storage_write_cost = self.gas_config.storage_write_cost;
+        let storage_byte_cost = self.gas_config.storage_byte_cost;
+        let env_query_cost = self.gas_config.env_query_cost;
+        let call_cost = self.gas_config.call_cost;
+        
+        // Host functions trap on out-of-bounds pointers and when gas runs out; the call then fails.
+        // Those taking data from the contract charge per byte once it has been read.
+        
         // Add host function: log message
-        linker.func_wrap("env", "log", |mut caller: Caller<'_, ExecutionContext>, msg_ptr: i32, msg_len: i32| {
-            caller.data_mut().consume_gas(100).ok();
-            println!("📝 WASM Log: ptr={}, len={}", msg_ptr, msg_len);
-        })?;
+        linker.func_wrap(
+            "env",
+            "log",
+            move |mut caller: Caller<'_, ExecutionContext<H>>, msg_ptr: i32, msg_len: i32| -> Result<()> {
+                let gas_before = charge_gas(&mut caller, log_cost)?;
+                let msg = read_memory(&mut caller, msg_ptr, msg_len)?;
+                charge_gas(&mut caller, per_byte(log_byte_cost, msg.len()))?;
+                let msg = String::from_utf8_lossy(&msg).into_owned();
+                
+                let ctx = caller.data_mut();
+                ctx.trace(gas_before, || host_call("log", json!({ "message": msg }), Value::Null));
+                ctx.logs.push(msg);
+                Ok(())
+            },
+        )?;
         
         // Add host function: emit event
         linker.func_wrap(
             "env",
             "emit_event",
-            |mut caller: Caller<'_, ExecutionContext>, 
+            move |mut caller: Caller<'_, ExecutionContext<H>>, 
              topic_ptr: i32, 
              topic_len: i32,
              data_ptr: i32,
-             data_len: i32| -> i32 {
+             data_len: i32| -> Result<i32> {
                 // Consume gas for event emission
-                if caller.data_mut().consume_gas(1000).is_err() {
-                    return -1; // Out of gas
-                }
+                let gas_before = charge_gas(&mut caller, event_cost)?;
                 
-                // Read memory (simplified - in production would read from WASM memory)
-                let topics = vec![format!("topic_{}_{}", topic_ptr, topic_len)];
-                let data = vec![0u8; data_len as usize];
+                let topic = read_memory(&mut caller, topic_ptr, topic_len)?;
+                let data = read_memory(&mut caller, data_ptr, data_len)?;
+                charge_gas(&mut caller, per_byte(event_byte_cost, topic.len() + data.len()))?;
                 
-                caller.data_mut().emit_event(topics, data);
-                println!("📢 Event emitted: topic_ptr={}, data_len={}", topic_ptr, data_len);
+                // Topics are normally short names such as "Transfer"; binary ones are hex-encoded
+                let topic = String::from_utf8(topic).unwrap_or_else(|e| format!("0x{}", hex::encode(e.into_bytes())));
+                let ctx = caller.data_mut();
+                ctx.trace(gas_before, || TraceOp::Event { topics: vec![topic.clone()], data: hex::encode(&data) });
+                ctx.emit_event(vec![topic], data);
                 
-                0 // Success
+                Ok(0) // Success
             },
         )?;
         
-        // Add host function: storage write
+        // Add host function: storage write. Empty values are stored; `storage_delete` removes keys.
         linker.func_wrap(
             "env",
             "storage_write",
-            |mut caller: Caller<'_, ExecutionContext>, key_ptr: i32, key_len: i32, val_ptr: i32, val_len: i32| -> i32 {
-                if caller.data_mut().consume_gas(5000).is_err() {
-                    return -1;
-                }
-                println!("💾 Storage write: key_ptr={}, val_ptr={}", key_ptr, val_ptr);
-                0
+            move |mut caller: Caller<'_, ExecutionContext<H>>, key_ptr: i32, key_len: i32, val_ptr: i32, val_len: i32| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, storage_write_cost)?;
+                let key = read_memory(&mut caller, key_ptr, key_len)?;
+                let value = read_memory(&mut caller, val_ptr, val_len)?;
+                charge_gas(&mut caller, per_byte(storage_byte_cost, key.len() + value.len()))?;
+                
+                let ctx = caller.data_mut();
+                let contract = ctx.contract_address.clone();
+                ctx.host_mut().storage_write(&contract, &key, &value)?;
+                ctx.trace(gas_before, || TraceOp::StorageWrite { key: hex::encode(&key), value: Some(hex::encode(&value)) });
+                Ok(0)
+            },
+        )?;
+        
+        // Add host function: remove a key from storage
+        linker.func_wrap(
+            "env",
+            "storage_delete",
+            move |mut caller: Caller<'_, ExecutionContext<H>>, key_ptr: i32, key_len: i32| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, storage_write_cost)?;
+                let key = read_memory(&mut caller, key_ptr, key_len)?;
+                charge_gas(&mut caller, per_byte(storage_byte_cost, key.len()))?;
+                
+                let ctx = caller.data_mut();
+                let contract = ctx.contract_address.clone();
+                ctx.host_mut().storage_delete(&contract, &key)?;
+                ctx.trace(gas_before, || TraceOp::StorageWrite { key: hex::encode(&key), value: None });
+                Ok(0)
             },
         )?;
         
-        // Add host function: storage read
+        // Add host function: storage read into a buffer of `val_cap` bytes.
+        // Returns the value's length, or -1 if the key is unset (so an empty value reads as 0).
         linker.func_wrap(
             "env",
             "storage_read",
-            |mut caller: Caller<'_, ExecutionContext>, key_ptr: i32, key_len: i32, val_ptr: i32| -> i32 {
-                if caller.data_mut().consume_gas(2000).is_err() {
-                    return -1;
+            move |mut caller: Caller<'_, ExecutionContext<H>>, key_ptr: i32, key_len: i32, val_ptr: i32, val_cap: i32| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, storage_read_cost)?;
+                let key = read_memory(&mut caller, key_ptr, key_len)?;
+                
+                let ctx = caller.data();
+                let stored = ctx.host().storage_read(&ctx.contract_address, &key)?;
+                caller.data_mut().trace(gas_before, || TraceOp::StorageRead {
+                    key: hex::encode(&key),
+                    value: stored.as_ref().map(hex::encode),
+                });
+                
+                match stored {
+                    Some(value) => write_output(&mut caller, val_ptr, val_cap, &value),
+                    None => Ok(-1),
                 }
-                println!("📖 Storage read: key_ptr={}", key_ptr);
-                0 // Return bytes written
             },
         )?;
         
-        // Add host function: call another contract
+        // Add host function: call another contract.
+        // Returns the callee's return data length on success or a negative `call_status` code;
+        // a failed callee has its state changes reverted.
+        let vm = self.clone();
         linker.func_wrap(
             "env",
             "call_contract",
-            |mut caller: Caller<'_, ExecutionContext>,
+            move |mut caller: Caller<'_, ExecutionContext<H>>,
              contract_addr_ptr: i32,
              contract_addr_len: i32,
              method_ptr: i32,
              method_len: i32,
              args_ptr: i32,
              args_len: i32,
-             gas: i64| -> i32 {
-                // Check call depth
-                if !caller.data().can_call_contract() {
-                    println!("❌ Max call depth exceeded");
-                    return -1;
-                }
+             value: i64,
+             gas: i64| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, call_cost)?;
                 
-                // Consume gas for the call
-                let gas_for_call = if gas > 0 { gas as u64 } else { 100_000 };
-                if caller.data_mut().consume_gas(gas_for_call).is_err() {
-                    return -2; // Out of gas
+                let contract = read_string(&mut caller, contract_addr_ptr, contract_addr_len)?;
+                let method = read_string(&mut caller, method_ptr, method_len)?;
+                let args = read_memory(&mut caller, args_ptr, args_len)?;
+                let value = value.max(0) as ActAmount;
+                
+                let ctx = caller.data_mut();
+                let depth = ctx.call_depth + 1;
+                if !ctx.can_call_contract() {
+                    let status = call_status::DEPTH_EXCEEDED;
+                    ctx.trace(gas_before, || TraceOp::Call { contract, method, depth, status, call: None });
+                    return Ok(status);
                 }
                 
-                // Increment call depth
-                caller.data_mut().call_depth += 1;
+                // Forward the requested gas, or everything left if none was requested
+                let available = ctx.gas_limit - ctx.gas_used;
+                let gas_for_call = if gas > 0 { (gas as u64).min(available) } else { available };
+                
+                let mut host = ctx.host.take().expect("host state present in caller");
+                let checkpoint = host.checkpoint();
+                let mut call_trace = None;
                 
-                println!("🔗 Contract call: addr_ptr={}, method_ptr={}, args_len={}, gas={}",
-                    contract_addr_ptr, method_ptr, args_len, gas_for_call);
+                let status = if value > 0 && host.transfer(&ctx.contract_address, &contract, value).is_err() {
+                    host.revert(checkpoint);
+                    call_status::INSUFFICIENT_BALANCE
+                } else {
+                    match host.get_code(&contract) {
+                        Ok(Some(code)) => {
+                            let params = CallParams {
+                                contract_address: contract.clone(),
+                                caller: ctx.contract_address.clone(),
+                                method: method.clone(),
+                                input: args,
+                                value,
+                                gas_limit: gas_for_call,
+                                transaction_hash: ctx.transaction_hash.clone(),
+                                block_height: ctx.block_height,
+                                trace: ctx.trace.is_some(),
+                            };
+                            
+                            let (mut result, returned) = vm.run(&params, &code, host, depth);
+                            host = returned;
+                            call_trace = result.trace.take();
+                            
+                            ctx.last_call_return = result.return_data;
+                            ctx.logs.extend(result.logs);
+                            // Child gas is charged below, after the host is back in place
+                            ctx.gas_used += result.gas_used;
+                            
+                            if result.success {
+                                for mut event in result.events {
+                                    event.log_index = ctx.events.len() as u32;
+                                    ctx.events.push(event);
+                                }
+                                ctx.last_call_return.len() as i32
+                            } else {
+                                host.revert(checkpoint);
+                                call_status::CALLEE_FAILED
+                            }
+                        }
+                        _ => {
+                            host.revert(checkpoint);
+                            call_status::NO_CONTRACT
+                        }
+                    }
+                };
                 
-                // Simulate successful call (in production would load and execute target contract)
-                caller.data_mut().call_depth -= 1;
+                ctx.host = Some(host);
+                ctx.trace(gas_before, || TraceOp::Call { contract, method, depth, status, call: call_trace });
                 
-                0 // Success
+                // The child's gas came out of this frame's budget
+                let fuel = ctx.refuel();
+                caller.set_fuel(fuel)?;
+                Ok(status)
+            },
+        )?;
+        
+        // Add host function: copy the latest nested call's return data into a buffer of `out_len` bytes
+        linker.func_wrap(
+            "env",
+            "get_return_data",
+            move |mut caller: Caller<'_, ExecutionContext<H>>, out_ptr: i32, out_len: i32| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let data = caller.data().last_call_return.clone();
+                let len = write_output(&mut caller, out_ptr, out_len, &data)?;
+                caller.data_mut().trace(gas_before, || host_call("get_return_data", Value::Null, json!(hex::encode(&data))));
+                Ok(len)
+            },
+        )?;
+        
+        // Add host function: set data returned to this contract's caller
+        linker.func_wrap(
+            "env",
+            "set_return_data",
+            move |mut caller: Caller<'_, ExecutionContext<H>>, data_ptr: i32, data_len: i32| -> Result<()> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let data = read_memory(&mut caller, data_ptr, data_len)?;
+                let ctx = caller.data_mut();
+                ctx.trace(gas_before, || host_call("set_return_data", json!({ "data": hex::encode(&data) }), Value::Null));
+                ctx.return_data = data;
+                Ok(())
             },
         )?;
         
-        // Add host function: get caller address
+        // Add host function: size of the call input
+        linker.func_wrap(
+            "env",
+            "input_size",
+            move |mut caller: Caller<'_, ExecutionContext<H>>| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let ctx = caller.data_mut();
+                let size = ctx.input.len() as i32;
+                ctx.trace(gas_before, || host_call("input_size", Value::Null, json!(size)));
+                Ok(size)
+            },
+        )?;
+        
+        // Add host function: copy the call input into a buffer of `out_len` bytes
+        linker.func_wrap(
+            "env",
+            "get_input",
+            move |mut caller: Caller<'_, ExecutionContext<H>>, out_ptr: i32, out_len: i32| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let input = caller.data().input.clone();
+                let len = write_output(&mut caller, out_ptr, out_len, &input)?;
+                caller.data_mut().trace(gas_before, || host_call("get_input", Value::Null, json!(hex::encode(&input))));
+                Ok(len)
+            },
+        )?;
+        
+        // Add host function: value sent with this call
+        linker.func_wrap(
+            "env",
+            "get_value",
+            move |mut caller: Caller<'_, ExecutionContext<H>>| -> Result<i64> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let ctx = caller.data_mut();
+                let value = i64::try_from(ctx.value).unwrap_or(i64::MAX);
+                ctx.trace(gas_before, || host_call("get_value", Value::Null, json!(value)));
+                Ok(value)
+            },
+        )?;
+        
+        // Add host function: copy the caller's address into a buffer of `out_len` bytes
         linker.func_wrap(
             "env",
             "get_caller",
-            |mut caller: Caller<'_, ExecutionContext>, out_ptr: i32| -> i32 {
-                if caller.data_mut().consume_gas(100).is_err() {
-                    return -1;
-                }
-                println!("👤 Get caller: out_ptr={}", out_ptr);
-                0 // Return address length
+            move |mut caller: Caller<'_, ExecutionContext<H>>, out_ptr: i32, out_len: i32| -> Result<i32> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let address = caller.data().caller.clone();
+                let len = write_output(&mut caller, out_ptr, out_len, address.as_bytes())?;
+                caller.data_mut().trace(gas_before, || host_call("get_caller", Value::Null, json!(address)));
+                Ok(len)
             },
         )?;
         
-        // Add host function: get contract balance
+        // Add host function: get balance of an address (empty address = this contract)
         linker.func_wrap(
             "env",
             "get_balance",
-            |mut caller: Caller<'_, ExecutionContext>, addr_ptr: i32, addr_len: i32| -> i64 {
-                if caller.data_mut().consume_gas(100).is_err() {
-                    return -1;
-                }
-                println!("💰 Get balance: addr_ptr={}", addr_ptr);
-                1000000000000000000 // Return 1 ACT as example
+            move |mut caller: Caller<'_, ExecutionContext<H>>, addr_ptr: i32, addr_len: i32| -> Result<i64> {
+                let gas_before = charge_gas(&mut caller, env_query_cost)?;
+                let address = if addr_len == 0 {
+                    caller.data().contract_address.clone()
+                } else {
+                    read_string(&mut caller, addr_ptr, addr_len)?
+                };
+                
+                // Balances above i64::MAX are reported as i64::MAX
+                let balance = caller.data().host().balance(&address)?;
+                caller.data_mut().trace(gas_before, || {
+                    host_call("get_balance", json!({ "address": address }), json!(balance.to_string()))
+                });
+                Ok(i64::try_from(balance).unwrap_or(i64::MAX))
             },
         )?;
         
-        // Instantiate the module
-        let instance = linker.instantiate(&mut store, &module)?;
+        Ok(linker)
+    }
+}
+
+fn failed(params: &CallParams, gas_used: u64, reason: String) -> ExecutionResult {
+    ExecutionResult {
+        success: false,
+        gas_used: gas_used.min(params.gas_limit),
+        state_changes: HashMap::new(),
+        logs: vec![reason],
+        events: Vec::new(),
+        return_data: Vec::new(),
+        trace: None,
+    }
+}
+
+fn host_call(function: &str, args: Value, result: Value) -> TraceOp {
+    TraceOp::HostCall { function: function.to_string(), args, result }
+}
+
+/// Attach a call trace to `result` when the call was traced
+fn with_trace(mut result: ExecutionResult, params: &CallParams, steps: Option<Vec<TraceStep>>) -> ExecutionResult {
+    if params.trace {
+        result.trace = Some(CallTrace::new(params, &result, steps.unwrap_or_default()));
+    }
+    result
+}
+
+impl WasmRuntime {
+    pub fn new() -> Result<Self> {
+        Self::with_gas_config(GasConfig::default())
+    }
+
+    pub fn with_gas_config(gas_config: GasConfig) -> Result<Self> {
+        let mut config = Config::new();
+        config.wasm_threads(false);
+        config.wasm_reference_types(true);
+        config.wasm_bulk_memory(true);
         
-        // Get the main execution function
-        let result_code = if let Ok(execute_fn) = instance.get_typed_func::<(), i32>(&mut store, "execute") {
-            execute_fn.call(&mut store, ())?
-        } else if let Ok(execute_fn) = instance.get_typed_func::<(i32, i32), i32>(&mut store, "execute") {
-            execute_fn.call(&mut store, (0, 0))?
-        } else {
-            return Err(anyhow!("No compatible execute function found in WASM module"));
-        };
+        // Deterministic execution: every node must burn the same fuel and compute the same results
+        config.consume_fuel(true);
+        config.wasm_relaxed_simd(false);
+        config.cranelift_nan_canonicalization(true);
         
-        // Extract context data
-        let ctx = store.into_data();
+        let engine = Engine::new(&config)?;
         
-        // Build execution result
-        let execution_result = ExecutionResult {
-            success: result_code == 0,
-            gas_used: ctx.gas_used,
-            state_changes: HashMap::new(),
-            logs: vec![format!("Executed action from actor: {}", action.actor)],
-            events: ctx.events,
-            return_data: Vec::new(),
+        Ok(Self {
+            vm: Vm {
+                engine,
+                gas_config: Arc::new(gas_config),
+                modules: Arc::new(Mutex::new(ModuleCache::new(DEFAULT_MODULE_CACHE_SIZE))),
+                limits: ContractLimits::default(),
+            },
+        })
+    }
+
+    /// Persist compiled modules under `dir` so they survive restarts
+    pub fn with_cache_dir(self, dir: impl Into<PathBuf>) -> Result<Self> {
+        self.vm.modules.lock().unwrap().set_cache_dir(dir)?;
+        Ok(self)
+    }
+
+    pub fn gas_config(&self) -> &GasConfig {
+        &self.vm.gas_config
+    }
+
+    /// Check contract code against this runtime's limits before it is deployed
+    pub fn validate_contract(&self, code: &[u8]) -> Result<()> {
+        validate_contract(code, &self.vm.limits)
+    }
+
+    /// Number of compiled modules held in memory
+    pub fn cached_modules(&self) -> usize {
+        self.vm.modules.lock().unwrap().len()
+    }
+
+    pub fn execute_action(&mut self, action: &Action, wasm_bytes: &[u8]) -> Result<ExecutionResult> {
+        let params = CallParams {
+            contract_address: action.actor.clone(),
+            caller: action.actor.clone(),
+            method: "execute".to_string(),
+            input: action.payload.clone(),
+            value: 0,
+            gas_limit: 1_000_000,
+            transaction_hash: "tx_hash_placeholder".to_string(),
+            block_height: 0,
+            trace: false,
         };
         
-        Ok(execution_result)
+        let (mut result, _) = self.execute_call(&params, wasm_bytes, MemoryState::default());
+        result.logs.insert(0, format!("Executed action from actor: {}", action.actor));
+        Ok(result)
+    }
+
+    /// Invoke `params.method` on a contract module against `host`.
+    ///
+    /// The host is handed back together with the result. Failures never lose it:
+    /// a module that cannot be compiled or instantiated consumes all gas, while
+    /// traps and out-of-gas keep the gas consumed so far. State written by a
+    /// failed call is left in the host for the caller to revert.
+    pub fn execute_call<H: HostState>(&mut self, params: &CallParams, wasm_bytes: &[u8], host: H) -> (ExecutionResult, H) {
+        self.vm.run(params, wasm_bytes, host, 0)
     }
 
     pub fn execute_action_with_state(
@@ -279,6 +855,7 @@ impl WasmRuntime {
             ],
             events: Vec::new(),
             return_data: Vec::new(),
+            trace: None,
         };
         
         Ok(execution_result)
@@ -295,12 +872,445 @@ pub fn execute_action_block(action: &Action) -> Result<ExecutionResult> {
 mod tests {
     use super::*;
 
+    /// Parameters for calling `execute` on `contract`
+    fn call_params(contract: &str, gas_limit: u64) -> CallParams {
+        CallParams {
+            contract_address: contract.to_string(),
+            caller: "ACT-caller".to_string(),
+            method: "execute".to_string(),
+            input: Vec::new(),
+            value: 0,
+            gas_limit,
+            transaction_hash: "tx".to_string(),
+            block_height: 1,
+            trace: false,
+        }
+    }
+
     #[test]
     fn test_wasm_runtime_creation() {
         let runtime = WasmRuntime::new();
         assert!(runtime.is_ok());
     }
 
+    #[test]
+    fn test_fuel_accounting() {
+        let params = call_params("ACT-CONTRACT-test", 100);
+        let mut ctx = ExecutionContext::new(MemoryState::default(), &params, 2, 0);
+        
+        // 100 gas buys 50 instructions at 2 gas each
+        assert_eq!(ctx.refuel(), 50);
+        ctx.sync_fuel(40);
+        assert_eq!(ctx.gas_used, 20);
+        
+        ctx.consume_gas(30).unwrap();
+        assert_eq!(ctx.refuel(), 25);
+        
+        let err = ctx.consume_gas(51).unwrap_err();
+        assert!(is_out_of_gas(&err));
+        assert_eq!(ctx.gas_used, 100);
+    }
+
+    #[test]
+    fn test_module_cache() {
+        // Empty modules made distinct by a one-byte custom section name
+        let module = |name: u8| vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, name];
+        let (a, b, c) = (module(b'a'), module(b'b'), module(b'c'));
+        let engine = Engine::new(&Config::new()).unwrap();
+        
+        let mut cache = ModuleCache::new(2);
+        cache.get_or_compile(&engine, &a).unwrap();
+        cache.get_or_compile(&engine, &b).unwrap();
+        cache.get_or_compile(&engine, &a).unwrap();
+        cache.get_or_compile(&engine, &c).unwrap();
+        
+        // `b` was least recently used
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(&code_hash(&a)));
+        assert!(!cache.contains(&code_hash(&b)));
+        assert!(cache.contains(&code_hash(&c)));
+        
+        // Compiled artifacts are written to and reloaded from the cache directory
+        let dir = std::env::temp_dir().join(format!("act_module_cache_test_{}", std::process::id()));
+        let mut cache = ModuleCache::new(2);
+        cache.set_cache_dir(&dir).unwrap();
+        cache.get_or_compile(&engine, &a).unwrap();
+        assert!(dir.join(format!("{}.cwasm", code_hash(&a))).exists());
+        
+        let mut restarted = ModuleCache::new(2);
+        restarted.set_cache_dir(&dir).unwrap();
+        assert!(restarted.get_or_compile(&engine, &a).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Encode a module from (section id, contents) pairs
+    fn wasm_module(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        for (id, contents) in sections {
+            wasm.push(*id);
+            wasm.push(contents.len() as u8);
+            wasm.extend_from_slice(contents);
+        }
+        wasm
+    }
+
+    #[test]
+    fn test_contract_validation() {
+        let limits = ContractLimits::default();
+        let func_type = |result: u8| (1, vec![0x01, 0x60, 0x00, 0x01, result]);
+        let functions = (3, vec![0x01, 0x00]);
+        let export = |name: &[u8]| {
+            let mut section = vec![0x01, name.len() as u8];
+            section.extend_from_slice(name);
+            section.extend_from_slice(&[0x00, 0x00]);
+            (7, section)
+        };
+        let i32_body = (10, vec![0x01, 0x04, 0x00, 0x41, 0x00, 0x0b]);
+        
+        let valid = wasm_module(&[func_type(0x7f), functions.clone(), export(b"execute"), i32_body.clone()]);
+        assert!(validate_contract(&valid, &limits).is_ok());
+        
+        // Code size
+        let small = ContractLimits { max_code_size: 16, ..ContractLimits::default() };
+        assert!(validate_contract(&valid, &small).is_err());
+        
+        // Malformed bytes
+        assert!(validate_contract(b"not wasm", &limits).is_err());
+        
+        // Floats
+        let floats = wasm_module(&[
+            func_type(0x7d),
+            functions.clone(),
+            export(b"execute"),
+            (10, vec![0x01, 0x07, 0x00, 0x43, 0x00, 0x00, 0x00, 0x00, 0x0b]),
+        ]);
+        assert!(validate_contract(&floats, &limits).is_err());
+        
+        // Imports outside the host ABI
+        let mut wasi = vec![0x01, 0x04];
+        wasi.extend_from_slice(b"wasi");
+        wasi.push(0x08);
+        wasi.extend_from_slice(b"fd_write");
+        wasi.extend_from_slice(&[0x00, 0x00]);
+        let import = wasm_module(&[func_type(0x7f), (2, wasi), functions.clone(), export(b"execute"), i32_body.clone()]);
+        assert!(validate_contract(&import, &limits).is_err());
+        
+        // Memory of 512 pages
+        let memory = wasm_module(&[
+            func_type(0x7f),
+            functions.clone(),
+            (5, vec![0x01, 0x00, 0x80, 0x04]),
+            export(b"execute"),
+            i32_body.clone(),
+        ]);
+        assert!(validate_contract(&memory, &limits).is_err());
+        
+        // Missing `execute`
+        let no_execute = wasm_module(&[func_type(0x7f), functions, export(b"run"), i32_body]);
+        assert!(validate_contract(&no_execute, &limits).is_err());
+    }
+
+    #[test]
+    fn test_nested_call_revert() {
+        let mut host = MemoryState::default();
+        host.balances.insert("ACT-CONTRACT-a".to_string(), 50);
+        host.storage_write("ACT-CONTRACT-b", b"key", b"before").unwrap();
+        
+        // A failed sub-call rolls back its value transfer and storage writes
+        let checkpoint = host.checkpoint();
+        host.transfer("ACT-CONTRACT-a", "ACT-CONTRACT-b", 20).unwrap();
+        host.storage_write("ACT-CONTRACT-b", b"key", b"after").unwrap();
+        host.revert(checkpoint);
+        
+        assert_eq!(host.balance("ACT-CONTRACT-a").unwrap(), 50);
+        assert_eq!(host.balance("ACT-CONTRACT-b").unwrap(), 0);
+        assert_eq!(host.storage_read("ACT-CONTRACT-b", b"key").unwrap(), Some(b"before".to_vec()));
+        assert!(host.transfer("ACT-CONTRACT-a", "ACT-CONTRACT-b", 51).is_err());
+        
+        let params = call_params("ACT-CONTRACT-a", 100);
+        assert!(ExecutionContext::new(MemoryState::default(), &params, 1, MAX_CALL_DEPTH - 1).can_call_contract());
+        assert!(!ExecutionContext::new(MemoryState::default(), &params, 1, MAX_CALL_DEPTH).can_call_contract());
+    }
+
+    #[test]
+    fn test_nested_contract_calls() {
+        let callee = wat::parse_str(
+            r#"(module
+                (import "env" "get_value" (func $get_value (result i64)))
+                (import "env" "set_return_data" (func $set_return_data (param i32 i32)))
+                (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 8) "pong")
+                (data (i32.const 16) "seen")
+                (data (i32.const 24) "yes")
+                (data (i32.const 32) "bad")
+                (func (export "echo") (result i32)
+                    (drop (call $storage_write (i32.const 16) (i32.const 4) (i32.const 24) (i32.const 3)))
+                    (i64.store (i32.const 0) (call $get_value))
+                    (call $set_return_data (i32.const 0) (i32.const 12))
+                    (i32.const 0))
+                (func (export "fail") (result i32)
+                    (drop (call $storage_write (i32.const 16) (i32.const 4) (i32.const 32) (i32.const 3)))
+                    (unreachable))
+                (func (export "spin") (result i32)
+                    (loop $forever (br $forever))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+        // Returns the three call statuses followed by the return data of `echo`
+        let caller = |spin_gas: u64| {
+            wat::parse_str(format!(
+                r#"(module
+                    (import "env" "call_contract" (func $call (param i32 i32 i32 i32 i32 i32 i64 i64) (result i32)))
+                    (import "env" "get_return_data" (func $get_return_data (param i32 i32) (result i32)))
+                    (import "env" "set_return_data" (func $set_return_data (param i32 i32)))
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "ACT-CONTRACT-b")
+                    (data (i32.const 16) "echo")
+                    (data (i32.const 24) "fail")
+                    (data (i32.const 32) "spin")
+                    (func (export "execute") (result i32)
+                        (i32.store (i32.const 200) (call $call (i32.const 0) (i32.const 14) (i32.const 16) (i32.const 4) (i32.const 0) (i32.const 0) (i64.const 7) (i64.const 0)))
+                        (drop (call $get_return_data (i32.const 212) (i32.const 12)))
+                        (i32.store (i32.const 204) (call $call (i32.const 0) (i32.const 14) (i32.const 24) (i32.const 4) (i32.const 0) (i32.const 0) (i64.const 5) (i64.const 0)))
+                        (i32.store (i32.const 208) (call $call (i32.const 0) (i32.const 14) (i32.const 32) (i32.const 4) (i32.const 0) (i32.const 0) (i64.const 0) (i64.const {spin_gas})))
+                        (call $set_return_data (i32.const 200) (i32.const 24))
+                        (i32.const 0)))"#
+            ))
+            .unwrap()
+        };
+        let run = |spin_gas: u64| {
+            let mut host = MemoryState::default();
+            host.balances.insert("ACT-CONTRACT-a".to_string(), 100);
+            host.code.insert("ACT-CONTRACT-b".to_string(), callee.clone());
+            let mut runtime = WasmRuntime::new().unwrap();
+            runtime.execute_call(&call_params("ACT-CONTRACT-a", 1_000_000), &caller(spin_gas), host)
+        };
+        
+        let (result, state) = run(10_000);
+        assert!(result.success);
+        
+        // The callee's return data reaches the caller, along with the value sent
+        let statuses: Vec<i32> = result.return_data[..12]
+            .chunks(4)
+            .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(statuses, vec![12, call_status::CALLEE_FAILED, call_status::CALLEE_FAILED]);
+        assert_eq!(&result.return_data[12..20], &7u64.to_le_bytes());
+        assert_eq!(&result.return_data[20..], b"pong");
+        
+        // A trapping callee's value transfer and storage writes are undone
+        assert_eq!(state.balance("ACT-CONTRACT-a").unwrap(), 93);
+        assert_eq!(state.balance("ACT-CONTRACT-b").unwrap(), 7);
+        assert_eq!(state.storage_read("ACT-CONTRACT-b", b"seen").unwrap(), Some(b"yes".to_vec()));
+        
+        // The callee that loops forever burns exactly the gas forwarded to it
+        let (more_gas, _) = run(20_000);
+        assert!(more_gas.success);
+        assert_eq!(more_gas.gas_used - result.gas_used, 10_000);
+        
+        // Each call is recorded in the trace, whether or not the callee ran
+        let mut host = MemoryState::default();
+        host.balances.insert("ACT-CONTRACT-a".to_string(), 100);
+        let mut params = call_params("ACT-CONTRACT-a", 1_000_000);
+        params.trace = true;
+        let (traced, _) = WasmRuntime::new().unwrap().execute_call(&params, &caller(10_000), host);
+        let calls: Vec<_> = traced.trace.unwrap().steps
+            .into_iter()
+            .filter_map(|step| match step.op {
+                TraceOp::Call { contract, method, depth, status, call } => Some((contract, method, depth, status, call.is_some())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0], ("ACT-CONTRACT-b".to_string(), "echo".to_string(), 1, call_status::NO_CONTRACT, false));
+        assert_eq!(calls[2].1, "spin");
+    }
+
+    #[test]
+    fn test_infinite_loop_runs_out_of_gas() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "execute") (result i32)
+                    (loop $forever (br $forever))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+        
+        let mut runtime = WasmRuntime::new().unwrap();
+        let (result, _) = runtime.execute_call(&call_params("ACT-CONTRACT-loop", 50_000), &wasm, MemoryState::default());
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 50_000);
+    }
+
+    #[test]
+    fn test_deterministic_gas() {
+        // Loops, then logs, emits and stores `len` bytes
+        let contract = |len: usize| {
+            wat::parse_str(format!(
+                r#"(module
+                    (import "env" "log" (func $log (param i32 i32)))
+                    (import "env" "emit_event" (func $emit_event (param i32 i32 i32 i32) (result i32)))
+                    (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32) (result i32)))
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "key")
+                    (func (export "execute") (result i32)
+                        (local $i i32)
+                        (loop $again
+                            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                            (br_if $again (i32.lt_u (local.get $i) (i32.const 100))))
+                        (call $log (i32.const 16) (i32.const {len}))
+                        (drop (call $emit_event (i32.const 0) (i32.const 3) (i32.const 16) (i32.const {len})))
+                        (drop (call $storage_write (i32.const 0) (i32.const 3) (i32.const 16) (i32.const {len})))
+                        (i32.const 0)))"#
+            ))
+            .unwrap()
+        };
+        let run = |wasm: &[u8]| {
+            let mut runtime = WasmRuntime::new().unwrap();
+            runtime.execute_call(&call_params("ACT-CONTRACT-gas", 1_000_000), wasm, MemoryState::default())
+        };
+        
+        // The same input always costs the same gas and leaves the same state
+        let short = contract(10);
+        let (first, first_state) = run(&short);
+        let (second, second_state) = run(&short);
+        assert!(first.success);
+        assert_eq!(first.gas_used, second.gas_used);
+        assert_eq!(first_state.storage, second_state.storage);
+        
+        // Each extra byte is charged once per host function it is passed to
+        let (long, _) = run(&contract(110));
+        let gas = GasConfig::default();
+        let per_byte = gas.log_byte_cost + gas.event_byte_cost + gas.storage_byte_cost;
+        assert_eq!(long.gas_used - first.gas_used, 100 * per_byte);
+    }
+
+    #[test]
+    fn test_output_buffers() {
+        // Returns the lengths reported by each read followed by the buffer passed too small,
+        // and stores what was copied into the buffers that fit
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "storage_read" (func $storage_read (param i32 i32 i32 i32) (result i32)))
+                (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32) (result i32)))
+                (import "env" "get_caller" (func $get_caller (param i32 i32) (result i32)))
+                (import "env" "get_input" (func $get_input (param i32 i32) (result i32)))
+                (import "env" "set_return_data" (func $set_return_data (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "key")
+                (data (i32.const 8) "value")
+                (data (i32.const 16) "copy")
+                (func (export "execute") (result i32)
+                    (drop (call $storage_write (i32.const 0) (i32.const 3) (i32.const 8) (i32.const 5)))
+                    (i32.store (i32.const 32) (call $storage_read (i32.const 0) (i32.const 3) (i32.const 64) (i32.const 16)))
+                    (i32.store (i32.const 36) (call $storage_read (i32.const 0) (i32.const 3) (i32.const 48) (i32.const 2)))
+                    (i32.store (i32.const 40) (call $get_caller (i32.const 128) (i32.const 0)))
+                    (i32.store (i32.const 44) (call $get_input (i32.const 256) (i32.const 64)))
+                    (drop (call $get_caller (i32.const 128) (i32.load (i32.const 40))))
+                    (drop (call $storage_write (i32.const 16) (i32.const 4) (i32.const 64) (i32.load (i32.const 32))))
+                    (drop (call $storage_write (i32.const 128) (i32.load (i32.const 40)) (i32.const 256) (i32.load (i32.const 44))))
+                    (call $set_return_data (i32.const 32) (i32.const 18))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+        
+        let mut params = call_params("ACT-CONTRACT-buffers", 1_000_000);
+        params.input = b"input".to_vec();
+        let mut runtime = WasmRuntime::new().unwrap();
+        let (result, state) = runtime.execute_call(&params, &wasm, MemoryState::default());
+        assert!(result.success);
+        
+        let lengths: Vec<u32> = result.return_data[..16]
+            .chunks(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(lengths, vec![5, 5, "ACT-caller".len() as u32, 5]);
+        // A buffer that is too small is left untouched
+        assert_eq!(&result.return_data[16..], &[0, 0]);
+        
+        let stored = |key: &[u8]| state.storage_read("ACT-CONTRACT-buffers", key).unwrap();
+        assert_eq!(stored(b"copy"), Some(b"value".to_vec()));
+        assert_eq!(stored(b"ACT-caller"), Some(b"input".to_vec()));
+    }
+
+    #[test]
+    fn test_empty_storage_values() {
+        // Returns what storage_read reports for "key" after an empty write, then after a delete,
+        // and for "other" after its delete
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "storage_read" (func $storage_read (param i32 i32 i32 i32) (result i32)))
+                (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32) (result i32)))
+                (import "env" "storage_delete" (func $storage_delete (param i32 i32) (result i32)))
+                (import "env" "set_return_data" (func $set_return_data (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "key")
+                (data (i32.const 8) "other")
+                (func (export "execute") (result i32)
+                    (drop (call $storage_write (i32.const 0) (i32.const 3) (i32.const 16) (i32.const 0)))
+                    (i32.store (i32.const 32) (call $storage_read (i32.const 0) (i32.const 3) (i32.const 64) (i32.const 16)))
+                    (drop (call $storage_delete (i32.const 0) (i32.const 3)))
+                    (i32.store (i32.const 36) (call $storage_read (i32.const 0) (i32.const 3) (i32.const 64) (i32.const 16)))
+                    (drop (call $storage_delete (i32.const 8) (i32.const 5)))
+                    (i32.store (i32.const 40) (call $storage_read (i32.const 8) (i32.const 5) (i32.const 64) (i32.const 16)))
+                    (drop (call $storage_write (i32.const 8) (i32.const 5) (i32.const 16) (i32.const 0)))
+                    (call $set_return_data (i32.const 32) (i32.const 12))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+        
+        let mut host = MemoryState::default();
+        host.storage_write("ACT-CONTRACT-empty", b"other", b"value").unwrap();
+        let mut runtime = WasmRuntime::new().unwrap();
+        let (result, state) = runtime.execute_call(&call_params("ACT-CONTRACT-empty", 1_000_000), &wasm, host);
+        assert!(result.success);
+        
+        let lengths: Vec<i32> = result.return_data
+            .chunks(4)
+            .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(lengths, vec![0, -1, -1]);
+        assert_eq!(state.storage_read("ACT-CONTRACT-empty", b"key").unwrap(), None);
+        assert_eq!(state.storage_read("ACT-CONTRACT-empty", b"other").unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_out_of_bounds_memory_traps() {
+        let contract = |body: &str| {
+            wat::parse_str(format!(
+                r#"(module
+                    (import "env" "storage_read" (func $storage_read (param i32 i32 i32 i32) (result i32)))
+                    (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32) (result i32)))
+                    (import "env" "log" (func $log (param i32 i32)))
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "key")
+                    (func (export "execute") (result i32)
+                        {body}
+                        (i32.const 0)))"#
+            ))
+            .unwrap()
+        };
+        let run = |wasm: &[u8]| {
+            let mut host = MemoryState::default();
+            host.storage_write("ACT-CONTRACT-oob", b"key", b"value").unwrap();
+            let mut runtime = WasmRuntime::new().unwrap();
+            runtime.execute_call(&call_params("ACT-CONTRACT-oob", 1_000_000), wasm, host).0
+        };
+        
+        // Reading past the end of memory
+        assert!(!run(&contract("(call $log (i32.const 65530) (i32.const 16))")).success);
+        assert!(!run(&contract("(drop (call $storage_write (i32.const 0) (i32.const 3) (i32.const -1) (i32.const 4)))")).success);
+        
+        // Output buffers past the end of memory, whether or not the value fits
+        assert!(!run(&contract("(drop (call $storage_read (i32.const 0) (i32.const 3) (i32.const 65534) (i32.const 16)))")).success);
+        assert!(!run(&contract("(drop (call $storage_read (i32.const 0) (i32.const 3) (i32.const 65535) (i32.const 2)))")).success);
+        
+        // In bounds
+        assert!(run(&contract("(drop (call $storage_read (i32.const 0) (i32.const 3) (i32.const 65531) (i32.const 5)))")).success);
+    }
+
     #[test]
     fn test_action_execution() {
         let action = Action {
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use wasmtime::{Engine, Module};
+
+/// Default number of compiled modules kept in memory
+pub const DEFAULT_MODULE_CACHE_SIZE: usize = 256;
+
+/// Compiled contract modules keyed by code hash, evicted least recently used first.
+///
+/// With a cache directory set, compiled artifacts are also written to disk so a
+/// restarted node can load them instead of recompiling.
+pub struct ModuleCache {
+    capacity: usize,
+    modules: HashMap<String, (Module, u64)>,  // code hash -> (module, last use)
+    clock: u64,
+    cache_dir: Option<PathBuf>,
+}
+
+/// Hex SHA-256 of contract code, matching `Account.code_hash`
+pub fn code_hash(wasm_bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(wasm_bytes);
+    hex::encode(hasher.finalize())
+}
+
+impl ModuleCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            modules: HashMap::new(),
+            clock: 0,
+            cache_dir: None,
+        }
+    }
+
+    /// Persist compiled artifacts under `dir`
+    pub fn set_cache_dir(&mut self, dir: impl Into<PathBuf>) -> Result<()> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        self.cache_dir = Some(dir);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    pub fn contains(&self, code_hash: &str) -> bool {
+        self.modules.contains_key(code_hash)
+    }
+
+    /// Compiled module for `wasm_bytes`, from memory, disk, or a fresh compile
+    pub fn get_or_compile(&mut self, engine: &Engine, wasm_bytes: &[u8]) -> Result<Module> {
+        let hash = code_hash(wasm_bytes);
+        self.clock += 1;
+
+        if let Some((module, last_used)) = self.modules.get_mut(&hash) {
+            *last_used = self.clock;
+            return Ok(module.clone());
+        }
+
+        let module = match self.load_artifact(engine, &hash) {
+            Some(module) => module,
+            None => {
+                let module = Module::new(engine, wasm_bytes)?;
+                self.store_artifact(&hash, &module);
+                module
+            }
+        };
+
+        self.insert(hash, module.clone());
+        Ok(module)
+    }
+
+    fn insert(&mut self, hash: String, module: Module) {
+        if self.modules.len() >= self.capacity {
+            let oldest = self
+                .modules
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(hash, _)| hash.clone());
+            if let Some(oldest) = oldest {
+                self.modules.remove(&oldest);
+            }
+        }
+        self.modules.insert(hash, (module, self.clock));
+    }
+
+    fn artifact_path(&self, hash: &str) -> Option<PathBuf> {
+        self.cache_dir.as_ref().map(|dir| dir.join(format!("{}.cwasm", hash)))
+    }
+
+    /// Load a previously compiled artifact. Artifacts built by a different wasmtime
+    /// version or engine config are rejected by wasmtime and recompiled.
+    fn load_artifact(&self, engine: &Engine, hash: &str) -> Option<Module> {
+        let path = self.artifact_path(hash)?;
+        if !path.exists() {
+            return None;
+        }
+
+        // SAFETY: artifacts in the cache directory are only written by `store_artifact`,
+        // from modules this node compiled itself, and are renamed into place once complete.
+        match unsafe { Module::deserialize_file(engine, &path) } {
+            Ok(module) => Some(module),
+            Err(e) => {
+                println!("⚠️  Discarding cached module {}: {}", hash, e);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Write a compiled artifact to disk; failures only cost a recompile later
+    fn store_artifact(&self, hash: &str, module: &Module) {
+        let Some(path) = self.artifact_path(hash) else {
+            return;
+        };
+
+        let tmp_path = path.with_extension("cwasm.tmp");
+        let written = module
+            .serialize()
+            .and_then(|bytes| Ok(fs::write(&tmp_path, bytes)?))
+            .and_then(|_| Ok(fs::rename(&tmp_path, &path)?));
+
+        if let Err(e) = written {
+            println!("⚠️  Failed to persist compiled module {}: {}", hash, e);
+            let _ = fs::remove_file(&tmp_path);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use types::ActAmount;
+
+use crate::{CallParams, ExecutionResult};
+
+/// Structured record of one contract call frame, including nested calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallTrace {
+    pub contract: String,
+    pub caller: String,
+    pub method: String,
+    pub input: String,  // hex
+    pub value: ActAmount,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub return_data: String,  // hex
+    pub steps: Vec<TraceStep>,
+}
+
+impl CallTrace {
+    pub(crate) fn new(params: &CallParams, result: &ExecutionResult, steps: Vec<TraceStep>) -> Self {
+        Self {
+            contract: params.contract_address.clone(),
+            caller: params.caller.clone(),
+            method: params.method.clone(),
+            input: hex::encode(&params.input),
+            value: params.value,
+            gas_limit: params.gas_limit,
+            gas_used: result.gas_used,
+            success: result.success,
+            error: if result.success { None } else { result.logs.last().cloned() },
+            return_data: hex::encode(&result.return_data),
+            steps,
+        }
+    }
+}
+
+/// A host function invocation, with gas used by the frame before and after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub gas_before: u64,
+    pub gas_after: u64,
+    #[serde(flatten)]
+    pub op: TraceOp,
+}
+
+/// What a host function did. Byte strings are hex-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TraceOp {
+    HostCall { function: String, args: Value, result: Value },
+    StorageRead { key: String, value: Option<String> },
+    StorageWrite { key: String, value: Option<String> },  // None when the key was deleted
+    Event { topics: Vec<String>, data: String },
+    Call { contract: String, method: String, depth: u32, status: i32, call: Option<CallTrace> },  // `call` is None when the callee did not run
+}
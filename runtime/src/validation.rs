@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Result};
+use wasmparser::{ExternalKind, Parser, Payload, TypeRef, Validator, WasmFeatures};
+use wasmtime::{StoreLimits, StoreLimitsBuilder};
+
+/// Host functions contracts may import from the `env` module
+pub const HOST_FUNCTIONS: &[&str] = &[
+    "log",
+    "emit_event",
+    "storage_write",
+    "storage_delete",
+    "storage_read",
+    "call_contract",
+    "get_return_data",
+    "set_return_data",
+    "input_size",
+    "get_input",
+    "get_value",
+    "get_caller",
+    "get_balance",
+];
+
+/// Functions every contract must export
+pub const REQUIRED_EXPORTS: &[&str] = &["execute"];
+
+/// Bounds on contract code, checked at deploy time and enforced again while running
+#[derive(Debug, Clone)]
+pub struct ContractLimits {
+    pub max_code_size: usize,     // Bytes of WASM
+    pub max_memory_pages: u64,    // 64 KiB pages per linear memory
+    pub max_table_elements: u32,
+}
+
+impl Default for ContractLimits {
+    fn default() -> Self {
+        Self {
+            max_code_size: 256 * 1024,
+            max_memory_pages: 256,  // 16 MiB
+            max_table_elements: 10_000,
+        }
+    }
+}
+
+impl ContractLimits {
+    /// Store limits that stop a running contract from growing past these bounds
+    pub fn store_limits(&self) -> StoreLimits {
+        StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_pages as usize * 64 * 1024)
+            .table_elements(self.max_table_elements)
+            .instances(1)
+            .build()
+    }
+}
+
+/// Features a contract may use. Floats, threads and SIMD are off so every node
+/// computes bit-identical results.
+fn contract_features() -> WasmFeatures {
+    WasmFeatures {
+        floats: false,
+        saturating_float_to_int: false,
+        threads: false,
+        simd: false,
+        relaxed_simd: false,
+        memory64: false,
+        multi_memory: false,
+        exceptions: false,
+        component_model: false,
+        ..WasmFeatures::default()
+    }
+}
+
+/// Check that `code` is a deployable contract: well-formed, deterministic,
+/// importing only the host ABI and within `limits`
+pub fn validate_contract(code: &[u8], limits: &ContractLimits) -> Result<()> {
+    if code.len() > limits.max_code_size {
+        return Err(anyhow!("Contract code is {} bytes, limit is {}", code.len(), limits.max_code_size));
+    }
+
+    Validator::new_with_features(contract_features())
+        .validate_all(code)
+        .map_err(|e| anyhow!("Invalid contract module: {}", e))?;
+
+    let mut exports = Vec::new();
+
+    for payload in Parser::new(0).parse_all(code) {
+        match payload? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    let import = import?;
+                    let is_host_function = import.module == "env"
+                        && HOST_FUNCTIONS.contains(&import.name)
+                        && matches!(import.ty, TypeRef::Func(_));
+                    if !is_host_function {
+                        return Err(anyhow!("Disallowed import {}::{}", import.module, import.name));
+                    }
+                }
+            }
+            Payload::MemorySection(memories) => {
+                for memory in memories {
+                    let memory = memory?;
+                    // Growth past the limit is also refused at runtime when no maximum is declared
+                    let pages = memory.maximum.unwrap_or(memory.initial);
+                    if pages > limits.max_memory_pages {
+                        return Err(anyhow!(
+                            "Memory of {} pages exceeds limit of {}",
+                            pages,
+                            limits.max_memory_pages
+                        ));
+                    }
+                }
+            }
+            Payload::TableSection(tables) => {
+                for table in tables {
+                    let table = table?.ty;
+                    let elements = table.maximum.unwrap_or(table.initial);
+                    if elements > limits.max_table_elements {
+                        return Err(anyhow!(
+                            "Table of {} elements exceeds limit of {}",
+                            elements,
+                            limits.max_table_elements
+                        ));
+                    }
+                }
+            }
+            Payload::ExportSection(section) => {
+                for export in section {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        exports.push(export.name.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for required in REQUIRED_EXPORTS {
+        if !exports.iter().any(|name| name == required) {
+            return Err(anyhow!("Contract does not export '{}'", required));
+        }
+    }
+
+    Ok(())
+}
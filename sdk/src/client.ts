@@ -178,6 +178,20 @@ export class ActClient {
     return this.call<string>('eth_call', [call, block]);
   }
 
+  /**
+   * Estimate gas for a transaction by dry-running it against pending state
+   */
+  async estimateGas(call: { from?: string; to?: string | null; data?: string; value?: string }): Promise<number> {
+    const request: Record<string, string> = {};
+    if (call.from) request.from = call.from;
+    if (call.to) request.to = call.to;
+    if (call.data) request.data = call.data.startsWith('0x') ? call.data : '0x' + Buffer.from(call.data).toString('hex');
+    if (call.value) request.value = '0x' + BigInt(call.value).toString(16);
+
+    const gas = await this.call<string>('eth_estimateGas', [request]);
+    return parseInt(gas, 16);
+  }
+
   // ===== Staking Methods =====
 
   /**
@@ -18,7 +18,8 @@ export class Contract {
     client: ActClient,
     wallet: Wallet,
     wasmCode: Uint8Array | string,
-    constructorArgs: any[] = []
+    constructorArgs: any[] = [],
+    gasLimit?: number
   ): Promise<Contract> {
     // Get nonce
     const nonce = await client.getNonce(wallet.address);
@@ -31,12 +32,16 @@ export class Contract {
       args: constructorArgs
     });
 
+    // Estimate gas unless a limit was given
+    const gas_limit = gasLimit ?? await client.estimateGas({ from: wallet.address, data });
+
     // Sign and send transaction
     const tx = wallet.signTransaction({
       to: null, // null address means contract deployment
       value: '0',
       data,
-      nonce
+      nonce,
+      gas_limit
     });
 
     const txHash = await client.sendTransaction(tx);
@@ -54,7 +59,7 @@ export class Contract {
   /**
    * Call contract method (state-changing)
    */
-  async call(method: string, args: any[] = [], value: string = '0'): Promise<string> {
+  async call(method: string, args: any[] = [], value: string = '0', gasLimit?: number): Promise<string> {
     if (!this.address) {
       throw new Error('Contract not deployed');
     }
@@ -67,11 +72,20 @@ export class Contract {
       args
     });
 
+    // Estimate gas unless a limit was given
+    const gas_limit = gasLimit ?? await this.client.estimateGas({
+      from: this.wallet.address,
+      to: this.address,
+      data,
+      value
+    });
+
     const tx = this.wallet.signTransaction({
       to: this.address,
       value,
       data,
-      nonce
+      nonce,
+      gas_limit
     });
 
     return this.client.sendTransaction(tx);
@@ -57,13 +57,17 @@ export class Wallet {
     value: string | number;
     data?: string;
     nonce: number;
+    gas_limit?: number;
+    gas_price?: string;
   }): Transaction {
     const transaction = {
       from: this.address,
       to: tx.to,
       value: typeof tx.value === 'number' ? toBaseUnits(tx.value) : tx.value,
       data: tx.data || '',
-      nonce: tx.nonce
+      nonce: tx.nonce,
+      gas_limit: tx.gas_limit,
+      gas_price: tx.gas_price
     };
 
     const hash = hashTransaction(transaction);
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 /// Minimum stake required to become a validator (100,000 ACT)
 pub const MIN_VALIDATOR_STAKE: u64 = 100_000_000_000_000; // 100k with 9 decimals
@@ -10,6 +10,21 @@ pub const MAX_ACTIVE_VALIDATORS: usize = 100;
 /// Unstaking lock period in blocks (~14 days at 2s blocks = 604,800 blocks)
 pub const UNSTAKE_LOCK_PERIOD: u64 = 604_800;
 
+/// Blocks per epoch. Consensus reloads its validator set from staking at each epoch boundary.
+pub const EPOCH_LENGTH: u64 = 100;
+
+/// Number of recent blocks a validator's liveness is judged over (~8 hours at 30s blocks)
+pub const SIGNED_BLOCKS_WINDOW: usize = 1_000;
+
+/// Blocks a validator must have signed out of the window to stay active (50%)
+pub const MIN_SIGNED_PER_WINDOW: usize = 500;
+
+/// Blocks a validator jailed for downtime must wait before it can unjail (~8 hours at 30s blocks)
+pub const DOWNTIME_JAIL_PERIOD: u64 = 1_000;
+
+/// Account holding staked and unbonding tokens until they are claimed back
+pub const STAKING_POOL_ACCOUNT: &str = "ACT-staking-pool";
+
 /// Block reward in smallest units (50 ACT)
 pub const BLOCK_REWARD: u64 = 50_000_000_000; // 50 ACT with 9 decimals
 
@@ -54,6 +69,8 @@ pub struct SlashEvent {
     pub amount: u64,
     pub block_height: u64,
     pub reporter: Option<String>,
+    #[serde(default)]
+    pub evidence_hash: Option<String>,  // Evidence the slash was proven with, if any
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +85,10 @@ pub struct Validator {
     pub total_blocks: u64,
     pub slash_events: Vec<SlashEvent>,
     pub unclaimed_rewards: u64,
+    #[serde(default)]
+    pub signing_window: VecDeque<bool>,  // Whether it signed each recent block it was due to, oldest first
+    #[serde(default)]
+    pub jailed_until: Option<u64>,       // Set while jailed: height from which it may unjail
 }
 
 impl Validator {
@@ -83,6 +104,8 @@ impl Validator {
             total_blocks: 0,
             slash_events: Vec::new(),
             unclaimed_rewards: 0,
+            signing_window: VecDeque::new(),
+            jailed_until: None,
         }
     }
 
@@ -91,7 +114,12 @@ impl Validator {
     }
 
     pub fn can_validate(&self) -> bool {
-        self.active && self.stake >= MIN_VALIDATOR_STAKE
+        self.active && self.jailed_until.is_none() && self.stake >= MIN_VALIDATOR_STAKE
+    }
+
+    /// Blocks missed within the signing window
+    pub fn missed_blocks(&self) -> usize {
+        self.signing_window.iter().filter(|signed| !**signed).count()
     }
 }
 
@@ -113,12 +141,14 @@ pub struct UnstakeRequest {
     pub is_validator: bool,
 }
 
+#[derive(Clone)]
 pub struct StakingManager {
     validators: HashMap<String, Validator>,
     delegators: HashMap<String, Vec<Delegator>>,
     unstake_requests: Vec<UnstakeRequest>,
     current_height: u64,
     total_staked: u64,
+    epoch_validators: BTreeMap<String, u64>,  // Active set when the epoch began, as consensus loaded it: address -> total stake
 }
 
 impl StakingManager {
@@ -129,6 +159,7 @@ impl StakingManager {
             unstake_requests: Vec::new(),
             current_height: 0,
             total_staked: 0,
+            epoch_validators: BTreeMap::new(),
         }
     }
 
@@ -425,6 +456,7 @@ impl StakingManager {
         validator_address: String,
         reason: SlashReason,
         reporter: Option<String>,
+        evidence_hash: Option<String>,
     ) -> Result<u64, String> {
         let validator = self
             .validators
@@ -443,6 +475,7 @@ impl StakingManager {
             amount: slash_amount,
             block_height: self.current_height,
             reporter,
+            evidence_hash,
         });
 
         // Check if validator should be deactivated
@@ -455,6 +488,99 @@ impl StakingManager {
         Ok(slash_amount)
     }
 
+    /// Start block `height`. `parent_signers` are the validators whose precommits finalized
+    /// the parent block, when known. Each of the epoch's validators gets the parent counted
+    /// as signed or missed; once its window is full and it has signed fewer than
+    /// `MIN_SIGNED_PER_WINDOW`, it is slashed for downtime and jailed. On an epoch boundary
+    /// the active set is then taken as the new epoch's, matching what consensus loads before
+    /// the block. Returns the jailed validators with the stake slashed from each.
+    pub fn begin_block(&mut self, height: u64, parent_signers: Option<&HashSet<String>>) -> Vec<(String, u64)> {
+        self.set_block_height(height);
+
+        // Consensus loads the set before the block runs, so take it before anyone is jailed
+        let next_epoch = (height.is_multiple_of(EPOCH_LENGTH) || self.epoch_validators.is_empty())
+            .then(|| self.get_active_validators().into_iter().map(|v| (v.address.clone(), v.total_stake())).collect());
+
+        let mut jailed = Vec::new();
+        if let Some(signers) = parent_signers {
+            for address in self.epoch_validators.keys().cloned().collect::<Vec<_>>() {
+                let Some(validator) = self.validators.get_mut(&address) else { continue };
+                if validator.jailed_until.is_some() {
+                    continue;
+                }
+
+                validator.signing_window.push_back(signers.contains(&address));
+                if validator.signing_window.len() > SIGNED_BLOCKS_WINDOW {
+                    validator.signing_window.pop_front();
+                }
+                let signed = validator.signing_window.len() - validator.missed_blocks();
+                if validator.signing_window.len() < SIGNED_BLOCKS_WINDOW || signed >= MIN_SIGNED_PER_WINDOW {
+                    continue;
+                }
+
+                validator.signing_window.clear();
+                validator.jailed_until = Some(height + DOWNTIME_JAIL_PERIOD);
+                if let Ok(slashed) = self.slash(address.clone(), SlashReason::Downtime, None, None) {
+                    jailed.push((address, slashed));
+                }
+            }
+        }
+
+        if let Some(validators) = next_epoch {
+            self.epoch_validators = validators;
+        }
+        jailed
+    }
+
+    /// Validators of the current epoch, with the stake each had when it began. Consensus
+    /// reloads this set when it restarts mid-epoch.
+    pub fn epoch_validators(&self) -> &BTreeMap<String, u64> {
+        &self.epoch_validators
+    }
+
+    /// Return a validator jailed for downtime to the active set once its jail period is over.
+    /// It is back in consensus from the next epoch.
+    pub fn unjail(&mut self, address: String) -> Result<(), String> {
+        let validator = self
+            .validators
+            .get_mut(&address)
+            .ok_or("Not a validator")?;
+
+        match validator.jailed_until {
+            None => Err("Validator is not jailed".to_string()),
+            Some(until) if self.current_height < until => {
+                Err(format!("Validator is jailed until block {}", until))
+            }
+            Some(_) => {
+                validator.jailed_until = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether a validator has already been slashed on the evidence with this hash
+    pub fn has_slash_evidence(&self, evidence_hash: &str) -> bool {
+        self.validators
+            .values()
+            .flat_map(|v| &v.slash_events)
+            .any(|event| event.evidence_hash.as_deref() == Some(evidence_hash))
+    }
+
+    /// Check that evidence of an offence at `height` can still be punished: it has not been
+    /// already, and it is recent enough that the stake bonded then cannot have been withdrawn
+    pub fn check_evidence(&self, evidence_hash: &str, height: u64) -> Result<(), String> {
+        if self.has_slash_evidence(evidence_hash) {
+            return Err(format!("Evidence {} was already reported", evidence_hash));
+        }
+        if self.current_height.saturating_sub(height) > UNSTAKE_LOCK_PERIOD {
+            return Err(format!(
+                "Evidence from block {} is older than the unbonding period of {} blocks",
+                height, UNSTAKE_LOCK_PERIOD
+            ));
+        }
+        Ok(())
+    }
+
     /// Get active validators sorted by total stake
     pub fn get_active_validators(&self) -> Vec<Validator> {
         let mut validators: Vec<Validator> = self
@@ -634,6 +760,7 @@ mod tests {
             "ACT-validator1".to_string(),
             SlashReason::DoubleSigning,
             Some("ACT-reporter1".to_string()),
+            Some("evidence".to_string()),
         ).unwrap();
 
         assert_eq!(slashed, (MIN_VALIDATOR_STAKE as f64 * 0.3) as u64);
@@ -641,6 +768,60 @@ mod tests {
         let validator = staking.get_validator("ACT-validator1").unwrap();
         assert_eq!(validator.slash_events.len(), 1);
         assert!(!validator.active); // Should be deactivated due to insufficient stake
+        assert!(staking.has_slash_evidence("evidence"));
+        assert!(!staking.has_slash_evidence("other"));
+
+        // Evidence is only accepted once, and within the unbonding period
+        assert!(staking.check_evidence("evidence", 900).is_err());
+        assert!(staking.check_evidence("other", 900).is_ok());
+        staking.set_block_height(900 + UNSTAKE_LOCK_PERIOD + 1);
+        assert!(staking.check_evidence("other", 900).is_err());
+        assert!(staking.check_evidence("other", 901).is_ok());
+    }
+
+    #[test]
+    fn test_downtime_jailing() {
+        let mut staking = StakingManager::new();
+        staking.stake("ACT-online".to_string(), MIN_VALIDATOR_STAKE * 2, 10).unwrap();
+        staking.stake("ACT-offline".to_string(), MIN_VALIDATOR_STAKE * 2, 10).unwrap();
+        staking.begin_block(0, None);
+
+        // Signing just half the window keeps a validator active
+        let both: HashSet<String> = ["ACT-online".to_string(), "ACT-offline".to_string()].into();
+        let online: HashSet<String> = ["ACT-online".to_string()].into();
+        let mut height = 1;
+        for i in 0..SIGNED_BLOCKS_WINDOW {
+            let signers = if i < MIN_SIGNED_PER_WINDOW { &both } else { &online };
+            assert!(staking.begin_block(height, Some(signers)).is_empty());
+            height += 1;
+        }
+        assert_eq!(staking.get_validator("ACT-offline").unwrap().missed_blocks(), SIGNED_BLOCKS_WINDOW - MIN_SIGNED_PER_WINDOW);
+
+        // One more miss and it is slashed and jailed
+        let jailed = staking.begin_block(height, Some(&online));
+        assert_eq!(jailed, vec![("ACT-offline".to_string(), MIN_VALIDATOR_STAKE * 2 / 20)]);
+        let validator = staking.get_validator("ACT-offline").unwrap();
+        assert_eq!(validator.jailed_until, Some(height + DOWNTIME_JAIL_PERIOD));
+        assert!(!validator.can_validate());
+        assert!(matches!(validator.slash_events[0].reason, SlashReason::Downtime));
+        assert_eq!(staking.get_active_validators().len(), 1);
+
+        // It stays in this epoch's set but is no longer counted
+        assert!(staking.begin_block(height + 1, Some(&online)).is_empty());
+        assert!(staking.get_validator("ACT-offline").unwrap().signing_window.is_empty());
+
+        // The next epoch is taken without it
+        let boundary = (height / EPOCH_LENGTH + 1) * EPOCH_LENGTH;
+        staking.begin_block(boundary, Some(&online));
+        assert_eq!(staking.epoch_validators().keys().collect::<Vec<_>>(), vec!["ACT-online"]);
+
+        // Unjailing waits out the jail period
+        assert!(staking.unjail("ACT-offline".to_string()).is_err());
+        staking.set_block_height(height + DOWNTIME_JAIL_PERIOD);
+        staking.unjail("ACT-offline".to_string()).unwrap();
+        assert!(staking.get_validator("ACT-offline").unwrap().can_validate());
+        assert!(staking.unjail("ACT-offline".to_string()).is_err());
+        assert!(staking.unjail("ACT-online".to_string()).is_err());
     }
 }
 
@@ -650,16 +831,34 @@ pub struct StakingState {
     pub validators: Vec<Validator>,
     pub delegators: Vec<Delegator>,
     pub unstake_requests: Vec<UnstakeRequest>,
+    #[serde(default)]
+    pub epoch_validators: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub current_height: u64,
+    #[serde(default)]
+    pub total_staked: u64,
 }
 
 // Persistence methods for StakingManager
 impl StakingManager {
-    /// Serialize staking state to JSON for persistence
+    /// Serialize staking state to JSON for persistence.
+    /// Records are ordered by address, so equal states always serialize to the same bytes.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        let mut validators: Vec<Validator> = self.validators.values().cloned().collect();
+        validators.sort_by(|a, b| a.address.cmp(&b.address));
+        let mut delegator_addresses: Vec<&String> = self.delegators.keys().collect();
+        delegator_addresses.sort();
+
         let state = StakingState {
-            validators: self.validators.values().cloned().collect(),
-            delegators: self.delegators.values().flatten().cloned().collect(),
+            validators,
+            delegators: delegator_addresses
+                .into_iter()
+                .flat_map(|address| self.delegators[address].iter().cloned())
+                .collect(),
             unstake_requests: self.unstake_requests.clone(),
+            epoch_validators: self.epoch_validators.clone(),
+            current_height: self.current_height,
+            total_staked: self.total_staked,
         };
         serde_json::to_string(&state)
     }
@@ -678,6 +877,9 @@ impl StakingManager {
                 .push(delegator);
         }
         manager.unstake_requests = state.unstake_requests;
+        manager.epoch_validators = state.epoch_validators;
+        manager.current_height = state.current_height;
+        manager.total_staked = state.total_staked;
         
         Ok(manager)
     }
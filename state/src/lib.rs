@@ -1,12 +1,19 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use storage::BlockchainStorage;
 use types::{Account, ActAmount, EventLog, Transaction, TransactionReceipt, TransactionType};
 
+mod trie;
+
+pub use trie::{verify_proof, MerkleTrie, ProofLeaf, StateProof, TrieProof};
+
+const STATE_ROOT_KEY: &str = "state_trie_root";
+const GENESIS_ROOT_KEY: &str = "genesis_state_root";
+
 /// Cache entry with TTL
 struct CacheEntry<T> {
     value: T,
@@ -33,17 +40,28 @@ pub struct StateManager {
     receipts: Arc<RwLock<HashMap<String, TransactionReceipt>>>,  // tx_hash -> receipt
     balance_cache: Arc<RwLock<HashMap<String, CacheEntry<ActAmount>>>>,  // address -> balance cache
     nonce_cache: Arc<RwLock<HashMap<String, CacheEntry<u64>>>>,  // address -> nonce cache
+    trie: RwLock<MerkleTrie>,  // address -> account JSON
 }
 
 impl StateManager {
-    pub fn new(storage: Arc<BlockchainStorage>) -> Self {
-        Self {
+    pub fn new(storage: Arc<BlockchainStorage>) -> Result<Self> {
+        let root = match storage.get_state(STATE_ROOT_KEY)? {
+            Some(bytes) => bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow!("Invalid stored state root"))?,
+            None => trie::EMPTY_ROOT,
+        };
+        let trie = MerkleTrie::new(storage.clone(), root);
+        
+        Ok(Self {
             accounts: Arc::new(RwLock::new(HashMap::new())),
             storage,
             receipts: Arc::new(RwLock::new(HashMap::new())),
             balance_cache: Arc::new(RwLock::new(HashMap::new())),
             nonce_cache: Arc::new(RwLock::new(HashMap::new())),
-        }
+            trie: RwLock::new(trie),
+        })
     }
 
     /// Initialize genesis state with pre-funded accounts
@@ -71,9 +89,24 @@ impl StateManager {
             );
         }
         
+        // Pre-state of the first block, for re-executing it later
+        if self.storage.get_state(GENESIS_ROOT_KEY)?.is_none() {
+            let root = self.trie.read().unwrap().root();
+            self.storage.store_state(GENESIS_ROOT_KEY, &root)?;
+        }
+        
         Ok(())
     }
 
+    /// State root right after genesis initialization
+    pub fn genesis_state_root(&self) -> Result<String> {
+        let root = self
+            .storage
+            .get_state(GENESIS_ROOT_KEY)?
+            .ok_or_else(|| anyhow!("Genesis state has not been initialized"))?;
+        Ok(hex::encode(root))
+    }
+
     /// Get account by address
     pub fn get_account(&self, address: &str) -> Result<Account> {
         let accounts = self.accounts.read().unwrap();
@@ -251,7 +284,7 @@ impl StateManager {
             balance: initial_balance,
             nonce: 0,
             code_hash: Some(code_hash),
-            storage_root: Some("empty_storage".to_string()),
+            storage_root: Some(hex::encode(trie::EMPTY_ROOT)),
         };
         
         accounts.insert(contract_address.clone(), contract_account.clone());
@@ -283,33 +316,151 @@ impl StateManager {
         format!("ACT-CONTRACT-{}", encoded)
     }
 
-    /// Calculate state root (simplified Merkle root)
+    /// Root of the account state trie
     pub fn calculate_state_root(&self) -> Result<String> {
-        let accounts = self.accounts.read().unwrap();
+        Ok(self.trie.read().unwrap().root_hex())
+    }
+
+    /// Check the committed state is the one the stored tip block commits to. A block and its
+    /// state are written together, so a mismatch means the data directory is damaged.
+    pub fn verify_tip(&self) -> Result<()> {
+        let height = match self.storage.get_latest_height()? {
+            Some(height) => height,
+            None => return Ok(()),
+        };
+        let tip = self
+            .storage
+            .get_block(height)?
+            .ok_or_else(|| anyhow!("Tip block {} is missing", height))?;
+        let root = self.calculate_state_root()?;
+        if root != tip.header.state_root {
+            return Err(anyhow!(
+                "State root {} does not match {} committed by tip block {}",
+                root, tip.header.state_root, height
+            ));
+        }
+        Ok(())
+    }
+
+    /// Calculate the state root that would result from committing an overlay
+    pub fn calculate_state_root_with(&self, overlay: &StateOverlay) -> Result<String> {
+        let mut trie = match overlay.root {
+            Some(root) => MerkleTrie::new(self.storage.clone(), root),
+            None => self.trie.read().unwrap().clone(),
+        };
+        for account in overlay.accounts.values() {
+            trie.insert(account.address.as_bytes(), &serde_json::to_vec(account)?)?;
+        }
+        Ok(trie.root_hex())
+    }
+
+    /// Account at `address` with a Merkle proof against the current state root
+    pub fn get_proof(&self, address: &str) -> Result<StateProof> {
+        let trie = self.trie.read().unwrap();
+        let proof = trie.get_proof(address.as_bytes())?;
         
-        let mut account_hashes = Vec::new();
-        for account in accounts.values() {
-            let account_data = serde_json::to_vec(account)?;
-            let mut hasher = Sha256::new();
-            hasher.update(&account_data);
-            account_hashes.push(hasher.finalize().to_vec());
+        // Persisted accounts are exactly the ones committed in the trie
+        let account = self.load_account_from_storage(address)?;
+        
+        Ok(StateProof {
+            state_root: trie.root_hex(),
+            address: address.to_string(),
+            account,
+            proof,
+        })
+    }
+
+    /// Get deployed contract code
+    pub fn get_code(&self, address: &str) -> Result<Option<Vec<u8>>> {
+        self.storage.get_state(&format!("contract_code_{}", address))
+    }
+
+    /// Read a value from a contract's storage
+    pub fn get_storage(&self, address: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.storage.get_state(&storage_key(address, key))
+    }
+
+    /// Open a contract's storage trie at the given root
+    fn storage_trie(&self, root: Option<&str>) -> MerkleTrie {
+        MerkleTrie::new(self.storage.clone(), trie::parse_root(root))
+    }
+
+    /// Start a set of uncommitted changes on top of the current state
+    pub fn overlay(self: &Arc<Self>) -> StateOverlay {
+        StateOverlay::new(self.clone())
+    }
+
+    /// Start a set of changes on top of the state as of a past state root.
+    /// The overlay can be executed against but not committed.
+    pub fn overlay_at(self: &Arc<Self>, state_root: &str) -> Result<StateOverlay> {
+        let mut overlay = StateOverlay::new(self.clone());
+        overlay.root = Some(trie::decode_hash(state_root)?);
+        Ok(overlay)
+    }
+
+    /// Account as of a past state root
+    fn get_account_at(&self, root: trie::Hash, address: &str) -> Result<Account> {
+        match MerkleTrie::new(self.storage.clone(), root).get(address.as_bytes())? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Ok(Account::new(address.to_string())),
         }
+    }
+
+    /// Apply the accounts, code and contract storage buffered in an overlay
+    pub fn commit(&self, mut overlay: StateOverlay) -> Result<()> {
+        if overlay.root.is_some() {
+            return Err(anyhow!("Cannot commit an overlay opened at a past state root"));
+        }
+        overlay.update_storage_roots()?;
         
-        account_hashes.sort();
+        for (address, code) in &overlay.code {
+            self.storage.store_state(&format!("contract_code_{}", address), code)?;
+        }
         
-        let mut hasher = Sha256::new();
-        for hash in account_hashes {
-            hasher.update(&hash);
+        // Rebuild each touched storage trie from its committed root
+        for (address, writes) in &overlay.storage {
+            let committed = self.get_account(address)?;
+            let mut trie = self.storage_trie(committed.storage_root.as_deref());
+            for (key, value) in writes {
+                match value {
+                    Some(value) => {
+                        trie.insert(key, value)?;
+                        self.storage.store_state(&storage_key(address, key), value)?;
+                    }
+                    None => {
+                        trie.remove(key)?;
+                        self.storage.delete_state(&storage_key(address, key))?;
+                    }
+                }
+            }
+            trie.flush()?;
         }
         
-        Ok(hex::encode(hasher.finalize()))
+        {
+            let mut accounts = self.accounts.write().unwrap();
+            for (address, account) in &overlay.accounts {
+                accounts.insert(address.clone(), account.clone());
+            }
+        }
+        
+        for (address, account) in &overlay.accounts {
+            self.save_account_to_storage(account)?;
+            self.invalidate_cache(address);
+        }
+        
+        Ok(())
     }
 
-    /// Save account to persistent storage
+    /// Save account to persistent storage and the state trie
     fn save_account_to_storage(&self, account: &Account) -> Result<()> {
         let key = format!("account_{}", account.address);
         let value = serde_json::to_vec(account)?;
         self.storage.store_state(&key, &value)?;
+        
+        let mut trie = self.trie.write().unwrap();
+        trie.insert(account.address.as_bytes(), &value)?;
+        trie.flush()?;
+        self.storage.store_state(STATE_ROOT_KEY, &trie.root())?;
         Ok(())
     }
 
@@ -420,17 +571,288 @@ impl StateManager {
         
         // Filter by topics if specified
         if let Some(topic_filters) = topics {
-            all_logs.retain(|log| {
-                topic_filters.iter().all(|filter_topic| {
-                    log.topics.contains(filter_topic)
-                })
-            });
+            all_logs.retain(|log| log.matches(None, &topic_filters));
         }
         
         Ok(all_logs)
     }
 }
 
+fn storage_key(address: &str, key: &[u8]) -> String {
+    format!("contract_storage_{}_{}", address, hex::encode(key))
+}
+
+/// Account, code and contract storage changes buffered on top of a `StateManager`.
+///
+/// Reads fall through to the committed state; nothing is persisted until
+/// the overlay is passed to `StateManager::commit`.
+#[derive(Clone)]
+pub struct StateOverlay {
+    base: Arc<StateManager>,
+    root: Option<trie::Hash>,  // Past state root to read from instead of the latest state
+    accounts: HashMap<String, Account>,
+    code: HashMap<String, Vec<u8>>,
+    storage: HashMap<String, HashMap<Vec<u8>, Option<Vec<u8>>>>,  // contract -> key -> value (None = deleted)
+    dirty_storage: HashSet<String>,  // contracts whose storage_root is stale
+    journal: Vec<JournalEntry>,  // Every change, with what it replaced, oldest first
+}
+
+/// Position in an overlay's journal to roll a failed transaction or call back to.
+/// Taking one copies nothing; reverting undoes only the changes made since.
+pub struct StateCheckpoint {
+    journal_len: usize,
+}
+
+/// An overlay change and the entry it replaced (`None` if there was none)
+#[derive(Clone)]
+enum JournalEntry {
+    Account { address: String, previous: Option<Account> },
+    Code { address: String, previous: Option<Vec<u8>> },
+    Storage { address: String, key: Vec<u8>, previous: Option<Option<Vec<u8>>> },
+    StorageDirtied { address: String },
+    StorageCleaned { address: String },
+}
+
+impl StateOverlay {
+    pub fn new(base: Arc<StateManager>) -> Self {
+        Self {
+            base,
+            root: None,
+            accounts: HashMap::new(),
+            code: HashMap::new(),
+            storage: HashMap::new(),
+            dirty_storage: HashSet::new(),
+            journal: Vec::new(),
+        }
+    }
+
+    pub fn get_account(&self, address: &str) -> Result<Account> {
+        match self.accounts.get(address) {
+            Some(account) => Ok(account.clone()),
+            None => self.base_account(address),
+        }
+    }
+
+    /// Account as of the state this overlay started from
+    fn base_account(&self, address: &str) -> Result<Account> {
+        match self.root {
+            Some(root) => self.base.get_account_at(root, address),
+            None => self.base.get_account(address),
+        }
+    }
+
+    pub fn set_account(&mut self, account: Account) {
+        let address = account.address.clone();
+        let previous = self.accounts.insert(address.clone(), account);
+        self.journal.push(JournalEntry::Account { address, previous });
+    }
+
+    pub fn get_balance(&self, address: &str) -> Result<ActAmount> {
+        Ok(self.get_account(address)?.balance)
+    }
+
+    pub fn get_nonce(&self, address: &str) -> Result<u64> {
+        Ok(self.get_account(address)?.nonce)
+    }
+
+    pub fn get_code(&self, address: &str) -> Result<Option<Vec<u8>>> {
+        match self.code.get(address) {
+            Some(code) => Ok(Some(code.clone())),
+            // Code never changes once deployed, but may not exist yet at a past root
+            None if self.root.is_some() && !self.base_account(address)?.is_contract() => Ok(None),
+            None => self.base.get_code(address),
+        }
+    }
+
+    /// Read a value from a contract's storage
+    pub fn get_storage(&self, address: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.storage.get(address).and_then(|writes| writes.get(key)) {
+            Some(value) => Ok(value.clone()),
+            None if self.root.is_some() => {
+                let committed = self.base_account(address)?;
+                self.base.storage_trie(committed.storage_root.as_deref()).get(key)
+            }
+            None => self.base.get_storage(address, key),
+        }
+    }
+
+    /// Write a value to a contract's storage. An empty value is a value like any other;
+    /// keys are only removed by `delete_storage`.
+    /// `storage_root` is brought up to date by `update_storage_roots`.
+    pub fn set_storage(&mut self, address: &str, key: &[u8], value: &[u8]) {
+        self.write_storage(address, key, Some(value.to_vec()));
+    }
+
+    /// Remove a key from a contract's storage
+    pub fn delete_storage(&mut self, address: &str, key: &[u8]) {
+        self.write_storage(address, key, None);
+    }
+
+    fn write_storage(&mut self, address: &str, key: &[u8], value: Option<Vec<u8>>) {
+        let previous = self
+            .storage
+            .entry(address.to_string())
+            .or_default()
+            .insert(key.to_vec(), value);
+        self.journal.push(JournalEntry::Storage { address: address.to_string(), key: key.to_vec(), previous });
+        if self.dirty_storage.insert(address.to_string()) {
+            self.journal.push(JournalEntry::StorageDirtied { address: address.to_string() });
+        }
+    }
+
+    /// Recompute `storage_root` for every contract written since the last call
+    pub fn update_storage_roots(&mut self) -> Result<()> {
+        let dirty: Vec<String> = self.dirty_storage.drain().collect();
+        for address in dirty {
+            self.journal.push(JournalEntry::StorageCleaned { address: address.clone() });
+            let committed = self.base_account(&address)?;
+            let mut trie = self.base.storage_trie(committed.storage_root.as_deref());
+            for (key, value) in &self.storage[&address] {
+                match value {
+                    Some(value) => trie.insert(key, value)?,
+                    None => trie.remove(key)?,
+                }
+            }
+            
+            let mut account = self.get_account(&address)?;
+            account.storage_root = Some(trie.root_hex());
+            self.set_account(account);
+        }
+        Ok(())
+    }
+
+    /// Add ACT to an account
+    pub fn credit(&mut self, address: &str, amount: ActAmount) -> Result<()> {
+        let mut account = self.get_account(address)?;
+        account.balance = account
+            .balance
+            .checked_add(amount)
+            .ok_or_else(|| anyhow!("Balance overflow for {}", address))?;
+        self.set_account(account);
+        Ok(())
+    }
+
+    /// Remove ACT from an account
+    pub fn debit(&mut self, address: &str, amount: ActAmount) -> Result<()> {
+        let mut account = self.get_account(address)?;
+        if account.balance < amount {
+            return Err(anyhow!(
+                "Insufficient balance: has {}, needs {}",
+                account.balance,
+                amount
+            ));
+        }
+        account.balance -= amount;
+        self.set_account(account);
+        Ok(())
+    }
+
+    /// Transfer ACT between accounts
+    pub fn transfer(&mut self, from: &str, to: &str, amount: ActAmount) -> Result<()> {
+        self.debit(from, amount)?;
+        self.credit(to, amount)
+    }
+
+    pub fn increment_nonce(&mut self, address: &str) -> Result<()> {
+        let mut account = self.get_account(address)?;
+        account.nonce += 1;
+        self.set_account(account);
+        Ok(())
+    }
+
+    /// Deploy a contract at the address derived from the deployer and its current nonce
+    pub fn deploy_contract(&mut self, deployer: &str, code: &[u8], initial_balance: ActAmount) -> Result<String> {
+        let deployer_nonce = self.get_nonce(deployer)?;
+        let contract_address = self.base.calculate_contract_address(deployer, deployer_nonce);
+        
+        if self.get_account(&contract_address)?.is_contract() {
+            return Err(anyhow!("Contract already exists at {}", contract_address));
+        }
+        
+        let mut hasher = Sha256::new();
+        hasher.update(code);
+        let code_hash = hex::encode(hasher.finalize());
+        
+        self.transfer(deployer, &contract_address, initial_balance)?;
+        
+        let mut contract_account = self.get_account(&contract_address)?;
+        contract_account.code_hash = Some(code_hash);
+        contract_account.storage_root = Some(hex::encode(trie::EMPTY_ROOT));
+        self.set_account(contract_account);
+        
+        let previous = self.code.insert(contract_address.clone(), code.to_vec());
+        self.journal.push(JournalEntry::Code { address: contract_address.clone(), previous });
+        
+        Ok(contract_address)
+    }
+
+    pub fn checkpoint(&self) -> StateCheckpoint {
+        StateCheckpoint { journal_len: self.journal.len() }
+    }
+
+    /// Undo every change made since `checkpoint`, newest first
+    pub fn revert(&mut self, checkpoint: StateCheckpoint) {
+        while self.journal.len() > checkpoint.journal_len {
+            match self.journal.pop().unwrap() {
+                JournalEntry::Account { address, previous } => match previous {
+                    Some(account) => {
+                        self.accounts.insert(address, account);
+                    }
+                    None => {
+                        self.accounts.remove(&address);
+                    }
+                },
+                JournalEntry::Code { address, previous } => match previous {
+                    Some(code) => {
+                        self.code.insert(address, code);
+                    }
+                    None => {
+                        self.code.remove(&address);
+                    }
+                },
+                JournalEntry::Storage { address, key, previous } => {
+                    let writes = self.storage.entry(address.clone()).or_default();
+                    match previous {
+                        Some(value) => {
+                            writes.insert(key, value);
+                        }
+                        None => {
+                            writes.remove(&key);
+                        }
+                    }
+                    if writes.is_empty() {
+                        self.storage.remove(&address);
+                    }
+                }
+                JournalEntry::StorageDirtied { address } => {
+                    self.dirty_storage.remove(&address);
+                }
+                JournalEntry::StorageCleaned { address } => {
+                    self.dirty_storage.insert(address);
+                }
+            }
+        }
+    }
+
+    /// Root of the state this overlay started from
+    pub fn base_state_root(&self) -> String {
+        match self.root {
+            Some(root) => hex::encode(root),
+            None => self.base.trie.read().unwrap().root_hex(),
+        }
+    }
+
+    /// Overlay on the state as of a past state root of the same chain
+    pub fn overlay_at(&self, state_root: &str) -> Result<StateOverlay> {
+        self.base.overlay_at(state_root)
+    }
+
+    /// State root after committing this overlay
+    pub fn state_root(&self) -> Result<String> {
+        self.base.calculate_state_root_with(self)
+    }
+}
+
 /// Genesis account configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenesisAccount {
@@ -455,6 +877,15 @@ pub struct GasConfig {
     pub contract_deploy_base: u64,
     pub contract_call_base: u64,
     pub storage_write_cost: u64,
+    pub storage_read_cost: u64,
+    pub wasm_instruction_cost: u64,  // Per executed WASM instruction (one unit of wasmtime fuel)
+    pub log_cost: u64,
+    pub log_byte_cost: u64,      // Per byte of a log message
+    pub event_cost: u64,
+    pub event_byte_cost: u64,    // Per byte of event topic and data
+    pub storage_byte_cost: u64,  // Per byte of key and value written to storage
+    pub env_query_cost: u64,  // get_caller / get_balance and other context queries
+    pub call_cost: u64,       // Base cost of a nested contract call, on top of forwarded gas
 }
 
 impl Default for GasConfig {
@@ -465,6 +896,15 @@ impl Default for GasConfig {
             contract_deploy_base: 53000,
             contract_call_base: 25000,
             storage_write_cost: 20000,
+            storage_read_cost: 2000,
+            wasm_instruction_cost: 1,
+            log_cost: 100,
+            log_byte_cost: 8,
+            event_cost: 1000,
+            event_byte_cost: 8,
+            storage_byte_cost: 200,
+            env_query_cost: 100,
+            call_cost: 700,
         }
     }
 }
@@ -483,6 +923,11 @@ pub fn calculate_gas_cost(tx: &Transaction, gas_config: &GasConfig) -> u64 {
             // Ethereum legacy transaction gas calculation
             gas_config.transfer_cost + (data.len() as u64 * 16)
         }
+        TransactionType::Propose { title, description, .. } => {
+            gas_config.transfer_cost + ((title.len() + description.len()) as u64 * 16)
+        }
+        // Other staking and governance actions cost the same as a transfer
+        _ => gas_config.transfer_cost,
     }
 }
 
@@ -493,7 +938,7 @@ mod tests {
     #[test]
     fn test_genesis_initialization() {
         let storage = Arc::new(BlockchainStorage::new("./test_state_db").unwrap());
-        let state_manager = StateManager::new(storage);
+        let state_manager = StateManager::new(storage).unwrap();
         
         let genesis_accounts = vec![
             GenesisAccount::new("ACT-test1".to_string(), 1000000.0),
@@ -508,10 +953,260 @@ mod tests {
         std::fs::remove_dir_all("./test_state_db").ok();
     }
 
+    #[test]
+    fn test_overlay_commit_and_revert() {
+        let storage = Arc::new(BlockchainStorage::new("./test_overlay_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+        
+        let genesis_accounts = vec![GenesisAccount::new("ACT-sender".to_string(), 1000.0)];
+        state_manager.initialize_genesis(genesis_accounts).unwrap();
+        let root_before = state_manager.calculate_state_root().unwrap();
+        
+        let mut overlay = state_manager.overlay();
+        overlay.transfer("ACT-sender", "ACT-receiver", 1_000).unwrap();
+        
+        let checkpoint = overlay.checkpoint();
+        overlay.transfer("ACT-sender", "ACT-receiver", 5_000).unwrap();
+        overlay.revert(checkpoint);
+        
+        // Nothing is visible until commit
+        assert_eq!(state_manager.get_balance("ACT-receiver").unwrap(), 0);
+        assert_eq!(state_manager.calculate_state_root().unwrap(), root_before);
+        
+        let expected_root = overlay.state_root().unwrap();
+        state_manager.commit(overlay).unwrap();
+        
+        assert_eq!(state_manager.get_balance("ACT-receiver").unwrap(), 1_000);
+        assert_eq!(state_manager.calculate_state_root().unwrap(), expected_root);
+        
+        std::fs::remove_dir_all("./test_overlay_db").ok();
+    }
+
+    #[test]
+    fn test_nested_checkpoints() {
+        let storage = Arc::new(BlockchainStorage::new("./test_checkpoint_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+        state_manager
+            .initialize_genesis(vec![GenesisAccount::new("ACT-deployer".to_string(), 1000.0)])
+            .unwrap();
+        
+        let mut overlay = state_manager.overlay();
+        let contract = overlay.deploy_contract("ACT-deployer", b"code", 0).unwrap();
+        overlay.set_storage(&contract, b"kept", b"1");
+        overlay.update_storage_roots().unwrap();
+        let root = overlay.state_root().unwrap();
+        
+        let outer = overlay.checkpoint();
+        overlay.transfer("ACT-deployer", "ACT-receiver", 7).unwrap();
+        overlay.set_storage(&contract, b"kept", b"2");
+        
+        let inner = overlay.checkpoint();
+        let second = overlay.deploy_contract("ACT-receiver", b"more code", 0).unwrap();
+        overlay.delete_storage(&contract, b"kept");
+        overlay.set_storage(&contract, b"new", b"");
+        overlay.update_storage_roots().unwrap();
+        overlay.revert(inner);
+        
+        // Only the inner changes are undone
+        assert_eq!(overlay.get_code(&second).unwrap(), None);
+        assert_eq!(overlay.get_storage(&contract, b"kept").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(overlay.get_storage(&contract, b"new").unwrap(), None);
+        assert_eq!(overlay.get_balance("ACT-receiver").unwrap(), 7);
+        
+        // Back to where the outer checkpoint was taken, storage roots included
+        overlay.revert(outer);
+        assert_eq!(overlay.get_balance("ACT-receiver").unwrap(), 0);
+        assert_eq!(overlay.get_storage(&contract, b"kept").unwrap(), Some(b"1".to_vec()));
+        overlay.update_storage_roots().unwrap();
+        assert_eq!(overlay.state_root().unwrap(), root);
+        
+        std::fs::remove_dir_all("./test_checkpoint_db").ok();
+    }
+
+    #[test]
+    fn test_state_proofs() {
+        let storage = Arc::new(BlockchainStorage::new("./test_proof_db").unwrap());
+        let state_manager = StateManager::new(storage).unwrap();
+        
+        let genesis_accounts = (0..8)
+            .map(|i| GenesisAccount::new(format!("ACT-proof{}", i), 10.0 + i as f64))
+            .collect();
+        state_manager.initialize_genesis(genesis_accounts).unwrap();
+        let root = state_manager.calculate_state_root().unwrap();
+        
+        // Inclusion
+        let proof = state_manager.get_proof("ACT-proof3").unwrap();
+        assert_eq!(proof.state_root, root);
+        assert_eq!(proof.account.as_ref().unwrap().address, "ACT-proof3");
+        assert!(verify_proof(&root, "ACT-proof3", &proof));
+        assert!(!verify_proof(&root, "ACT-proof4", &proof));
+        
+        let mut forged = proof.clone();
+        forged.account.as_mut().unwrap().balance += 1;
+        assert!(!verify_proof(&root, "ACT-proof3", &forged));
+        
+        // Exclusion
+        let proof = state_manager.get_proof("ACT-missing").unwrap();
+        assert!(proof.account.is_none());
+        assert!(verify_proof(&root, "ACT-missing", &proof));
+        
+        // Proofs are tied to the root they were built against
+        state_manager.transfer("ACT-proof0", "ACT-missing", 1).unwrap();
+        let new_root = state_manager.calculate_state_root().unwrap();
+        assert_ne!(root, new_root);
+        assert!(!verify_proof(&new_root, "ACT-missing", &proof));
+        assert!(verify_proof(&new_root, "ACT-missing", &state_manager.get_proof("ACT-missing").unwrap()));
+        
+        // The root depends only on the account set, not insertion order
+        let reordered = Arc::new(BlockchainStorage::new("./test_proof_reordered_db").unwrap());
+        let other = StateManager::new(reordered).unwrap();
+        let genesis_accounts = (0..8)
+            .rev()
+            .map(|i| GenesisAccount::new(format!("ACT-proof{}", i), 10.0 + i as f64))
+            .collect();
+        other.initialize_genesis(genesis_accounts).unwrap();
+        assert_eq!(other.calculate_state_root().unwrap(), root);
+        
+        std::fs::remove_dir_all("./test_proof_db").ok();
+        std::fs::remove_dir_all("./test_proof_reordered_db").ok();
+    }
+
+    #[test]
+    fn test_contract_storage() {
+        let storage = Arc::new(BlockchainStorage::new("./test_contract_storage_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+        
+        let genesis_accounts = vec![GenesisAccount::new("ACT-deployer".to_string(), 1000.0)];
+        state_manager.initialize_genesis(genesis_accounts).unwrap();
+        
+        let mut overlay = state_manager.overlay();
+        let contract = overlay.deploy_contract("ACT-deployer", b"\0asm", 0).unwrap();
+        let empty_root = overlay.get_account(&contract).unwrap().storage_root;
+        
+        overlay.set_storage(&contract, b"counter", &[1]);
+        overlay.set_storage(&contract, b"owner", b"ACT-deployer");
+        overlay.update_storage_roots().unwrap();
+        let storage_root = overlay.get_account(&contract).unwrap().storage_root;
+        assert_ne!(storage_root, empty_root);
+        assert_eq!(overlay.get_storage(&contract, b"counter").unwrap(), Some(vec![1]));
+        
+        // Deleting a key restores the root without it
+        let checkpoint = overlay.checkpoint();
+        overlay.delete_storage(&contract, b"owner");
+        overlay.delete_storage(&contract, b"counter");
+        overlay.update_storage_roots().unwrap();
+        assert_eq!(overlay.get_storage(&contract, b"owner").unwrap(), None);
+        assert_eq!(overlay.get_account(&contract).unwrap().storage_root, empty_root);
+        overlay.revert(checkpoint);
+        
+        // An empty value is stored, not a deletion
+        let checkpoint = overlay.checkpoint();
+        overlay.set_storage(&contract, b"owner", b"");
+        overlay.update_storage_roots().unwrap();
+        assert_eq!(overlay.get_storage(&contract, b"owner").unwrap(), Some(Vec::new()));
+        assert_ne!(overlay.get_account(&contract).unwrap().storage_root, storage_root);
+        overlay.revert(checkpoint);
+        
+        let expected_root = overlay.state_root().unwrap();
+        state_manager.commit(overlay).unwrap();
+        
+        assert_eq!(state_manager.get_storage(&contract, b"owner").unwrap(), Some(b"ACT-deployer".to_vec()));
+        assert_eq!(state_manager.get_account(&contract).unwrap().storage_root, storage_root);
+        assert_eq!(state_manager.calculate_state_root().unwrap(), expected_root);
+        
+        // Later writes build on the committed storage trie
+        let mut overlay = state_manager.overlay();
+        overlay.set_storage(&contract, b"counter", &[2]);
+        overlay.set_storage(&contract, b"flag", b"");
+        overlay.update_storage_roots().unwrap();
+        assert_ne!(overlay.get_account(&contract).unwrap().storage_root, storage_root);
+        assert_eq!(overlay.get_storage(&contract, b"owner").unwrap(), Some(b"ACT-deployer".to_vec()));
+        
+        // Empty values survive a commit
+        state_manager.commit(overlay).unwrap();
+        assert_eq!(state_manager.get_storage(&contract, b"flag").unwrap(), Some(Vec::new()));
+        assert_eq!(state_manager.get_storage(&contract, b"missing").unwrap(), None);
+        
+        std::fs::remove_dir_all("./test_contract_storage_db").ok();
+    }
+
+    #[test]
+    fn test_historical_overlay() {
+        let storage = Arc::new(BlockchainStorage::new("./test_historical_overlay_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage).unwrap());
+        
+        let genesis_accounts = vec![GenesisAccount::new("ACT-deployer".to_string(), 1000.0)];
+        state_manager.initialize_genesis(genesis_accounts).unwrap();
+        let genesis_root = state_manager.genesis_state_root().unwrap();
+        
+        let mut overlay = state_manager.overlay();
+        let contract = overlay.deploy_contract("ACT-deployer", b"\0asm", 0).unwrap();
+        overlay.set_storage(&contract, b"counter", &[1]);
+        overlay.transfer("ACT-deployer", "ACT-receiver", 100).unwrap();
+        state_manager.commit(overlay).unwrap();
+        let first_root = state_manager.calculate_state_root().unwrap();
+        
+        let mut overlay = state_manager.overlay();
+        overlay.set_storage(&contract, b"counter", &[2]);
+        overlay.transfer("ACT-deployer", "ACT-receiver", 100).unwrap();
+        state_manager.commit(overlay).unwrap();
+        
+        // Reads see the state as of the requested root
+        let past = state_manager.overlay_at(&first_root).unwrap();
+        assert_eq!(past.get_balance("ACT-receiver").unwrap(), 100);
+        assert_eq!(past.get_storage(&contract, b"counter").unwrap(), Some(vec![1]));
+        assert_eq!(past.state_root().unwrap(), first_root);
+        
+        let genesis = state_manager.overlay_at(&genesis_root).unwrap();
+        assert_eq!(genesis.get_balance("ACT-receiver").unwrap(), 0);
+        assert_eq!(genesis.get_code(&contract).unwrap(), None);
+        assert!(state_manager.commit(genesis).is_err());
+        
+        std::fs::remove_dir_all("./test_historical_overlay_db").ok();
+    }
+
+    #[test]
+    fn test_verify_tip() {
+        std::fs::remove_dir_all("./test_verify_tip_db").ok();
+        let storage = Arc::new(BlockchainStorage::new("./test_verify_tip_db").unwrap());
+        let state_manager = Arc::new(StateManager::new(storage.clone()).unwrap());
+        state_manager.initialize_genesis(vec![GenesisAccount::new("ACT-sender".to_string(), 10.0)]).unwrap();
+        assert!(state_manager.verify_tip().is_ok());
+        
+        let tip = |state_root: String| {
+            let header = types::BlockHeader {
+                parent_hash: "0".repeat(64),
+                tx_root: "0".repeat(64),
+                actor_pubkey: String::new(),
+                state_root,
+                receipts_root: "0".repeat(64),
+                timestamp: 0,
+                validator_commitment: "ACT-validator".to_string(),
+                reward: 0,
+                height: 0,
+                round: 0,
+                proposer_pubkey: Vec::new(),
+                signature: Vec::new(),
+                last_commit_hash: "0".repeat(64),
+            };
+            types::Block::new(header, Vec::new(), Vec::new())
+        };
+        storage.store_block(&tip(state_manager.calculate_state_root().unwrap())).unwrap();
+        assert!(state_manager.verify_tip().is_ok());
+        
+        // State committed without its block no longer matches the tip
+        let mut overlay = state_manager.overlay();
+        overlay.transfer("ACT-sender", "ACT-receiver", 1).unwrap();
+        state_manager.commit(overlay).unwrap();
+        assert!(state_manager.verify_tip().unwrap_err().to_string().contains("does not match"));
+        
+        std::fs::remove_dir_all("./test_verify_tip_db").ok();
+    }
+
     #[test]
     fn test_transfer() {
         let storage = Arc::new(BlockchainStorage::new("./test_transfer_db").unwrap());
-        let state_manager = StateManager::new(storage);
+        let state_manager = StateManager::new(storage).unwrap();
         
         let genesis_accounts = vec![GenesisAccount::new("ACT-sender".to_string(), 1000.0)];
         state_manager.initialize_genesis(genesis_accounts).unwrap();
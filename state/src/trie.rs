@@ -0,0 +1,424 @@
+//! Sparse Merkle trie used for account state and contract storage.
+//!
+//! Entries live at the 256-bit path `sha256(key)`. Subtrees holding a single
+//! entry are collapsed into that entry's leaf, which keeps paths (and proofs)
+//! around `log2(n)` nodes long. Nodes are content-addressed and persisted
+//! through `BlockchainStorage` under `trie_node_{hash}`, so all tries share
+//! one node store; superseded nodes are left in place. Values are kept under
+//! `trie_value_{hash}`, so any past root can still be read.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use storage::BlockchainStorage;
+use types::Account;
+
+pub type Hash = [u8; 32];
+
+/// Root of a trie with no entries
+pub const EMPTY_ROOT: Hash = [0u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const INTERNAL_PREFIX: u8 = 0x01;
+
+enum Node {
+    Leaf { path: Hash, value_hash: Hash },
+    Internal { left: Hash, right: Hash },
+}
+
+impl Node {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(65);
+        match self {
+            Node::Leaf { path, value_hash } => {
+                bytes.push(LEAF_PREFIX);
+                bytes.extend_from_slice(path);
+                bytes.extend_from_slice(value_hash);
+            }
+            Node::Internal { left, right } => {
+                bytes.push(INTERNAL_PREFIX);
+                bytes.extend_from_slice(left);
+                bytes.extend_from_slice(right);
+            }
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 65 {
+            return Err(anyhow!("Invalid trie node length: {}", bytes.len()));
+        }
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        a.copy_from_slice(&bytes[1..33]);
+        b.copy_from_slice(&bytes[33..65]);
+        match bytes[0] {
+            LEAF_PREFIX => Ok(Node::Leaf { path: a, value_hash: b }),
+            INTERNAL_PREFIX => Ok(Node::Internal { left: a, right: b }),
+            other => Err(anyhow!("Invalid trie node tag: {}", other)),
+        }
+    }
+
+    fn hash(&self) -> Hash {
+        sha256(&self.encode())
+    }
+}
+
+fn sha256(data: &[u8]) -> Hash {
+    Sha256::digest(data).into()
+}
+
+/// Bit `depth` of a path, most significant bit first
+fn bit(path: &Hash, depth: usize) -> bool {
+    path[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn leaf_hash(path: &Hash, value_hash: &Hash) -> Hash {
+    Node::Leaf { path: *path, value_hash: *value_hash }.hash()
+}
+
+fn internal_hash(left: &Hash, right: &Hash) -> Hash {
+    Node::Internal { left: *left, right: *right }.hash()
+}
+
+/// Parse a hex root as stored in headers and `Account.storage_root`.
+/// Missing or legacy placeholder values are treated as the empty root.
+pub fn parse_root(root: Option<&str>) -> Hash {
+    root.and_then(|r| decode_hash(r).ok()).unwrap_or(EMPTY_ROOT)
+}
+
+/// Authenticated key/value trie persisted in `BlockchainStorage`.
+///
+/// Updates are buffered in memory until `flush`, so a cloned trie can be
+/// used to compute a prospective root without touching the database.
+#[derive(Clone)]
+pub struct MerkleTrie {
+    storage: Arc<BlockchainStorage>,
+    root: Hash,
+    pending: HashMap<Hash, Vec<u8>>,
+    pending_values: HashMap<Hash, Vec<u8>>,
+}
+
+impl MerkleTrie {
+    /// Open the trie with the given root
+    pub fn new(storage: Arc<BlockchainStorage>, root: Hash) -> Self {
+        Self {
+            storage,
+            root,
+            pending: HashMap::new(),
+            pending_values: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    pub fn root_hex(&self) -> String {
+        hex::encode(self.root)
+    }
+
+    /// Insert or replace the value stored under `key`
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        let path = sha256(key);
+        let value_hash = sha256(value);
+        self.root = self.insert_at(self.root, &path, &value_hash, 0)?;
+        self.pending_values.insert(value_hash, value.to_vec());
+        Ok(())
+    }
+
+    /// Value stored under `key`
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let path = sha256(key);
+        let mut node = self.root;
+        let mut depth = 0;
+
+        loop {
+            if node == EMPTY_ROOT {
+                return Ok(None);
+            }
+
+            match self.load(&node)? {
+                Node::Leaf { path: leaf_path, value_hash } => {
+                    if leaf_path != path {
+                        return Ok(None);
+                    }
+                    return self.load_value(&value_hash).map(Some);
+                }
+                Node::Internal { left, right } => {
+                    node = if bit(&path, depth) { right } else { left };
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Remove `key` if present
+    pub fn remove(&mut self, key: &[u8]) -> Result<()> {
+        let path = sha256(key);
+        self.root = self.remove_at(self.root, &path, 0)?;
+        Ok(())
+    }
+
+    /// Persist buffered nodes
+    pub fn flush(&mut self) -> Result<()> {
+        for (hash, bytes) in self.pending.drain() {
+            self.storage.store_state(&node_key(&hash), &bytes)?;
+        }
+        for (hash, value) in self.pending_values.drain() {
+            self.storage.store_state(&value_key(&hash), &value)?;
+        }
+        Ok(())
+    }
+
+    /// Build an inclusion or exclusion proof for `key`
+    pub fn get_proof(&self, key: &[u8]) -> Result<TrieProof> {
+        let path = sha256(key);
+        let mut siblings = Vec::new();
+        let mut node = self.root;
+        let mut depth = 0;
+
+        loop {
+            if node == EMPTY_ROOT {
+                return Ok(TrieProof { siblings, leaf: None });
+            }
+
+            match self.load(&node)? {
+                Node::Leaf { path: leaf_path, value_hash } => {
+                    let leaf = if leaf_path == path {
+                        None
+                    } else {
+                        Some(ProofLeaf {
+                            key: hex::encode(leaf_path),
+                            value_hash: hex::encode(value_hash),
+                        })
+                    };
+                    return Ok(TrieProof { siblings, leaf });
+                }
+                Node::Internal { left, right } => {
+                    if bit(&path, depth) {
+                        siblings.push(hex::encode(left));
+                        node = right;
+                    } else {
+                        siblings.push(hex::encode(right));
+                        node = left;
+                    }
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    fn insert_at(&mut self, node: Hash, path: &Hash, value_hash: &Hash, depth: usize) -> Result<Hash> {
+        if node == EMPTY_ROOT {
+            return Ok(self.put(Node::Leaf { path: *path, value_hash: *value_hash }));
+        }
+
+        match self.load(&node)? {
+            Node::Leaf { path: leaf_path, .. } if leaf_path == *path => {
+                Ok(self.put(Node::Leaf { path: *path, value_hash: *value_hash }))
+            }
+            Node::Leaf { path: leaf_path, .. } => {
+                let new_leaf = self.put(Node::Leaf { path: *path, value_hash: *value_hash });
+                Ok(self.split(node, &leaf_path, new_leaf, path, depth))
+            }
+            Node::Internal { left, right } => {
+                if bit(path, depth) {
+                    let right = self.insert_at(right, path, value_hash, depth + 1)?;
+                    Ok(self.put(Node::Internal { left, right }))
+                } else {
+                    let left = self.insert_at(left, path, value_hash, depth + 1)?;
+                    Ok(self.put(Node::Internal { left, right }))
+                }
+            }
+        }
+    }
+
+    /// Push two leaves down until their paths diverge
+    fn split(&mut self, existing: Hash, existing_path: &Hash, new: Hash, new_path: &Hash, depth: usize) -> Hash {
+        let existing_bit = bit(existing_path, depth);
+        let new_bit = bit(new_path, depth);
+
+        let (left, right) = if existing_bit == new_bit {
+            let child = self.split(existing, existing_path, new, new_path, depth + 1);
+            if new_bit {
+                (EMPTY_ROOT, child)
+            } else {
+                (child, EMPTY_ROOT)
+            }
+        } else if new_bit {
+            (existing, new)
+        } else {
+            (new, existing)
+        };
+
+        self.put(Node::Internal { left, right })
+    }
+
+    fn remove_at(&mut self, node: Hash, path: &Hash, depth: usize) -> Result<Hash> {
+        if node == EMPTY_ROOT {
+            return Ok(EMPTY_ROOT);
+        }
+
+        match self.load(&node)? {
+            Node::Leaf { path: leaf_path, .. } => Ok(if leaf_path == *path { EMPTY_ROOT } else { node }),
+            Node::Internal { left, right } => {
+                let (left, right) = if bit(path, depth) {
+                    (left, self.remove_at(right, path, depth + 1)?)
+                } else {
+                    (self.remove_at(left, path, depth + 1)?, right)
+                };
+
+                // Keep the trie canonical: a lone leaf moves up to replace its parent
+                let only_child = match (left == EMPTY_ROOT, right == EMPTY_ROOT) {
+                    (true, true) => return Ok(EMPTY_ROOT),
+                    (true, false) => Some(right),
+                    (false, true) => Some(left),
+                    (false, false) => None,
+                };
+                if let Some(child) = only_child {
+                    if let Node::Leaf { .. } = self.load(&child)? {
+                        return Ok(child);
+                    }
+                }
+
+                Ok(self.put(Node::Internal { left, right }))
+            }
+        }
+    }
+
+    fn put(&mut self, node: Node) -> Hash {
+        let bytes = node.encode();
+        let hash = sha256(&bytes);
+        self.pending.insert(hash, bytes);
+        hash
+    }
+
+    fn load(&self, hash: &Hash) -> Result<Node> {
+        if let Some(bytes) = self.pending.get(hash) {
+            return Node::decode(bytes);
+        }
+        let bytes = self
+            .storage
+            .get_state(&node_key(hash))?
+            .ok_or_else(|| anyhow!("Missing trie node {}", hex::encode(hash)))?;
+        Node::decode(&bytes)
+    }
+
+    fn load_value(&self, hash: &Hash) -> Result<Vec<u8>> {
+        if let Some(value) = self.pending_values.get(hash) {
+            return Ok(value.clone());
+        }
+        self.storage
+            .get_state(&value_key(hash))?
+            .ok_or_else(|| anyhow!("Missing trie value {}", hex::encode(hash)))
+    }
+}
+
+fn node_key(hash: &Hash) -> String {
+    format!("trie_node_{}", hex::encode(hash))
+}
+
+fn value_key(hash: &Hash) -> String {
+    format!("trie_value_{}", hex::encode(hash))
+}
+
+/// Leaf found on the path of an absent key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofLeaf {
+    pub key: String,
+    pub value_hash: String,
+}
+
+/// Sibling hashes from the root down to the key's position
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrieProof {
+    pub siblings: Vec<String>,
+    /// Set for exclusion proofs that end at another key's leaf
+    pub leaf: Option<ProofLeaf>,
+}
+
+impl TrieProof {
+    /// Check that `key` maps to `value` (or is absent, for `None`) under `root`
+    pub fn verify(&self, root: &str, key: &[u8], value: Option<&[u8]>) -> bool {
+        self.compute_root(key, value)
+            .map(|computed| hex::encode(computed) == root.trim_start_matches("0x"))
+            .unwrap_or(false)
+    }
+
+    fn compute_root(&self, key: &[u8], value: Option<&[u8]>) -> Result<Hash> {
+        if self.siblings.len() > 256 {
+            return Err(anyhow!("Proof too long"));
+        }
+
+        let path = sha256(key);
+        let depth = self.siblings.len();
+
+        let mut hash = match (value, &self.leaf) {
+            (Some(value), None) => leaf_hash(&path, &sha256(value)),
+            (None, None) => EMPTY_ROOT,
+            (None, Some(leaf)) => {
+                let leaf_path = decode_hash(&leaf.key)?;
+                // The other leaf must sit exactly where this key's path ends
+                if leaf_path == path || (0..depth).any(|d| bit(&leaf_path, d) != bit(&path, d)) {
+                    return Err(anyhow!("Leaf does not terminate the key's path"));
+                }
+                leaf_hash(&leaf_path, &decode_hash(&leaf.value_hash)?)
+            }
+            (Some(_), Some(_)) => return Err(anyhow!("Inclusion proof cannot end at another leaf")),
+        };
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            let sibling = decode_hash(sibling)?;
+            hash = if bit(&path, depth) {
+                internal_hash(&sibling, &hash)
+            } else {
+                internal_hash(&hash, &sibling)
+            };
+        }
+
+        Ok(hash)
+    }
+}
+
+/// Account (or its absence) together with a proof against a state root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateProof {
+    /// Root the proof was built against
+    pub state_root: String,
+    pub address: String,
+    pub account: Option<Account>,
+    pub proof: TrieProof,
+}
+
+/// Check that `proof` shows the state of `address` under `root`.
+///
+/// On success `proof.account` is the account committed in the state root,
+/// or `None` if the address has no account.
+pub fn verify_proof(root: &str, address: &str, proof: &StateProof) -> bool {
+    if proof.address != address {
+        return false;
+    }
+
+    match &proof.account {
+        Some(account) => {
+            if account.address != address {
+                return false;
+            }
+            match serde_json::to_vec(account) {
+                Ok(value) => proof.proof.verify(root, address.as_bytes(), Some(&value)),
+                Err(_) => false,
+            }
+        }
+        None => proof.proof.verify(root, address.as_bytes(), None),
+    }
+}
+
+pub(crate) fn decode_hash(value: &str) -> Result<Hash> {
+    hex::decode(value.trim_start_matches("0x"))?
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow!("Invalid hash length"))
+}
@@ -1,19 +1,17 @@
 use anyhow::{anyhow, Result};
-use rocksdb::{DB, Options};
-use serde::{Deserialize, Serialize};
+use rocksdb::{Options, WriteBatch, DB};
+use std::collections::BTreeMap;
 use std::path::Path;
-use std::sync::Arc;
-use types::{Action, BlockHeader};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StoredBlock {
-    pub header: BlockHeader,
-    pub action: Action,
-    pub height: u64,
-}
+use std::sync::{Arc, Mutex};
+use types::consensus::{CertifiedBlock, CommitCertificate};
+use types::{Block, Transaction};
+
+/// Writes held back by `atomically`, None = delete
+type Staged = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
 
 pub struct BlockchainStorage {
     db: Arc<DB>,
+    staged: Mutex<Option<Staged>>,
 }
 
 impl BlockchainStorage {
@@ -26,43 +24,159 @@ impl BlockchainStorage {
         
         Ok(Self {
             db: Arc::new(db),
+            staged: Mutex::new(None),
         })
     }
 
-    pub fn store_block(&self, block: &StoredBlock) -> Result<()> {
-        let key = format!("block_{}", block.height);
+    /// Run `f` with every write it makes held back, then apply them all in one batch, so a
+    /// crash leaves either all of them or none. Reads inside `f` see the held writes.
+    /// Nothing is written if `f` fails; a call made while a batch is open joins it.
+    pub fn atomically<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        {
+            let mut staged = self.staged.lock().unwrap();
+            if staged.is_some() {
+                drop(staged);
+                return f();
+            }
+            *staged = Some(BTreeMap::new());
+        }
+        
+        let result = f();
+        let writes = self.staged.lock().unwrap().take().unwrap_or_default();
+        let value = result?;
+        
+        let mut batch = WriteBatch::default();
+        for (key, value) in writes {
+            match value {
+                Some(value) => batch.put(key, value),
+                None => batch.delete(key),
+            }
+        }
+        self.db.write(batch)?;
+        Ok(value)
+    }
+
+    fn put(&self, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> Result<()> {
+        match self.staged.lock().unwrap().as_mut() {
+            Some(staged) => {
+                staged.insert(key.as_ref().to_vec(), Some(value.as_ref().to_vec()));
+            }
+            None => self.db.put(key, value)?,
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>> {
+        if let Some(staged) = self.staged.lock().unwrap().as_ref() {
+            if let Some(value) = staged.get(key.as_ref()) {
+                return Ok(value.clone());
+            }
+        }
+        Ok(self.db.get(key)?)
+    }
+
+    fn delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        match self.staged.lock().unwrap().as_mut() {
+            Some(staged) => {
+                staged.insert(key.as_ref().to_vec(), None);
+            }
+            None => self.db.delete(key)?,
+        }
+        Ok(())
+    }
+
+    pub fn store_block(&self, block: &Block) -> Result<()> {
+        let height = block.header.height;
+        let key = format!("block_{}", height);
         let value = serde_json::to_vec(block)?;
         
-        self.db.put(key.as_bytes(), value)?;
+        self.put(key.as_bytes(), value)?;
         
         // Store height -> hash index
-        let hash = &block.header.action_hash;
-        self.db.put(format!("height_to_hash_{}", block.height).as_bytes(), hash.as_bytes())?;
+        let hash = block.hash();
+        self.put(format!("height_to_hash_{}", height).as_bytes(), hash.as_bytes())?;
         
         // Store hash -> height index for reverse lookup
-        self.db.put(format!("hash_to_height_{}", hash).as_bytes(), block.height.to_be_bytes())?;
+        self.put(format!("hash_to_height_{}", hash).as_bytes(), height.to_be_bytes())?;
+        
+        // Store tx hash -> (height, index) so transactions can be found after leaving the mempool
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let mut location = height.to_be_bytes().to_vec();
+            location.extend_from_slice(&(index as u32).to_be_bytes());
+            self.put(format!("tx_to_block_{}", tx.hash()).as_bytes(), location)?;
+        }
         
         // Also store latest height
-        self.db.put(b"latest_height", block.height.to_be_bytes())?;
+        self.put(b"latest_height", height.to_be_bytes())?;
         
-        println!("💾 Stored block {} (hash: {}...) to database", block.height, &hash[..16]);
+        println!("💾 Stored block {} (hash: {}..., {} txs) to database",
+            height, &hash[..16], block.transactions.len());
         Ok(())
     }
 
-    pub fn get_block(&self, height: u64) -> Result<Option<StoredBlock>> {
+    pub fn get_block(&self, height: u64) -> Result<Option<Block>> {
         let key = format!("block_{}", height);
         
-        match self.db.get(key.as_bytes())? {
+        match self.get(key.as_bytes())? {
             Some(data) => {
-                let block: StoredBlock = serde_json::from_slice(&data)?;
+                let block: Block = serde_json::from_slice(&data)?;
                 Ok(Some(block))
             }
             None => Ok(None),
         }
     }
 
+    /// Store the precommits that finalized the block at `certificate.height`
+    pub fn store_commit(&self, certificate: &CommitCertificate) -> Result<()> {
+        let key = format!("commit_{}", certificate.height);
+        self.put(key.as_bytes(), serde_json::to_vec(certificate)?)?;
+        Ok(())
+    }
+
+    /// Commit certificate of the block at a given height
+    pub fn get_commit(&self, height: u64) -> Result<Option<CommitCertificate>> {
+        match self.get(format!("commit_{}", height).as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Block at a given height with the certificate that finalized it, for peers catching up
+    pub fn get_certified_block(&self, height: u64) -> Result<Option<CertifiedBlock>> {
+        match (self.get_block(height)?, self.get_commit(height)?) {
+            (Some(block), Some(certificate)) => Ok(Some(CertifiedBlock { block, certificate })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the hash of the block at a given height
+    pub fn get_block_hash(&self, height: u64) -> Result<Option<String>> {
+        match self.get(format!("height_to_hash_{}", height).as_bytes())? {
+            Some(data) => Ok(Some(String::from_utf8(data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get an included transaction by hash, with the height of the block that contains it
+    pub fn get_transaction(&self, tx_hash: &str) -> Result<Option<(Transaction, u64)>> {
+        let location = match self.get(format!("tx_to_block_{}", tx_hash).as_bytes())? {
+            Some(data) if data.len() == 12 => data,
+            Some(_) => return Err(anyhow!("Corrupt transaction index for {}", tx_hash)),
+            None => return Ok(None),
+        };
+        
+        let height = u64::from_be_bytes(location[..8].try_into().unwrap());
+        let index = u32::from_be_bytes(location[8..].try_into().unwrap()) as usize;
+        
+        let block = self
+            .get_block(height)?
+            .ok_or_else(|| anyhow!("Block {} referenced by transaction index is missing", height))?;
+        
+        Ok(block.transactions.get(index).cloned().map(|tx| (tx, height)))
+    }
+
     pub fn get_latest_height(&self) -> Result<Option<u64>> {
-        match self.db.get(b"latest_height")? {
+        match self.get(b"latest_height")? {
             Some(data) => {
                 if data.len() == 8 {
                     let height = u64::from_be_bytes(data.as_slice().try_into().unwrap());
@@ -77,13 +191,19 @@ impl BlockchainStorage {
 
     pub fn store_state(&self, key: &str, value: &[u8]) -> Result<()> {
         let state_key = format!("state_{}", key);
-        self.db.put(state_key.as_bytes(), value)?;
+        self.put(state_key.as_bytes(), value)?;
         Ok(())
     }
 
     pub fn get_state(&self, key: &str) -> Result<Option<Vec<u8>>> {
         let state_key = format!("state_{}", key);
-        Ok(self.db.get(state_key.as_bytes())?)
+        self.get(state_key.as_bytes())
+    }
+
+    pub fn delete_state(&self, key: &str) -> Result<()> {
+        let state_key = format!("state_{}", key);
+        self.delete(state_key.as_bytes())?;
+        Ok(())
     }
 
     pub fn get_block_count(&self) -> Result<u64> {
@@ -94,10 +214,10 @@ impl BlockchainStorage {
     }
     
     /// Get block by hash
-    pub fn get_block_by_hash(&self, hash: &str) -> Result<Option<StoredBlock>> {
+    pub fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>> {
         // Look up height from hash
         let height_key = format!("hash_to_height_{}", hash);
-        match self.db.get(height_key.as_bytes())? {
+        match self.get(height_key.as_bytes())? {
             Some(height_bytes) if height_bytes.len() == 8 => {
                 let height = u64::from_be_bytes(height_bytes.as_slice().try_into().unwrap());
                 self.get_block(height)
@@ -107,7 +227,7 @@ impl BlockchainStorage {
     }
     
     /// Get recent blocks (latest N blocks)
-    pub fn get_recent_blocks(&self, count: usize) -> Result<Vec<StoredBlock>> {
+    pub fn get_recent_blocks(&self, count: usize) -> Result<Vec<Block>> {
         let latest_height = match self.get_latest_height()? {
             Some(h) => h,
             None => return Ok(vec![]),
@@ -131,46 +251,34 @@ impl BlockchainStorage {
     
     /// Store staking state
     pub fn store_staking_state(&self, state_bytes: &[u8]) -> Result<()> {
-        self.db.put(b"staking_state", state_bytes)?;
+        self.put(b"staking_state", state_bytes)?;
         println!("💾 Stored staking state to database");
         Ok(())
     }
     
     /// Load staking state
     pub fn load_staking_state(&self) -> Result<Option<Vec<u8>>> {
-        Ok(self.db.get(b"staking_state")?)
+        self.get(b"staking_state")
     }
     
     /// Store governance state
     pub fn store_governance_state(&self, state_bytes: &[u8]) -> Result<()> {
-        self.db.put(b"governance_state", state_bytes)?;
+        self.put(b"governance_state", state_bytes)?;
         println!("💾 Stored governance state to database");
         Ok(())
     }
     
     /// Load governance state
     pub fn load_governance_state(&self) -> Result<Option<Vec<u8>>> {
-        Ok(self.db.get(b"governance_state")?)
+        self.get(b"governance_state")
     }
 }
 
-pub fn store_block(header: &BlockHeader, action: &Action, height: u64) -> Result<()> {
-    let storage = BlockchainStorage::new("./blockchain_data")?;
-    
-    let block = StoredBlock {
-        header: header.clone(),
-        action: action.clone(),
-        height,
-    };
-    
-    storage.store_block(&block)?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
+    use types::BlockHeader;
 
     #[test]
     fn test_storage_operations() {
@@ -181,38 +289,68 @@ mod tests {
         
         let storage = BlockchainStorage::new(test_path).unwrap();
         
-        let action = Action {
-            actor: "test_actor".to_string(),
-            payload: vec![1, 2, 3],
-            nonce: 1,
-        };
-        
         let header = BlockHeader {
             parent_hash: "parent".to_string(),
-            action_hash: "hash".to_string(),
+            tx_root: "hash".to_string(),
             actor_pubkey: "pubkey".to_string(),
             state_root: "state".to_string(),
             receipts_root: "receipts".to_string(),
             timestamp: 1234567890,
             validator_commitment: "validator1".to_string(),
             reward: 100,
-        };
-        
-        let block = StoredBlock {
-            header,
-            action,
             height: 0,
+            round: 0,
+            proposer_pubkey: Vec::new(),
+            signature: Vec::new(),
+            last_commit_hash: "0".repeat(64),
         };
         
+        let block = Block::new(header, Vec::new(), Vec::new());
+        
         storage.store_block(&block).unwrap();
         
         let retrieved = storage.get_block(0).unwrap();
         assert!(retrieved.is_some());
         
+        let by_hash = storage.get_block_by_hash(&block.hash()).unwrap();
+        assert_eq!(by_hash.unwrap().header.height, 0);
+        
         let count = storage.get_block_count().unwrap();
         assert_eq!(count, 1);
         
         // Clean up
         let _ = fs::remove_dir_all(test_path);
     }
+
+    #[test]
+    fn test_atomic_writes() {
+        let test_path = "./test_blockchain_atomic";
+        let _ = fs::remove_dir_all(test_path);
+        let storage = BlockchainStorage::new(test_path).unwrap();
+        storage.store_state("kept", b"old").unwrap();
+        
+        // A failed batch leaves nothing behind, though its reads saw its writes
+        let result: Result<()> = storage.atomically(|| {
+            storage.store_state("kept", b"new")?;
+            storage.delete_state("kept")?;
+            storage.store_state("dropped", b"value")?;
+            assert_eq!(storage.get_state("dropped")?, Some(b"value".to_vec()));
+            assert_eq!(storage.get_state("kept")?, None);
+            Err(anyhow!("crash"))
+        });
+        assert!(result.is_err());
+        assert_eq!(storage.get_state("kept").unwrap(), Some(b"old".to_vec()));
+        assert_eq!(storage.get_state("dropped").unwrap(), None);
+        
+        storage
+            .atomically(|| {
+                storage.store_state("kept", b"new")?;
+                storage.atomically(|| storage.store_state("nested", b"value"))
+            })
+            .unwrap();
+        assert_eq!(storage.get_state("kept").unwrap(), Some(b"new".to_vec()));
+        assert_eq!(storage.get_state("nested").unwrap(), Some(b"value".to_vec()));
+        
+        let _ = fs::remove_dir_all(test_path);
+    }
 }
@@ -0,0 +1,245 @@
+use crypto::{ActAddress, ActKeyPair};
+use serde::{Deserialize, Serialize};
+
+use crate::Block;
+
+/// The two voting steps of a consensus round
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VoteType {
+    Prevote,
+    Precommit,
+}
+
+/// A validator's signed prevote or precommit. `block_hash` is `None` for a nil vote.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Vote {
+    pub height: u64,
+    pub round: u32,
+    pub vote_type: VoteType,
+    pub block_hash: Option<String>,
+    pub validator: ActAddress,
+    pub pubkey: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl Vote {
+    /// Sign a vote with a validator's key
+    pub fn new(keypair: &ActKeyPair, height: u64, round: u32, vote_type: VoteType, block_hash: Option<String>) -> Self {
+        let mut vote = Self {
+            height,
+            round,
+            vote_type,
+            block_hash,
+            validator: keypair.address().clone(),
+            pubkey: keypair.public_key(),
+            signature: Vec::new(),
+        };
+        vote.signature = keypair.sign(&vote.signing_data());
+        vote
+    }
+
+    /// Bytes covered by the signature, tagged so a vote can never pass for another message
+    pub fn signing_data(&self) -> Vec<u8> {
+        serde_json::to_vec(&("act-vote", self.height, self.round, self.vote_type, &self.block_hash)).unwrap()
+    }
+
+    /// Check the signature and that `pubkey` actually belongs to `validator`
+    pub fn verify_signature(&self) -> bool {
+        if ActAddress::from_pubkey(&self.pubkey) != self.validator {
+            return false;
+        }
+        crypto::verify_signature(&self.pubkey, &self.signing_data(), &self.signature).unwrap_or(false)
+    }
+}
+
+/// Bytes a proposer signs for a block proposal; the block is covered through its hash
+pub fn proposal_signing_data(height: u64, round: u32, block_hash: &str, pol_round: Option<u32>) -> Vec<u8> {
+    serde_json::to_vec(&("act-proposal", height, round, block_hash, pol_round)).unwrap()
+}
+
+/// The signed part of a block proposal, without the block itself
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedProposal {
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: String,
+    pub pol_round: Option<u32>,
+    pub validator: ActAddress,
+    pub pubkey: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedProposal {
+    pub fn verify_signature(&self) -> bool {
+        if ActAddress::from_pubkey(&self.pubkey) != self.validator {
+            return false;
+        }
+        let data = proposal_signing_data(self.height, self.round, &self.block_hash, self.pol_round);
+        crypto::verify_signature(&self.pubkey, &data, &self.signature).unwrap_or(false)
+    }
+}
+
+/// Proof that a validator signed two different messages for the same step: two proposals
+/// for one round, or two prevotes or precommits for one round that name different blocks
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Evidence {
+    ConflictingProposals { first: SignedProposal, second: SignedProposal },
+    ConflictingVotes { first: Vote, second: Vote },
+}
+
+impl Evidence {
+    /// Validator that signed both messages
+    pub fn validator(&self) -> &ActAddress {
+        match self {
+            Evidence::ConflictingProposals { first, .. } => &first.validator,
+            Evidence::ConflictingVotes { first, .. } => &first.validator,
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        match self {
+            Evidence::ConflictingProposals { first, .. } => first.height,
+            Evidence::ConflictingVotes { first, .. } => first.height,
+        }
+    }
+
+    /// Identifies the offence: the validator and the height, round and step it signed
+    /// twice at. Any pair of conflicting messages for that step, in either order, gives the
+    /// same hash, so the offence is only punished once.
+    pub fn hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let offence = match self {
+            Evidence::ConflictingProposals { first, .. } => (&first.validator, first.height, first.round, None),
+            Evidence::ConflictingVotes { first, .. } => (&first.validator, first.height, first.round, Some(first.vote_type)),
+        };
+        hex::encode(Sha256::digest(serde_json::to_vec(&offence).unwrap()))
+    }
+
+    /// Check that both messages are validly signed by the same validator and really conflict
+    pub fn verify(&self) -> Result<(), String> {
+        let (same_step, conflicting, signed) = match self {
+            Evidence::ConflictingProposals { first, second } => (
+                first.height == second.height && first.round == second.round,
+                first.block_hash != second.block_hash,
+                first.verify_signature() && second.verify_signature(),
+            ),
+            Evidence::ConflictingVotes { first, second } => (
+                first.height == second.height && first.round == second.round && first.vote_type == second.vote_type,
+                first.block_hash != second.block_hash,
+                first.verify_signature() && second.verify_signature(),
+            ),
+        };
+        let same_validator = match self {
+            Evidence::ConflictingProposals { first, second } => first.validator == second.validator,
+            Evidence::ConflictingVotes { first, second } => first.validator == second.validator,
+        };
+
+        if !same_validator {
+            return Err("Messages are from different validators".to_string());
+        }
+        if !same_step {
+            return Err("Messages are for different steps".to_string());
+        }
+        if !conflicting {
+            return Err("Messages do not conflict".to_string());
+        }
+        if !signed {
+            return Err("Invalid signature".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Precommits from more than 2/3 of the stake for one block: proof the block is final
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommitCertificate {
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: String,
+    pub precommits: Vec<Vote>,
+}
+
+impl CommitCertificate {
+    pub fn hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(serde_json::to_vec(self).unwrap()))
+    }
+
+    /// Validators whose precommits the certificate holds
+    pub fn signers(&self) -> impl Iterator<Item = &ActAddress> {
+        self.precommits.iter().map(|vote| &vote.validator)
+    }
+}
+
+/// A finalized block with the certificate proving it, as gossiped to peers
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CertifiedBlock {
+    pub block: Block,
+    pub certificate: CommitCertificate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vote_signature() {
+        let keypair = ActKeyPair::from_seed(&[7u8; 32]);
+        let vote = Vote::new(&keypair, 5, 1, VoteType::Precommit, Some("ab".repeat(32)));
+        assert!(vote.verify_signature());
+
+        // The signature covers every field
+        let mut nil = vote.clone();
+        nil.block_hash = None;
+        assert!(!nil.verify_signature());
+        let mut prevote = vote.clone();
+        prevote.vote_type = VoteType::Prevote;
+        assert!(!prevote.verify_signature());
+
+        // Signed by a key that is not the claimed validator's
+        let mut impostor = Vote::new(&ActKeyPair::from_seed(&[8u8; 32]), 5, 1, VoteType::Precommit, None);
+        impostor.validator = keypair.address().clone();
+        assert!(!impostor.verify_signature());
+    }
+
+    #[test]
+    fn test_vote_evidence() {
+        let keypair = ActKeyPair::from_seed(&[7u8; 32]);
+        let first = Vote::new(&keypair, 5, 1, VoteType::Prevote, Some("ab".repeat(32)));
+        let second = Vote::new(&keypair, 5, 1, VoteType::Prevote, None);
+        let evidence = Evidence::ConflictingVotes { first: first.clone(), second: second.clone() };
+        assert!(evidence.verify().is_ok());
+        assert_eq!(evidence.validator(), keypair.address());
+
+        // Same offence, same hash, whichever conflicting pair proves it
+        let swapped = Evidence::ConflictingVotes { first: second.clone(), second: first.clone() };
+        assert_eq!(swapped.hash(), evidence.hash());
+        let third = Vote::new(&keypair, 5, 1, VoteType::Prevote, Some("cd".repeat(32)));
+        let other_pair = Evidence::ConflictingVotes { first: third.clone(), second: second.clone() };
+        assert!(other_pair.verify().is_ok());
+        assert_eq!(other_pair.hash(), evidence.hash());
+
+        // Another step is another offence
+        let precommits = Evidence::ConflictingVotes {
+            first: Vote::new(&keypair, 5, 1, VoteType::Precommit, Some("ab".repeat(32))),
+            second: Vote::new(&keypair, 5, 1, VoteType::Precommit, None),
+        };
+        assert_ne!(precommits.hash(), evidence.hash());
+
+        // Votes for the same block, or for different rounds or steps, are no offence
+        let repeat = Evidence::ConflictingVotes { first: first.clone(), second: first.clone() };
+        assert!(repeat.verify().is_err());
+        let next_round = Vote::new(&keypair, 5, 2, VoteType::Prevote, None);
+        assert!(Evidence::ConflictingVotes { first: first.clone(), second: next_round }.verify().is_err());
+        let precommit = Vote::new(&keypair, 5, 1, VoteType::Precommit, None);
+        assert!(Evidence::ConflictingVotes { first: first.clone(), second: precommit }.verify().is_err());
+
+        // Nor are two validators' votes
+        let other = Vote::new(&ActKeyPair::from_seed(&[8u8; 32]), 5, 1, VoteType::Prevote, None);
+        assert!(Evidence::ConflictingVotes { first: first.clone(), second: other }.verify().is_err());
+
+        let mut forged = second;
+        forged.signature[0] ^= 1;
+        assert!(Evidence::ConflictingVotes { first, second: forged }.verify().is_err());
+    }
+}
@@ -0,0 +1,418 @@
+use anyhow::{anyhow, Result};
+use crypto::{keccak256, recover_eth_signer, ActAddress, EthAddress};
+
+use crate::rlp::{uint_bytes, Rlp};
+use crate::{ActAmount, Transaction, TransactionType};
+
+/// EIP-155 chain id of ACT Chain, as reported by `eth_chainId`
+pub const CHAIN_ID: u64 = 0xac7;
+
+/// Half the order of the secp256k1 curve, the largest `s` EIP-2 accepts
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Ethereum transaction envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EthTxKind {
+    Legacy,     // EIP-155 replay-protected legacy
+    AccessList, // EIP-2930, type 0x01
+    DynamicFee, // EIP-1559, type 0x02
+}
+
+/// A signed Ethereum transaction decoded from its raw bytes, with the recovered sender
+#[derive(Debug, Clone)]
+pub struct EthTransaction {
+    pub kind: EthTxKind,
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: ActAmount,  // Effective price; there is no base fee to burn
+    pub gas_limit: u64,
+    pub to: Option<String>,    // None: contract creation
+    pub value: ActAmount,
+    pub data: Vec<u8>,
+    pub sender: EthAddress,
+    pub pubkey: Vec<u8>,
+    pub signature: [u8; 64],  // r || s
+    pub recovery_id: i32,
+    pub hash: [u8; 32],
+    pub raw: Vec<u8>,
+}
+
+impl EthTxKind {
+    /// EIP-2718 transaction type
+    pub fn type_id(&self) -> u8 {
+        match self {
+            EthTxKind::Legacy => 0,
+            EthTxKind::AccessList => 1,
+            EthTxKind::DynamicFee => 2,
+        }
+    }
+}
+
+/// Decode a raw signed transaction as sent to `eth_sendRawTransaction` and check it
+/// is replay-protected for this chain
+pub fn decode_raw_transaction(raw: &[u8]) -> Result<EthTransaction> {
+    let tx = EthTransaction::decode(raw)?;
+    if tx.chain_id != CHAIN_ID {
+        return Err(anyhow!("Wrong chain id {}, expected {}", tx.chain_id, CHAIN_ID));
+    }
+    Ok(tx)
+}
+
+impl EthTransaction {
+    /// Decode a legacy or typed transaction and recover its sender
+    pub fn decode(raw: &[u8]) -> Result<Self> {
+        let (kind, payload) = match raw.first() {
+            Some(0x01) => (EthTxKind::AccessList, &raw[1..]),
+            Some(0x02) => (EthTxKind::DynamicFee, &raw[1..]),
+            Some(&prefix) if prefix >= 0xc0 => (EthTxKind::Legacy, raw),
+            Some(prefix) => return Err(anyhow!("Unsupported transaction type 0x{:02x}", prefix)),
+            None => return Err(anyhow!("Empty transaction")),
+        };
+
+        let item = Rlp::decode(payload)?;
+        let fields = item.as_list()?;
+
+        // Field layout, where each envelope keeps its values and how its signing payload is built
+        let (chain_id, nonce, gas_price, gas_limit, to, value, data, recovery_id, signing_payload) = match kind {
+            EthTxKind::Legacy => {
+                expect_fields(fields, 9)?;
+                let v = fields[6].as_u64()?;
+                if v == 27 || v == 28 {
+                    return Err(anyhow!("Transaction is not replay-protected (EIP-155)"));
+                }
+                let chain_id = v
+                    .checked_sub(35)
+                    .map(|v| v / 2)
+                    .ok_or_else(|| anyhow!("Invalid signature v value {}", v))?;
+                let recovery_id = ((v - 35) % 2) as i32;
+
+                let chain_id_bytes = uint_bytes(chain_id);
+                let mut unsigned = fields[..6].to_vec();
+                unsigned.extend([Rlp::Bytes(&chain_id_bytes), Rlp::Bytes(&[]), Rlp::Bytes(&[])]);
+
+                (
+                    chain_id,
+                    fields[0].as_u64()?,
+                    fields[1].as_uint()?,
+                    fields[2].as_u64()?,
+                    &fields[3],
+                    &fields[4],
+                    &fields[5],
+                    recovery_id,
+                    Rlp::List(unsigned).encode(),
+                )
+            }
+            EthTxKind::AccessList => {
+                expect_fields(fields, 11)?;
+                fields[7].as_list()?;
+
+                let mut signing_payload = vec![0x01];
+                signing_payload.extend(Rlp::List(fields[..8].to_vec()).encode());
+
+                (
+                    fields[0].as_u64()?,
+                    fields[1].as_u64()?,
+                    fields[2].as_uint()?,
+                    fields[3].as_u64()?,
+                    &fields[4],
+                    &fields[5],
+                    &fields[6],
+                    y_parity(&fields[8])?,
+                    signing_payload,
+                )
+            }
+            EthTxKind::DynamicFee => {
+                expect_fields(fields, 12)?;
+                fields[8].as_list()?;
+                let max_priority_fee = fields[2].as_uint()?;
+                let max_fee = fields[3].as_uint()?;
+                if max_priority_fee > max_fee {
+                    return Err(anyhow!("Max priority fee exceeds max fee"));
+                }
+
+                let mut signing_payload = vec![0x02];
+                signing_payload.extend(Rlp::List(fields[..9].to_vec()).encode());
+
+                (
+                    fields[0].as_u64()?,
+                    fields[1].as_u64()?,
+                    max_priority_fee,  // Base fee is zero, so the tip is the whole price
+                    fields[4].as_u64()?,
+                    &fields[5],
+                    &fields[6],
+                    &fields[7],
+                    y_parity(&fields[9])?,
+                    signing_payload,
+                )
+            }
+        };
+
+        let to = match to.as_bytes()? {
+            [] => None,
+            address if address.len() == 20 => Some(format!("0x{}", hex::encode(address))),
+            address => return Err(anyhow!("Invalid recipient of {} bytes", address.len())),
+        };
+
+        let (r, s) = (&fields[fields.len() - 2], &fields[fields.len() - 1]);
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&word(r)?);
+        signature[32..].copy_from_slice(&word(s)?);
+        // EIP-2: `s` and `n - s` both verify, so only the low form is accepted and a
+        // transaction cannot be re-encoded under a second hash
+        if signature[32..] > SECP256K1_HALF_ORDER[..] {
+            return Err(anyhow!("Signature s value above secp256k1n/2 (EIP-2)"));
+        }
+
+        let (pubkey, sender) = recover_eth_signer(&keccak256(&signing_payload), &signature, recovery_id)?;
+
+        Ok(Self {
+            kind,
+            chain_id,
+            nonce,
+            gas_price,
+            gas_limit,
+            to,
+            value: value.as_uint()?,
+            data: data.as_bytes()?.to_vec(),
+            sender,
+            pubkey,
+            signature,
+            recovery_id,
+            hash: keccak256(raw),
+            raw: raw.to_vec(),
+        })
+    }
+
+    /// Signature `v` as reported over JSON-RPC: EIP-155 encoded for legacy transactions,
+    /// the y parity for typed ones
+    pub fn v(&self) -> u64 {
+        match self.kind {
+            EthTxKind::Legacy => self.chain_id * 2 + 35 + self.recovery_id as u64,
+            _ => self.recovery_id as u64,
+        }
+    }
+
+    /// Map into an ACT transaction. The raw envelope is kept as the signature so any
+    /// node can decode it again and re-check the sender.
+    pub fn into_transaction(self) -> Transaction {
+        Transaction {
+            from: ActAddress(self.sender.0),
+            nonce: self.nonce,
+            tx_type: TransactionType::EthereumLegacy {
+                to: self.to.unwrap_or_default(),
+                value: self.value,
+                data: self.data,
+                gas_price: self.gas_price,
+            },
+            gas_limit: self.gas_limit,
+            gas_price: self.gas_price,
+            signature: self.raw,
+            pubkey: self.pubkey,
+        }
+    }
+}
+
+fn expect_fields(fields: &[Rlp], count: usize) -> Result<()> {
+    if fields.len() != count {
+        return Err(anyhow!("Expected {} transaction fields, found {}", count, fields.len()));
+    }
+    Ok(())
+}
+
+fn y_parity(item: &Rlp) -> Result<i32> {
+    match item.as_uint()? {
+        0 => Ok(0),
+        1 => Ok(1),
+        v => Err(anyhow!("Invalid signature y parity {}", v)),
+    }
+}
+
+/// Signature scalar left-padded to 32 bytes
+fn word(item: &Rlp) -> Result<[u8; 32]> {
+    let bytes = item.as_bytes()?;
+    if bytes.len() > 32 {
+        return Err(anyhow!("Signature value longer than 32 bytes"));
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(bytes);
+    Ok(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::EthKeyPair;
+
+    /// Sign an EIP-1559 transaction for this chain
+    fn signed_dynamic_fee(keypair: &EthKeyPair, nonce: u64, to: &[u8], value: u64) -> Vec<u8> {
+        let (chain_id, nonce, tip, max_fee, gas, value) = (
+            uint_bytes(CHAIN_ID),
+            uint_bytes(nonce),
+            uint_bytes(2),
+            uint_bytes(5),
+            uint_bytes(21000),
+            uint_bytes(value),
+        );
+        let mut fields = vec![
+            Rlp::Bytes(&chain_id),
+            Rlp::Bytes(&nonce),
+            Rlp::Bytes(&tip),
+            Rlp::Bytes(&max_fee),
+            Rlp::Bytes(&gas),
+            Rlp::Bytes(to),
+            Rlp::Bytes(&value),
+            Rlp::Bytes(&[]),
+            Rlp::List(vec![]),
+        ];
+
+        let mut signing_payload = vec![0x02];
+        signing_payload.extend(Rlp::List(fields.clone()).encode());
+        let (signature, recovery_id) = keypair.sign_recoverable(&keccak256(&signing_payload));
+
+        let y_parity = uint_bytes(recovery_id as u64);
+        let r = signature[..32].to_vec();
+        let s = signature[32..].to_vec();
+        fields.extend([Rlp::Bytes(&y_parity), Rlp::Bytes(&r), Rlp::Bytes(&s)]);
+
+        let mut raw = vec![0x02];
+        raw.extend(Rlp::List(fields).encode());
+        raw
+    }
+
+    /// Sign an EIP-155 legacy transaction for this chain
+    fn signed_legacy(keypair: &EthKeyPair, nonce: u64, to: &[u8], value: u64) -> Vec<u8> {
+        let (nonce, gas_price, gas, value, chain_id) = (
+            uint_bytes(nonce),
+            uint_bytes(7),
+            uint_bytes(21000),
+            uint_bytes(value),
+            uint_bytes(CHAIN_ID),
+        );
+        let mut fields = vec![
+            Rlp::Bytes(&nonce),
+            Rlp::Bytes(&gas_price),
+            Rlp::Bytes(&gas),
+            Rlp::Bytes(to),
+            Rlp::Bytes(&value),
+            Rlp::Bytes(&[]),
+        ];
+
+        let mut unsigned = fields.clone();
+        unsigned.extend([Rlp::Bytes(&chain_id), Rlp::Bytes(&[]), Rlp::Bytes(&[])]);
+        let (signature, recovery_id) = keypair.sign_recoverable(&keccak256(&Rlp::List(unsigned).encode()));
+
+        let v = uint_bytes(CHAIN_ID * 2 + 35 + recovery_id as u64);
+        let r = signature[..32].to_vec();
+        let s = signature[32..].to_vec();
+        fields.extend([Rlp::Bytes(&v), Rlp::Bytes(&r), Rlp::Bytes(&s)]);
+        Rlp::List(fields).encode()
+    }
+
+    /// The same EIP-1559 transaction signed with `n - s` and the other y parity, which
+    /// recovers the same sender
+    fn with_high_s(raw: &[u8]) -> Vec<u8> {
+        const ORDER: [u8; 32] = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+            0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+        ];
+        let item = Rlp::decode(&raw[1..]).unwrap();
+        let mut fields = item.as_list().unwrap().to_vec();
+
+        let s = word(&fields[11]).unwrap();
+        let mut high_s = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let digit = ORDER[i] as i16 - s[i] as i16 - borrow;
+            high_s[i] = digit.rem_euclid(256) as u8;
+            borrow = (digit < 0) as i16;
+        }
+        let y_parity = uint_bytes(1 - fields[9].as_u64().unwrap());
+        fields[9] = Rlp::Bytes(&y_parity);
+        fields[11] = Rlp::Bytes(&high_s);
+
+        let mut raw = vec![0x02];
+        raw.extend(Rlp::List(fields).encode());
+        raw
+    }
+
+    #[test]
+    fn test_decode_eip155_legacy() {
+        // Example transaction from the EIP-155 specification (chain id 1)
+        let raw = hex::decode(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025\
+             a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb7\
+             03304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+        )
+        .unwrap();
+
+        let tx = EthTransaction::decode(&raw).unwrap();
+        assert_eq!(tx.kind, EthTxKind::Legacy);
+        assert_eq!(tx.chain_id, 1);
+        assert_eq!(tx.nonce, 9);
+        assert_eq!(tx.gas_price, 20_000_000_000);
+        assert_eq!(tx.gas_limit, 21000);
+        assert_eq!(tx.to.as_deref(), Some("0x3535353535353535353535353535353535353535"));
+        assert_eq!(tx.value, 1_000_000_000_000_000_000);
+        assert_eq!(tx.sender.0, "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+
+        // Valid signature, but for another chain
+        assert!(decode_raw_transaction(&raw).is_err());
+    }
+
+    #[test]
+    fn test_decode_dynamic_fee_transaction() {
+        let keypair = EthKeyPair::generate().unwrap();
+        let to = [0x35u8; 20];
+        let raw = signed_dynamic_fee(&keypair, 3, &to, 1000);
+
+        let eth = decode_raw_transaction(&raw).unwrap();
+        assert_eq!(eth.kind, EthTxKind::DynamicFee);
+        assert_eq!(eth.sender, keypair.address);
+        assert_eq!(eth.gas_price, 2);
+
+        let tx = eth.into_transaction();
+        assert_eq!(tx.from.0, keypair.address.0);
+        assert_eq!(tx.hash(), hex::encode(keccak256(&raw)));
+        assert!(tx.verify_signature());
+
+        // Fields that no longer match the signed envelope are rejected
+        let mut tampered = tx.clone();
+        tampered.nonce = 4;
+        assert!(!tampered.verify_signature());
+
+        // A corrupted envelope recovers some other sender
+        let mut corrupted = raw.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(EthTransaction::decode(&corrupted).map_or(true, |eth| eth.sender != keypair.address));
+    }
+
+    #[test]
+    fn test_decode_legacy_for_this_chain() {
+        let keypair = EthKeyPair::generate().unwrap();
+        let raw = signed_legacy(&keypair, 1, &[0x35u8; 20], 500);
+
+        let eth = decode_raw_transaction(&raw).unwrap();
+        assert_eq!(eth.kind, EthTxKind::Legacy);
+        assert_eq!(eth.chain_id, CHAIN_ID);
+        assert_eq!(eth.sender, keypair.address);
+        assert_eq!(eth.v(), CHAIN_ID * 2 + 35 + eth.recovery_id as u64);
+        assert_eq!((eth.nonce, eth.gas_price, eth.value), (1, 7, 500));
+        assert!(eth.into_transaction().verify_signature());
+    }
+
+    #[test]
+    fn test_reject_high_s_signature() {
+        let keypair = EthKeyPair::generate().unwrap();
+        let raw = signed_dynamic_fee(&keypair, 0, &[0x35u8; 20], 1000);
+        assert!(decode_raw_transaction(&raw).is_ok());
+
+        // Still a valid signature by the same key, but a malleated one
+        let malleated = with_high_s(&raw);
+        assert_ne!(keccak256(&malleated), keccak256(&raw));
+        assert!(EthTransaction::decode(&malleated).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// What a governance proposal asks for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ProposalType {
+    ParameterChange {
+        parameter: String,
+        old_value: String,
+        new_value: String,
+    },
+    TreasurySpend {
+        recipient: String,
+        amount: u64,
+        purpose: String,
+    },
+    ValidatorAction {
+        action: ValidatorActionType,
+        validator_address: String,
+        reason: String,
+    },
+    UpgradeProposal {
+        version: String,
+        activation_height: u64,
+        description: String,
+    },
+    TextProposal {
+        content: String,
+    },
+}
+
+/// Action a `ValidatorAction` proposal takes against a validator
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ValidatorActionType {
+    Remove,
+    Slash,
+    Pardon,
+}
+
+/// A voter's choice on a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum VoteOption {
+    Yes,
+    No,
+    Abstain,
+}
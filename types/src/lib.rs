@@ -1,5 +1,10 @@
 use serde::{Serialize, Deserialize};
 
+pub mod consensus;
+pub mod eth;
+pub mod governance;
+pub mod rlp;
+
 /// Multi-chain address support
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum Address {
@@ -68,6 +73,63 @@ pub enum TransactionType {
         data: Vec<u8>,
         gas_price: ActAmount,
     },
+    // Staking and governance actions, authorized by the sender's signature like any other transaction
+    Stake {
+        amount: ActAmount,
+        commission_rate: u8,
+    },
+    Unstake {
+        amount: ActAmount,
+    },
+    Delegate {
+        validator: String,
+        amount: ActAmount,
+    },
+    Undelegate {
+        validator: String,
+        amount: ActAmount,
+    },
+    ClaimUnstaked,
+    ClaimRewards,
+    Propose {
+        proposal_type: governance::ProposalType,
+        title: String,
+        description: String,
+    },
+    Vote {
+        proposal_id: u64,
+        option: governance::VoteOption,
+    },
+    // Proof that a validator double-signed; gets it slashed for DoubleSigning
+    ReportEvidence {
+        evidence: consensus::Evidence,
+    },
+    // Return the sender's validator to the active set after it was jailed for downtime
+    Unjail,
+}
+
+impl TransactionType {
+    /// Tokens moved out of the sender's balance besides the fee
+    pub fn value(&self) -> ActAmount {
+        match self {
+            TransactionType::Transfer { amount, .. } => *amount,
+            TransactionType::EthereumLegacy { value, .. } => *value,
+            TransactionType::Stake { amount, .. } => *amount,
+            TransactionType::Delegate { amount, .. } => *amount,
+            _ => 0,
+        }
+    }
+
+    /// Whether this updates staking or governance records rather than accounts and contracts
+    pub fn is_system(&self) -> bool {
+        !matches!(
+            self,
+            TransactionType::Transfer { .. }
+                | TransactionType::ContractDeploy { .. }
+                | TransactionType::ContractCall { .. }
+                | TransactionType::EthereumLegacy { .. }
+        )
+    }
 }
 
 /// ACT Chain transaction
@@ -85,10 +147,45 @@ pub struct Transaction {
 impl Transaction {
     pub fn hash(&self) -> String {
         use sha2::{Digest, Sha256};
+        if self.is_raw_ethereum() {
+            // Same hash Ethereum tooling computes for the raw transaction
+            return hex::encode(crypto::keccak256(&self.signature));
+        }
         let data = serde_json::to_vec(self).unwrap();
         let hash = Sha256::digest(&data);
         hex::encode(hash)
     }
+
+    /// Bytes covered by the sender's signature (everything except signature and pubkey)
+    pub fn signing_data(&self) -> Vec<u8> {
+        serde_json::to_vec(&(
+            &self.from,
+            &self.nonce,
+            &self.tx_type,
+            &self.gas_limit,
+            &self.gas_price,
+        ))
+        .unwrap()
+    }
+
+    /// Ethereum transaction submitted as raw bytes, which carries the signed envelope as its signature
+    fn is_raw_ethereum(&self) -> bool {
+        matches!(self.tx_type, TransactionType::EthereumLegacy { .. }) && !self.signature.is_empty()
+    }
+
+    /// Check the signature and that `pubkey` actually belongs to `from`
+    pub fn verify_signature(&self) -> bool {
+        if self.is_raw_ethereum() {
+            // The raw envelope must decode to exactly this transaction
+            return eth::decode_raw_transaction(&self.signature)
+                .map(|eth| eth.into_transaction().signing_data() == self.signing_data())
+                .unwrap_or(false);
+        }
+        if crypto::ActAddress::from_pubkey(&self.pubkey) != self.from {
+            return false;
+        }
+        crypto::verify_signature(&self.pubkey, &self.signing_data(), &self.signature).unwrap_or(false)
+    }
 }
 
 /// Legacy action type (will be replaced by Transaction)
@@ -103,14 +200,154 @@ pub struct Action {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BlockHeader {
     pub parent_hash: String,
-    pub action_hash: String,        // Will become tx_root
+    pub tx_root: String,            // Merkle root of block transactions
     pub actor_pubkey: String,       // Block proposer
     pub state_root: String,
     pub receipts_root: String,
     pub timestamp: u64,
-    pub validator_commitment: String,
-    pub reward: ActAmount,          // Reward in ACT
+    pub validator_commitment: String,  // Address of the proposer that signed the header
+    pub reward: ActAmount,          // Block reward minted, in balance units
     pub height: u64,
+    #[serde(default)]
+    pub round: u32,                 // Consensus round the block was built for
+    #[serde(default)]
+    pub proposer_pubkey: Vec<u8>,
+    #[serde(default)]
+    pub signature: Vec<u8>,         // Proposer's signature over the header hash
+    #[serde(default)]
+    pub last_commit_hash: String,   // Hash of the certificate the block carries for its parent
+}
+
+impl BlockHeader {
+    /// Block hash (SHA-256 of the serialized header, without the signature)
+    pub fn hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let unsigned = BlockHeader {
+            signature: Vec::new(),
+            ..self.clone()
+        };
+        let data = serde_json::to_vec(&unsigned).unwrap();
+        let hash = Sha256::digest(&data);
+        hex::encode(hash)
+    }
+
+    /// Bytes covered by the proposer's signature
+    pub fn signing_data(&self) -> Vec<u8> {
+        serde_json::to_vec(&("act-block", self.hash())).unwrap()
+    }
+
+    /// Sign as the proposer: `keypair` must be the key of `validator_commitment`
+    pub fn sign(&mut self, keypair: &crypto::ActKeyPair) {
+        self.proposer_pubkey = keypair.public_key();
+        self.signature = keypair.sign(&self.signing_data());
+    }
+
+    /// Check the signature and that `proposer_pubkey` belongs to `validator_commitment`
+    pub fn verify_signature(&self) -> bool {
+        if crypto::ActAddress::from_pubkey(&self.proposer_pubkey).to_string() != self.validator_commitment {
+            return false;
+        }
+        crypto::verify_signature(&self.proposer_pubkey, &self.signing_data(), &self.signature).unwrap_or(false)
+    }
+}
+
+/// Full block: header plus the transactions it commits to and their receipts
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub transactions: Vec<Transaction>,
+    pub receipts: Vec<TransactionReceipt>,
+    #[serde(default)]
+    pub last_commit: Option<consensus::CommitCertificate>,  // Precommits that finalized the parent
+}
+
+impl Block {
+    pub fn new(header: BlockHeader, transactions: Vec<Transaction>, receipts: Vec<TransactionReceipt>) -> Self {
+        Self {
+            header,
+            transactions,
+            receipts,
+            last_commit: None,
+        }
+    }
+
+    /// Carry the parent's commit certificate; the header must commit to it in `last_commit_hash`
+    pub fn with_last_commit(mut self, last_commit: Option<consensus::CommitCertificate>) -> Self {
+        self.last_commit = last_commit;
+        self
+    }
+
+    pub fn hash(&self) -> String {
+        self.header.hash()
+    }
+
+    pub fn height(&self) -> u64 {
+        self.header.height
+    }
+
+    /// Merkle root over the hashes of the block's transactions
+    pub fn calculate_tx_root(transactions: &[Transaction]) -> String {
+        let leaves: Vec<String> = transactions.iter().map(|tx| tx.hash()).collect();
+        merkle_root(&leaves)
+    }
+
+    /// Hash of the certificate a block carries for its parent; the zero hash when it carries none
+    pub fn calculate_last_commit_hash(last_commit: Option<&consensus::CommitCertificate>) -> String {
+        match last_commit {
+            Some(certificate) => certificate.hash(),
+            None => "0".repeat(64),
+        }
+    }
+
+    /// Merkle root over the hashes of the block's receipts
+    pub fn calculate_receipts_root(receipts: &[TransactionReceipt]) -> String {
+        let leaves: Vec<String> = receipts.iter().map(|r| r.hash()).collect();
+        merkle_root(&leaves)
+    }
+}
+
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+const MERKLE_INTERNAL_PREFIX: u8 = 0x01;
+
+/// Binary SHA-256 Merkle root over hex-encoded leaf hashes.
+/// Leaves and inner nodes are hashed under different prefixes, as in the state trie, so an
+/// inner node cannot pass for a leaf. An odd node at any level is carried up unchanged rather
+/// than paired with itself, so no two leaf lists share a root (CVE-2012-2459); an empty list
+/// yields the zero hash.
+pub fn merkle_root(leaves: &[String]) -> String {
+    use sha2::{Digest, Sha256};
+
+    if leaves.is_empty() {
+        return "0".repeat(64);
+    }
+
+    let mut level: Vec<Vec<u8>> = leaves
+        .iter()
+        .map(|leaf| {
+            let mut hasher = Sha256::new();
+            hasher.update([MERKLE_LEAF_PREFIX]);
+            hasher.update(hex::decode(leaf).unwrap_or_else(|_| leaf.as_bytes().to_vec()));
+            hasher.finalize().to_vec()
+        })
+        .collect();
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update([MERKLE_INTERNAL_PREFIX]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().to_vec()
+                }
+                _ => pair[0].clone(),
+            })
+            .collect();
+    }
+
+    hex::encode(&level[0])
 }
 
 /// Account state in ACT Chain
@@ -168,6 +405,12 @@ impl EventLog {
             log_index,
         }
     }
+    /// Whether this log was emitted by `address` (any address if `None`) and carries every
+    /// one of `topics`, in any position
+    pub fn matches(&self, address: Option<&str>, topics: &[String]) -> bool {
+        address.is_none_or(|address| self.address == address)
+            && topics.iter().all(|topic| self.topics.contains(topic))
+    }
 }
 
 /// Transaction receipt with execution details
@@ -183,3 +426,56 @@ pub struct TransactionReceipt {
     pub logs: Vec<EventLog>,          // Event logs emitted
     pub logs_bloom: Option<Vec<u8>>,  // Bloom filter for efficient log searching
 }
+
+impl TransactionReceipt {
+    pub fn hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let data = serde_json::to_vec(self).unwrap();
+        let hash = Sha256::digest(&data);
+        hex::encode(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    fn leaf(byte: u8) -> String {
+        hex::encode([byte; 32])
+    }
+
+    fn hashed(leaf: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update([MERKLE_LEAF_PREFIX]);
+        hasher.update(hex::decode(leaf).unwrap());
+        hex::encode(hasher.finalize())
+    }
+
+    fn node(left: &str, right: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update([MERKLE_INTERNAL_PREFIX]);
+        hasher.update(hex::decode(left).unwrap());
+        hasher.update(hex::decode(right).unwrap());
+        hex::encode(hasher.finalize())
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        assert_eq!(merkle_root(&[]), "0".repeat(64));
+        assert_eq!(merkle_root(&[leaf(1)]), hashed(&leaf(1)));
+        
+        // The odd leaf is carried up unchanged
+        let (a, b, c) = (leaf(1), leaf(2), leaf(3));
+        let root = merkle_root(&[a.clone(), b.clone(), c.clone()]);
+        assert_eq!(root, node(&node(&hashed(&a), &hashed(&b)), &hashed(&c)));
+        
+        // An inner node does not pass for a leaf
+        let inner = node(&hashed(&a), &hashed(&b));
+        assert_ne!(merkle_root(&[inner, c.clone()]), root);
+        
+        // Repeating the last leaf no longer yields the same root (CVE-2012-2459)
+        assert_ne!(merkle_root(&[a.clone(), b.clone(), c.clone(), c.clone()]), root);
+        assert_ne!(merkle_root(&[a, b]), merkle_root(&[leaf(1), leaf(2), leaf(2)]));
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+
+/// A decoded RLP item, borrowing byte strings from the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rlp<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<Rlp<'a>>),
+}
+
+impl<'a> Rlp<'a> {
+    /// Decode exactly one item spanning all of `data`
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let (item, rest) = Self::decode_prefix(data)?;
+        if !rest.is_empty() {
+            return Err(anyhow!("{} trailing bytes after RLP item", rest.len()));
+        }
+        Ok(item)
+    }
+
+    /// Decode one item from the front of `data`, returning it and the remaining bytes
+    fn decode_prefix(data: &'a [u8]) -> Result<(Self, &'a [u8])> {
+        let (&prefix, rest) = data.split_first().ok_or_else(|| anyhow!("Unexpected end of RLP input"))?;
+
+        match prefix {
+            0x00..=0x7f => Ok((Rlp::Bytes(&data[..1]), rest)),
+            0x80..=0xb7 => {
+                let len = (prefix - 0x80) as usize;
+                let (bytes, rest) = split(rest, len)?;
+                if len == 1 && bytes[0] < 0x80 {
+                    return Err(anyhow!("Non-canonical RLP single byte"));
+                }
+                Ok((Rlp::Bytes(bytes), rest))
+            }
+            0xb8..=0xbf => {
+                let (len, rest) = long_length(rest, (prefix - 0xb7) as usize)?;
+                let (bytes, rest) = split(rest, len)?;
+                Ok((Rlp::Bytes(bytes), rest))
+            }
+            0xc0..=0xf7 => {
+                let (payload, rest) = split(rest, (prefix - 0xc0) as usize)?;
+                Ok((Rlp::List(decode_list(payload)?), rest))
+            }
+            0xf8..=0xff => {
+                let (len, rest) = long_length(rest, (prefix - 0xf7) as usize)?;
+                let (payload, rest) = split(rest, len)?;
+                Ok((Rlp::List(decode_list(payload)?), rest))
+            }
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<&'a [u8]> {
+        match self {
+            Rlp::Bytes(bytes) => Ok(bytes),
+            Rlp::List(_) => Err(anyhow!("Expected RLP string, found list")),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Rlp<'a>]> {
+        match self {
+            Rlp::List(items) => Ok(items),
+            Rlp::Bytes(_) => Err(anyhow!("Expected RLP list, found string")),
+        }
+    }
+
+    /// Big-endian unsigned integer without leading zeros
+    pub fn as_uint(&self) -> Result<u128> {
+        let bytes = self.as_bytes()?;
+        if bytes.first() == Some(&0) {
+            return Err(anyhow!("RLP integer has leading zeros"));
+        }
+        if bytes.len() > 16 {
+            return Err(anyhow!("RLP integer does not fit in 128 bits"));
+        }
+        Ok(bytes.iter().fold(0u128, |acc, &b| (acc << 8) | b as u128))
+    }
+
+    pub fn as_u64(&self) -> Result<u64> {
+        u64::try_from(self.as_uint()?).map_err(|_| anyhow!("RLP integer does not fit in 64 bits"))
+    }
+
+    /// Re-encode this item
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            Rlp::Bytes(bytes) if bytes.len() == 1 && bytes[0] < 0x80 => out.push(bytes[0]),
+            Rlp::Bytes(bytes) => {
+                encode_header(out, 0x80, bytes.len());
+                out.extend_from_slice(bytes);
+            }
+            Rlp::List(items) => {
+                let mut payload = Vec::new();
+                for item in items {
+                    item.encode_into(&mut payload);
+                }
+                encode_header(out, 0xc0, payload.len());
+                out.extend_from_slice(&payload);
+            }
+        }
+    }
+}
+
+/// Minimal big-endian bytes of an integer, as RLP encodes it
+pub fn uint_bytes(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+fn split(data: &[u8], len: usize) -> Result<(&[u8], &[u8])> {
+    if data.len() < len {
+        return Err(anyhow!("RLP item of {} bytes overruns input of {}", len, data.len()));
+    }
+    Ok(data.split_at(len))
+}
+
+fn long_length(data: &[u8], len_of_len: usize) -> Result<(usize, &[u8])> {
+    let (len_bytes, rest) = split(data, len_of_len)?;
+    if len_bytes[0] == 0 || len_of_len > std::mem::size_of::<usize>() {
+        return Err(anyhow!("Non-canonical RLP length"));
+    }
+    let len = len_bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+    if len < 56 {
+        return Err(anyhow!("Non-canonical RLP length"));
+    }
+    Ok((len, rest))
+}
+
+fn decode_list(mut payload: &[u8]) -> Result<Vec<Rlp<'_>>> {
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (item, rest) = Rlp::decode_prefix(payload)?;
+        items.push(item);
+        payload = rest;
+    }
+    Ok(items)
+}
+
+fn encode_header(out: &mut Vec<u8>, offset: u8, len: usize) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let len_bytes = uint_bytes(len as u64);
+        out.push(offset + 55 + len_bytes.len() as u8);
+        out.extend_from_slice(&len_bytes);
+    }
+}
@@ -74,17 +74,8 @@ impl ActWallet {
             .as_ref()
             .ok_or_else(|| anyhow!("Cannot sign with watch-only wallet"))?;
         
-        // Serialize transaction data for signing (without signature)
-        let tx_data = serde_json::to_vec(&(
-            &tx.from,
-            &tx.nonce,
-            &tx.tx_type,
-            &tx.gas_limit,
-            &tx.gas_price,
-        ))?;
-        
-        // Sign the transaction
-        let signature = keypair.sign(&tx_data);
+        // Sign the transaction data (without signature)
+        let signature = keypair.sign(&tx.signing_data());
         tx.signature = signature;
         tx.pubkey = keypair.public_key();
         
@@ -116,6 +107,27 @@ impl ActWallet {
         self.sign_transaction(tx)
     }
     
+    /// Create a transaction of any type, e.g. a staking or governance action
+    pub fn create_transaction(
+        &self,
+        tx_type: TransactionType,
+        nonce: u64,
+        gas_limit: u64,
+        gas_price: ActAmount,
+    ) -> Result<Transaction> {
+        let tx = Transaction {
+            from: self.address.clone(),
+            nonce,
+            tx_type,
+            gas_limit,
+            gas_price,
+            signature: vec![],
+            pubkey: vec![],
+        };
+        
+        self.sign_transaction(tx)
+    }
+    
     /// Create a contract deployment transaction
     pub fn create_contract_deploy(
         &self,